@@ -1,9 +1,22 @@
 use countdown_bot3::countdown_bot::bot;
 use std::alloc::System;
+use std::time::Duration;
 #[global_allocator]
 static ALLOCATOR: System = System;
-#[tokio::main(flavor = "multi_thread")]
-async fn main() {
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create tokio runtime.");
+    let restart = runtime.block_on(async_main());
+    // 控制台读取任务会一直阻塞在stdin上，不等待它结束
+    runtime.shutdown_timeout(Duration::from_secs(1));
+    if restart {
+        println!("Restarting..");
+        bot::restart_process().expect("Failed to restart the bot.");
+    }
+}
+async fn async_main() -> bool {
     let cwd = std::env::current_dir().expect("Cannot get current working dir!");
     println!("Working dir: {}", &cwd.display());
     let mut bot = bot::CountdownBot::new(&cwd);
@@ -32,7 +45,10 @@ async fn main() {
     bot.add_plugin_static_register_hook(zxhdmx::plugin_register);
     bot.add_plugin_static_register_hook(message_sender::plugin_register);
     bot.add_plugin_static_register_hook(genshin_saying::plugin_register);
+    bot.add_plugin_static_register_hook(group_admin::plugin_register);
+    bot.add_plugin_static_register_hook(moderation::plugin_register);
 
     bot.init().await.expect("Failed to initialize bot.");
     bot.run().await.unwrap();
+    bot.is_restart_requested()
 }
@@ -0,0 +1,347 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use hmac::{Hmac, Mac};
+use log::{error, info};
+use salvo::{prelude::FlowCtrl, Depot, Handler, Request, Response};
+use sha2::Sha256;
+use tokio::sync::Mutex;
+
+use super::{client::ResultType, config::ArtifactProps, utils::SubUrlWrapper};
+
+pub const ARTIFACT_ROUTE: &str = "bot/artifact";
+
+#[derive(Debug, Clone)]
+pub struct ArtifactEntry {
+    pub plugin_name: String,
+    pub mime_type: String,
+    // 下载时使用的文件名，为None时在浏览器中直接打开
+    pub file_name: Option<String>,
+    pub size: u64,
+    // 过期时间戳(秒)
+    pub expire_at: i64,
+}
+
+struct ArtifactStoreInner {
+    dir: PathBuf,
+    props: ArtifactProps,
+    secret: Vec<u8>,
+    url_wrapper: SubUrlWrapper,
+    entries: Mutex<HashMap<String, ArtifactEntry>>,
+}
+
+/*
+由插件存储的临时文件，通过Web服务器以带签名的URL提供下载
+文件以uuid命名保存在存储目录下，索引仅保存在内存中，因此启动时会删除存储目录中以uuid命名的文件
+存储目录中的其他文件不会被删除
+*/
+#[derive(Clone)]
+pub struct ArtifactStore {
+    inner: Arc<ArtifactStoreInner>,
+}
+
+impl ArtifactStore {
+    pub fn open(
+        dir: PathBuf,
+        props: ArtifactProps,
+        url_wrapper: SubUrlWrapper,
+    ) -> ResultType<Self> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow!("创建文件存储目录 {} 失败: {}", dir.display(), e))?;
+        for entry in std::fs::read_dir(&dir)
+            .map_err(|e| anyhow!("读取文件存储目录 {} 失败: {}", dir.display(), e))?
+        {
+            let entry = entry?;
+            let created_by_store = entry
+                .file_name()
+                .to_str()
+                .map(|v| uuid::Uuid::parse_str(v).is_ok())
+                .unwrap_or(false);
+            if created_by_store && entry.file_type()?.is_file() {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        let secret = if props.secret.is_empty() {
+            uuid::Uuid::new_v4().as_bytes().to_vec()
+        } else {
+            props.secret.as_bytes().to_vec()
+        };
+        Ok(Self {
+            inner: Arc::new(ArtifactStoreInner {
+                dir,
+                props,
+                secret,
+                url_wrapper,
+                entries: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+    /*
+    存储文件并返回带签名的下载地址
+    ttl为None时使用默认有效期，超出最长有效期时按最长有效期处理
+    */
+    pub async fn put(
+        &self,
+        plugin_name: &str,
+        data: &[u8],
+        mime_type: &str,
+        file_name: Option<&str>,
+        ttl: Option<Duration>,
+    ) -> ResultType<String> {
+        let props = &self.inner.props;
+        let size = data.len() as u64;
+        if size > props.max_file_size {
+            return Err(anyhow!(
+                "文件大小 {} 字节超出了上限 {} 字节",
+                size,
+                props.max_file_size
+            )
+            .into());
+        }
+        let ttl = ttl
+            .map(|v| v.as_secs())
+            .unwrap_or(props.default_ttl)
+            .min(props.max_ttl)
+            .max(1);
+        let mut entries = self.inner.entries.lock().await;
+        self.remove_expired(&mut entries).await;
+        let total_used: u64 = entries.values().map(|v| v.size).sum();
+        let plugin_used: u64 = entries
+            .values()
+            .filter(|v| v.plugin_name == plugin_name)
+            .map(|v| v.size)
+            .sum();
+        if plugin_used + size > props.plugin_quota {
+            return Err(anyhow!(
+                "插件 {} 的文件存储配额不足: 已使用 {} 字节，配额 {} 字节",
+                plugin_name,
+                plugin_used,
+                props.plugin_quota
+            )
+            .into());
+        }
+        if total_used + size > props.total_quota {
+            return Err(anyhow!("文件存储空间不足，请稍后再试").into());
+        }
+        let id = uuid::Uuid::new_v4().to_simple().to_string();
+        tokio::fs::write(self.inner.dir.join(&id), data)
+            .await
+            .map_err(|e| anyhow!("写入文件失败: {}", e))?;
+        let expire_at = chrono::Local::now().timestamp() + ttl as i64;
+        entries.insert(
+            id.clone(),
+            ArtifactEntry {
+                plugin_name: plugin_name.to_string(),
+                mime_type: mime_type.to_string(),
+                file_name: file_name.map(|v| v.to_string()),
+                size,
+                expire_at,
+            },
+        );
+        Ok(self.inner.url_wrapper.get_sub_url(&format!(
+            "{}/{}?expires={}&sign={}",
+            ARTIFACT_ROUTE,
+            id,
+            expire_at,
+            self.sign(&id, expire_at)
+        )))
+    }
+    // 返回未过期的文件信息与路径
+    pub async fn get(&self, id: &str) -> Option<(ArtifactEntry, PathBuf)> {
+        let entries = self.inner.entries.lock().await;
+        entries
+            .get(id)
+            .filter(|v| v.expire_at > chrono::Local::now().timestamp())
+            .map(|v| (v.clone(), self.inner.dir.join(id)))
+    }
+    pub async fn remove(&self, id: &str) {
+        if self.inner.entries.lock().await.remove(id).is_some() {
+            tokio::fs::remove_file(self.inner.dir.join(id)).await.ok();
+        }
+    }
+    // 清理过期文件，返回清理的数量
+    pub async fn cleanup(&self) -> usize {
+        let mut entries = self.inner.entries.lock().await;
+        self.remove_expired(&mut entries).await
+    }
+    // 各插件当前占用的字节数
+    pub async fn usage(&self) -> HashMap<String, u64> {
+        let mut result = HashMap::new();
+        for entry in self.inner.entries.lock().await.values() {
+            *result.entry(entry.plugin_name.clone()).or_insert(0) += entry.size;
+        }
+        result
+    }
+    pub fn cleanup_interval(&self) -> Duration {
+        Duration::from_secs(self.inner.props.cleanup_interval.max(1))
+    }
+    async fn remove_expired(&self, entries: &mut HashMap<String, ArtifactEntry>) -> usize {
+        let now = chrono::Local::now().timestamp();
+        let expired = entries
+            .iter()
+            .filter(|(_, v)| v.expire_at <= now)
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<String>>();
+        for id in expired.iter() {
+            entries.remove(id);
+            if let Err(e) = tokio::fs::remove_file(self.inner.dir.join(id)).await {
+                error!("Failed to remove expired artifact {}: {}", id, e);
+            }
+        }
+        expired.len()
+    }
+    fn make_mac(&self, id: &str, expire_at: i64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.inner.secret)
+            .expect("HMAC can take key of any size");
+        mac.update(format!("{}:{}", id, expire_at).as_bytes());
+        mac
+    }
+    fn sign(&self, id: &str, expire_at: i64) -> String {
+        hex::encode(self.make_mac(id, expire_at).finalize().into_bytes())
+    }
+    pub fn verify(&self, id: &str, expire_at: i64, sign: &str) -> bool {
+        match hex::decode(sign) {
+            Ok(v) => self.make_mac(id, expire_at).verify_slice(&v).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+// 定期清理过期文件，直到收到停止信号
+pub(crate) async fn run_cleanup_loop(
+    store: ArtifactStore,
+    mut stop_rx: tokio::sync::watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(store.cleanup_interval());
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let count = store.cleanup().await;
+                if count > 0 {
+                    info!("Removed {} expired artifacts", count);
+                }
+            }
+            _ = stop_rx.changed() => {
+                if *stop_rx.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/*
+解析Range请求头，仅支持单个区间
+返回闭区间[start, end]，无法满足时返回None
+*/
+pub fn parse_range(header: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || len == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    if start.is_empty() {
+        // bytes=-n 表示最后n个字节
+        let suffix = end.parse::<u64>().ok().filter(|v| *v > 0)?;
+        return Some((len.saturating_sub(suffix), len - 1));
+    }
+    let start = start.parse::<u64>().ok()?;
+    let end = if end.is_empty() {
+        len - 1
+    } else {
+        end.parse::<u64>().ok()?.min(len - 1)
+    };
+    if start > end {
+        return None;
+    }
+    Some((start, end))
+}
+
+// GET /bot/artifact/<id>?expires=<时间戳>&sign=<签名>
+pub struct ArtifactHandler {
+    pub(crate) store: ArtifactStore,
+}
+#[async_trait::async_trait]
+impl Handler for ArtifactHandler {
+    async fn handle(
+        &self,
+        req: &mut Request,
+        _depot: &mut Depot,
+        res: &mut Response,
+        _ctrl: &mut FlowCtrl,
+    ) {
+        use salvo::http::header::{self, HeaderValue};
+        use salvo::prelude::StatusCode;
+        let (id, expires, sign) = match (
+            req.get_param::<String>("id"),
+            req.get_query::<i64>("expires"),
+            req.get_query::<String>("sign"),
+        ) {
+            (Some(a), Some(b), Some(c)) => (a, b, c),
+            _ => {
+                res.set_status_code(StatusCode::BAD_REQUEST);
+                res.render_plain_text("Param required");
+                return;
+            }
+        };
+        if !self.store.verify(&id, expires, &sign) {
+            res.set_status_code(StatusCode::FORBIDDEN);
+            res.render_plain_text("签名无效");
+            return;
+        }
+        let (entry, path) = match self.store.get(&id).await {
+            Some(v) if expires > chrono::Local::now().timestamp() => v,
+            _ => {
+                res.set_status_code(StatusCode::NOT_FOUND);
+                res.render_plain_text("文件不存在或已过期");
+                return;
+            }
+        };
+        let data = match tokio::fs::read(&path).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to read artifact {}: {}", id, e);
+                res.set_status_code(StatusCode::INTERNAL_SERVER_ERROR);
+                res.render_plain_text("读取文件失败");
+                return;
+            }
+        };
+        let len = data.len() as u64;
+        let content_type = HeaderValue::from_str(&entry.mime_type)
+            .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+        let headers = res.headers_mut();
+        headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        if let Some(name) = &entry.file_name {
+            if let Ok(v) = HeaderValue::from_str(&format!(
+                "attachment; filename*=UTF-8''{}",
+                urlencoding::encode(name)
+            )) {
+                headers.insert(header::CONTENT_DISPOSITION, v);
+            }
+        }
+        let range = req
+            .headers()
+            .get(header::RANGE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| parse_range(v, len));
+        match range {
+            None => res.render_binary(content_type, &data[..]),
+            Some(Some((start, end))) => {
+                res.headers_mut().insert(
+                    header::CONTENT_RANGE,
+                    HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, len)).unwrap(),
+                );
+                res.render_binary(content_type, &data[start as usize..=end as usize]);
+                res.set_status_code(StatusCode::PARTIAL_CONTENT);
+            }
+            Some(None) => {
+                res.headers_mut().insert(
+                    header::CONTENT_RANGE,
+                    HeaderValue::from_str(&format!("bytes */{}", len)).unwrap(),
+                );
+                res.set_status_code(StatusCode::RANGE_NOT_SATISFIABLE);
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::anyhow;
+use log::error;
+use rusqlite::{params, Connection};
+use salvo::{prelude::FlowCtrl, Depot, Handler, Request, Response};
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use super::client::ResultType;
+
+#[derive(Debug, Clone)]
+pub struct CommandRecord {
+    pub time: i64,
+    // SenderType::generate_identifier
+    pub sender: String,
+    // SenderType::generate_context
+    pub context: String,
+    pub plugin_name: String,
+    pub command: String,
+    pub args: String,
+    pub duration_ms: i64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageEntry {
+    pub key: String,
+    pub count: i64,
+}
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginUsage {
+    pub plugin_name: String,
+    pub calls: i64,
+    pub failures: i64,
+    pub avg_duration_ms: f64,
+}
+#[derive(Clone)]
+pub struct CommandAuditLog {
+    database: Arc<Mutex<Connection>>,
+}
+
+impl CommandAuditLog {
+    pub fn open<P: AsRef<Path>>(path: P) -> ResultType<Self> {
+        let conn = Connection::open(path).map_err(|e| anyhow!("打开指令日志数据库失败: {}", e))?;
+        conn.execute(
+            r#"CREATE TABLE IF NOT EXISTS COMMAND_LOG(
+                TIME        INTEGER NOT NULL,
+                SENDER      TEXT    NOT NULL,
+                CONTEXT     TEXT    NOT NULL,
+                PLUGIN_NAME TEXT    NOT NULL,
+                COMMAND     TEXT    NOT NULL,
+                ARGS        TEXT    NOT NULL,
+                DURATION    INTEGER NOT NULL,
+                SUCCESS     INTEGER NOT NULL,
+                ERROR       TEXT
+            )"#,
+            params![],
+        )
+        .map_err(|e| anyhow!("创建表 COMMAND_LOG 时发生错误: {}", e))?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS COMMAND_LOG_TIME_INDEX ON COMMAND_LOG(TIME)",
+            params![],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS COMMAND_LOG_PLUGIN_INDEX ON COMMAND_LOG(PLUGIN_NAME)",
+            params![],
+        )?;
+        Ok(Self {
+            database: Arc::new(Mutex::new(conn)),
+        })
+    }
+    pub async fn record(&self, record: &CommandRecord) -> ResultType<()> {
+        let db = self.database.lock().await;
+        db.execute(
+            "INSERT INTO COMMAND_LOG (TIME,SENDER,CONTEXT,PLUGIN_NAME,COMMAND,ARGS,DURATION,SUCCESS,ERROR) \
+            VALUES (?,?,?,?,?,?,?,?,?)",
+            params![
+                record.time,
+                record.sender,
+                record.context,
+                record.plugin_name,
+                record.command,
+                record.args,
+                record.duration_ms,
+                record.success,
+                record.error
+            ],
+        )?;
+        Ok(())
+    }
+    // 关闭前将WAL中的数据写回数据库文件
+    pub async fn flush(&self) -> ResultType<()> {
+        let db = self.database.lock().await;
+        db.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+        Ok(())
+    }
+    // 写入失败不应影响指令本身，仅记录日志
+    pub async fn record_or_log(&self, record: CommandRecord) {
+        if let Err(e) = self.record(&record).await {
+            error!("Failed to save command record {:?}:\n{}", record, e);
+        }
+    }
+    async fn count_grouped_by(
+        &self,
+        column: &str,
+        extra_condition: &str,
+        since: i64,
+        limit: i64,
+    ) -> ResultType<Vec<UsageEntry>> {
+        let db = self.database.lock().await;
+        let mut stmt = db.prepare(&format!(
+            "SELECT {0}, COUNT(*) AS CNT FROM COMMAND_LOG WHERE TIME >= ? {1} \
+            GROUP BY {0} ORDER BY CNT DESC LIMIT ?",
+            column, extra_condition
+        ))?;
+        let rows = stmt
+            .query_map(params![since, limit], |r| {
+                Ok(UsageEntry {
+                    key: r.get(0)?,
+                    count: r.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<UsageEntry>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+    pub async fn top_commands(&self, since: i64, limit: i64) -> ResultType<Vec<UsageEntry>> {
+        self.count_grouped_by("COMMAND", "", since, limit).await
+    }
+    pub async fn top_users(&self, since: i64, limit: i64) -> ResultType<Vec<UsageEntry>> {
+        self.count_grouped_by("SENDER", "AND SENDER != 'console'", since, limit)
+            .await
+    }
+    pub async fn top_groups(&self, since: i64, limit: i64) -> ResultType<Vec<UsageEntry>> {
+        self.count_grouped_by("CONTEXT", "AND CONTEXT LIKE 'group:%'", since, limit)
+            .await
+    }
+    pub async fn plugin_usage(&self, since: i64) -> ResultType<Vec<PluginUsage>> {
+        let db = self.database.lock().await;
+        let mut stmt = db.prepare(
+            "SELECT PLUGIN_NAME, COUNT(*) AS CNT, SUM(1 - SUCCESS), AVG(DURATION) \
+            FROM COMMAND_LOG WHERE TIME >= ? GROUP BY PLUGIN_NAME ORDER BY CNT DESC",
+        )?;
+        let rows = stmt
+            .query_map(params![since], |r| {
+                Ok(PluginUsage {
+                    plugin_name: r.get(0)?,
+                    calls: r.get(1)?,
+                    failures: r.get(2)?,
+                    avg_duration_ms: r.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<PluginUsage>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+    pub async fn single_plugin_usage(
+        &self,
+        plugin_name: &str,
+        since: i64,
+    ) -> ResultType<Option<PluginUsage>> {
+        Ok(self
+            .plugin_usage(since)
+            .await?
+            .into_iter()
+            .find(|v| v.plugin_name == plugin_name))
+    }
+}
+
+// 供Web服务器使用，GET /bot/command_stats?token=<令牌>&days=N 返回各插件的使用统计
+pub struct CommandStatsHandler {
+    pub(crate) audit: CommandAuditLog,
+    pub(crate) default_days: i64,
+    pub(crate) token: String,
+}
+#[async_trait::async_trait]
+impl Handler for CommandStatsHandler {
+    async fn handle(
+        &self,
+        req: &mut Request,
+        _depot: &mut Depot,
+        res: &mut Response,
+        _ctrl: &mut FlowCtrl,
+    ) {
+        if req.get_query::<String>("token").as_deref() != Some(self.token.as_str()) {
+            res.set_status_code(salvo::prelude::StatusCode::FORBIDDEN);
+            res.render_json(&json!({
+                "code": -1,
+                "message": "令牌无效"
+            }));
+            return;
+        }
+        let days = req
+            .get_query::<i64>("days")
+            .filter(|v| *v > 0)
+            .unwrap_or(self.default_days);
+        let since = chrono::Local::now().timestamp() - days * 24 * 60 * 60;
+        match self.audit.plugin_usage(since).await {
+            Ok(v) => res.render_json(&json!({
+                "code": 0,
+                "days": days,
+                "data": v
+            })),
+            Err(e) => res.render_json(&json!({
+                "code": -1,
+                "message": format!("{}", e)
+            })),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use log::info;
 
 use crate::countdown_bot::{
@@ -63,7 +64,11 @@ https://github.com/Officeyutong/Countdown-Bot3"#,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let state_str = self
             .state_manager
-            .create_state(&self.plugin_manager)
+            .create_state(
+                &self.plugin_manager,
+                self.command_audit.as_ref(),
+                self.config.command_audit.stats_default_days,
+            )
             .await?;
         self.create_client()
             .quick_send_by_sender(&sender, &state_str)
@@ -71,6 +76,59 @@ https://github.com/Officeyutong/Countdown-Bot3"#,
             .ok();
         Ok(())
     }
+    pub async fn on_command_stats(
+        &mut self,
+        args: &Vec<String>,
+        sender: &SenderType,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let audit = self
+            .command_audit
+            .as_ref()
+            .ok_or(anyhow!("指令日志未启用!"))?;
+        let cfg = &self.config.command_audit;
+        let allowed = match sender {
+            SenderType::Console(_) => true,
+            SenderType::Private(evt) => cfg.stats_users.contains(&evt.user_id),
+            _ => false,
+        };
+        if !allowed {
+            return Err(anyhow!("你没有权限查看指令统计!").into());
+        }
+        let days = match args.get(0) {
+            Some(s) => i64::from_str_radix(s, 10).map_err(|_| anyhow!("请输入合法的天数!"))?,
+            None => cfg.stats_default_days,
+        };
+        if days <= 0 {
+            return Err(anyhow!("请输入合法的天数!").into());
+        }
+        let since = chrono::Local::now().timestamp() - days * 24 * 60 * 60;
+        let limit = cfg.stats_top_count;
+        let mut buf = format!("最近{}天的指令使用统计:\n", days);
+        buf.push_str("热门指令:\n");
+        for item in audit.top_commands(since, limit).await?.iter() {
+            buf.push_str(&format!("{}: {}次\n", item.key, item.count));
+        }
+        buf.push_str("活跃用户:\n");
+        for item in audit.top_users(since, limit).await?.iter() {
+            buf.push_str(&format!("{}: {}次\n", item.key, item.count));
+        }
+        buf.push_str("群聊使用:\n");
+        for item in audit.top_groups(since, limit).await?.iter() {
+            buf.push_str(&format!("{}: {}次\n", item.key, item.count));
+        }
+        buf.push_str("插件统计:\n");
+        for item in audit.plugin_usage(since).await?.iter() {
+            buf.push_str(&format!(
+                "{}: {}次, 失败{}次, 平均耗时{:.0}ms\n",
+                item.plugin_name, item.calls, item.failures, item.avg_duration_ms
+            ));
+        }
+        self.create_client()
+            .quick_send_by_sender(&sender, buf.trim_end())
+            .await
+            .ok();
+        Ok(())
+    }
     pub async fn on_command_server_status(
         &mut self,
         _sender: &SenderType,
@@ -94,6 +152,15 @@ https://github.com/Officeyutong/Countdown-Bot3"#,
         self.stop_signal_sender.as_ref().unwrap().send(true).ok();
         Ok(())
     }
+    pub async fn on_command_restart(
+        &mut self,
+        _sender: &SenderType,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        info!("Restarting after shutdown..");
+        self.restart_requested = true;
+        self.stop_signal_sender.as_ref().unwrap().send(true).ok();
+        Ok(())
+    }
     pub async fn on_command_help(
         &mut self,
         sender: &SenderType,
@@ -127,17 +194,19 @@ https://github.com/Officeyutong/Countdown-Bot3"#,
     pub async fn on_command(
         &mut self,
         command: String,
-        _args: Vec<String>,
+        args: Vec<String>,
         sender: SenderType,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match command.as_str() {
             "help" => self.on_command_help(&sender).await,
             "stop" => self.on_command_stop(&sender).await,
+            "restart" => self.on_command_restart(&sender).await,
             "server_status" => self.on_command_server_status(&sender).await,
             "server_version" => self.on_command_server_version(&sender).await,
             "status" => self.on_command_status(&sender).await,
             "about" => self.on_command_about(&sender).await,
             "plugins" => self.on_command_plugins(&sender).await,
+            "stats" => self.on_command_stats(&args, &sender).await,
             _ => {
                 panic!("?")
             }
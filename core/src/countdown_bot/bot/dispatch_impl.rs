@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use crate::countdown_bot::{
-    command::{Command, CommandSender, SenderType},
+    audit::CommandRecord,
+    command::{strip_reply_prefix, Command, CommandSender, SenderType},
     event::{message::MessageEvent, Event, EventContainer, OOPEventContainer},
 };
 use anyhow::anyhow;
@@ -11,11 +12,25 @@ use tokio::sync::RwLock;
 use super::CountdownBot;
 
 impl CountdownBot {
+    // 开启command_in_reply时，回复消息中的指令也会被识别
+    fn command_line(&self, raw_message: &str) -> String {
+        if self.config.command_in_reply {
+            strip_reply_prefix(raw_message).to_string()
+        } else {
+            raw_message.to_string()
+        }
+    }
     pub async fn dispatch_event(&mut self, event: EventContainer) {
         if let Event::Message(ref msg_evt) = event.event {
             let (msg_line, sender) = match msg_evt {
-                MessageEvent::Private(e) => (e.raw_message.clone(), SenderType::Private(e.clone())),
-                MessageEvent::Group(e) => (e.raw_message.clone(), SenderType::Group(e.clone())),
+                MessageEvent::Private(e) => (
+                    self.command_line(&e.raw_message),
+                    SenderType::Private(e.clone()),
+                ),
+                MessageEvent::Group(e) => (
+                    self.command_line(&e.raw_message),
+                    SenderType::Group(e.clone()),
+                ),
                 MessageEvent::Guild(e) => (e.message.to_string(), SenderType::Guild(e.clone())),
                 MessageEvent::Unknown => return,
             };
@@ -45,7 +60,7 @@ impl CountdownBot {
             post_type: event.post_type,
         };
         self.event_manager
-            .dispatch_event(Arc::new(RwLock::new(oop_event)))
+            .dispatch_event(Arc::new(RwLock::new(oop_event)), &self.inflight)
             .await;
         // for (_, val) in self.plugin_manager.plugins.iter() {
         //     let plugin_instance_ref = val.read().await.plugin_instance.clone();
@@ -96,8 +111,8 @@ impl CountdownBot {
         };
         let mut cmd_line = match &parsed_sender {
             SenderType::Console(evt) => evt.line.clone(),
-            SenderType::Private(evt) => evt.raw_message.clone(),
-            SenderType::Group(evt) => evt.raw_message.clone(),
+            SenderType::Private(evt) => self.command_line(&evt.raw_message),
+            SenderType::Group(evt) => self.command_line(&evt.raw_message),
             SenderType::Guild(evt) => evt.message.to_string(),
         };
         for prefix in self.config.command_prefix.iter() {
@@ -152,9 +167,22 @@ impl CountdownBot {
                                 .map(|x| String::from(*x))
                                 .collect::<Vec<String>>();
                             args.remove(0);
+                            let begin = std::time::Instant::now();
+                            let args_str = args.join(" ");
                             let call_result = self
                                 .on_command(cmd.command_name.clone(), args, parsed_sender.clone())
                                 .await;
+                            if let Some(audit) = self.command_audit.as_ref() {
+                                audit
+                                    .record_or_log(make_command_record(
+                                        &parsed_sender,
+                                        &cmd,
+                                        args_str,
+                                        begin,
+                                        call_result.as_ref().err().map(|e| e.to_string()),
+                                    ))
+                                    .await;
+                            }
                             if let Err(e) = call_result {
                                 self.create_client()
                                     .quick_send_by_sender_ex(
@@ -185,10 +213,15 @@ impl CountdownBot {
                             let sender_cloned = parsed_sender.clone();
                             let client_cloned = self.create_client();
                             let cmd_cloned = cmd.clone();
+                            let audit_cloned = self.command_audit.clone();
+                            let inflight_guard = self.inflight.enter();
                             // let should_use_command_handler = plugin_wrapper_guard.use_command_handler;
                             tokio::spawn(async move {
+                                let _inflight_guard = inflight_guard;
                                 let local_sender = sender_cloned;
                                 let local_cmd = cmd_cloned;
+                                let begin = std::time::Instant::now();
+                                let args_str = args.join(" ");
                                 let call_ret = (if let Some(handler) = &local_cmd.command_handler {
                                     trace!("Handling command through handler..");
                                     handler
@@ -206,6 +239,17 @@ impl CountdownBot {
                                 })
                                 .map_err(|e| anyhow!(format!("{}", e)));
                                 trace!("Command process done.");
+                                if let Some(audit) = audit_cloned {
+                                    audit
+                                        .record_or_log(make_command_record(
+                                            &local_sender,
+                                            &local_cmd,
+                                            args_str,
+                                            begin,
+                                            call_ret.as_ref().err().map(|e| e.to_string()),
+                                        ))
+                                        .await;
+                                }
                                 if let Err(e) = call_ret {
                                     // let err2 = anyhow!(format!("{}", e));
                                     error!("{:#?}", e);
@@ -265,3 +309,23 @@ impl CountdownBot {
         }
     }
 }
+
+fn make_command_record(
+    sender: &SenderType,
+    cmd: &Command,
+    args: String,
+    begin: std::time::Instant,
+    error: Option<String>,
+) -> CommandRecord {
+    CommandRecord {
+        time: chrono::Local::now().timestamp(),
+        sender: sender.generate_identifier(),
+        context: sender.generate_context(),
+        plugin_name: cmd.plugin_name.clone().unwrap_or_default(),
+        command: cmd.command_name.clone(),
+        args,
+        duration_ms: begin.elapsed().as_millis() as i64,
+        success: error.is_none(),
+        error,
+    }
+}
@@ -6,11 +6,15 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 pub type ReceiverMap = std::collections::HashMap<String, SingleCallSender>;
+use super::artifact::ArtifactStore;
+use super::audit::CommandAuditLog;
 use super::client::{CountdownBotClient, SingleCallSender};
 use super::command::{Command, CommandManager};
 use super::config::CountdownBotConfig;
 use super::event::manager::{EventListener, EventManager};
+use super::inflight::InflightTracker;
 use super::plugin::{BotPluginWrapped, PluginManager, PluginRegisterCallback};
+use super::protocol::{ProtocolSetting, ProtocolVersion, SharedProtocol};
 use super::schedule_loop::handler::ScheduleLoopHandler;
 use super::schedule_loop::ScheduleLoopManager;
 use super::state_hook::StateHookManager;
@@ -49,6 +53,13 @@ pub struct CountdownBot {
     salvo_router: Option<salvo::Router>,
     event_manager: EventManager,
     current_processing_plugin: Option<BotPluginWrapped>,
+    command_audit: Option<CommandAuditLog>,
+    artifact_store: Option<ArtifactStore>,
+    inflight: InflightTracker,
+    restart_requested: bool,
+    api_stop_sender: Option<tokio::sync::watch::Sender<bool>>,
+    api_done_receiver: Option<tokio::sync::oneshot::Receiver<()>>,
+    protocol: SharedProtocol,
 }
 mod builtin_command_impl;
 mod dispatch_impl;
@@ -114,6 +125,21 @@ impl CountdownBot {
             .as_mut()
             .expect("Cannot get router after the bot has started!");
     }
+    // 未启用指令日志时返回None
+    pub fn get_command_audit(&self) -> Option<CommandAuditLog> {
+        return self.command_audit.clone();
+    }
+    // 插件通过此对象存储临时文件并获取下载地址
+    pub fn get_artifact_store(&self) -> ArtifactStore {
+        return self.artifact_store.clone().unwrap();
+    }
+    // 当前使用的OneBot协议版本，自动检测时在收到第一个meta事件前为V11
+    pub fn get_protocol_version(&self) -> ProtocolVersion {
+        return self.protocol.get();
+    }
+    pub fn get_command_prefix(&self) -> Vec<String> {
+        return self.config.command_prefix.clone();
+    }
     pub fn create_url_wrapper(&self) -> SubUrlWrapper {
         return SubUrlWrapper::new(&self.config.web_server.template_prefix);
     }
@@ -140,6 +166,13 @@ impl CountdownBot {
             salvo_router: Some(salvo::Router::new()),
             event_manager: EventManager::new(),
             current_processing_plugin: None,
+            command_audit: None,
+            artifact_store: None,
+            inflight: InflightTracker::default(),
+            restart_requested: false,
+            api_stop_sender: None,
+            api_done_receiver: None,
+            protocol: SharedProtocol::new(ProtocolSetting::Auto),
         }
     }
     pub async fn init(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -182,32 +215,90 @@ impl CountdownBot {
         info!("Currently working path: {}", self.sys_root.display());
         info!("Executable: {}", std::env::current_exe().unwrap().display());
         debug!("Loaded config: {:?}", &self.config);
+        self.protocol = SharedProtocol::new(self.config.protocol);
         info!(
             "Rustc version: {}, core version: {}",
             RUSTC_VERSION, CORE_VERSION
         );
+        if self.config.command_audit.enable {
+            let db_path = self.sys_root.join(&self.config.command_audit.database_file);
+            info!("Command audit database: {}", db_path.display());
+            self.command_audit = Some(CommandAuditLog::open(db_path)?);
+        }
+        self.artifact_store = Some(ArtifactStore::open(
+            self.sys_root.join(&self.config.artifact.storage_dir),
+            self.config.artifact.clone(),
+            self.create_url_wrapper(),
+        )?);
         self.load_plugins().await?;
         self.init_inner_commands();
         return Ok(());
     }
+    // run()返回后调用，判断是否需要通过restart_process重新启动
+    pub fn is_restart_requested(&self) -> bool {
+        return self.restart_requested;
+    }
     async fn shutdown(&mut self) {
-        info!("Stopping main selector...");
+        info!("Stopping main selector, no longer accepting commands...");
+        self.stop = true;
+        let cfg = self.config.shutdown.clone();
+        info!(
+            "Waiting for {} in-flight tasks to finish..",
+            self.inflight.count()
+        );
+        if let Err(_) = tokio::time::timeout(
+            Duration::from_secs(cfg.drain_timeout),
+            self.inflight.wait_idle(),
+        )
+        .await
+        {
+            error!(
+                "{} tasks are still running after {}s, abandoning them..",
+                self.inflight.count(),
+                cfg.drain_timeout
+            );
+        }
+        // on_disable中仍可能调用API，因此在停止API发送之前执行
         for (name, plugin_wrapper) in self.plugin_manager.plugins.iter() {
-            // let locked_1 = plugin.lock().await;
             let guard1 = plugin_wrapper.read().await;
             let mut locked = guard1.plugin_instance.write().await;
-            // locked.on_disable().
-            if let Err(e) = tokio::time::timeout(Duration::from_secs(3), locked.on_disable()).await
+            match tokio::time::timeout(
+                Duration::from_secs(cfg.plugin_disable_timeout),
+                locked.on_disable(),
+            )
+            .await
+            {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => error!("{}: Error occurred in on_disable:\n{}", name, e),
+                Err(e) => error!(
+                    "{}: Spent more than {}s in on_disable, killing it..\n{}",
+                    name, cfg.plugin_disable_timeout, e
+                ),
+            }
+        }
+        info!("Flushing pending API calls..");
+        if let Some(sender) = self.api_stop_sender.take() {
+            sender.send(true).ok();
+        }
+        if let Some(receiver) = self.api_done_receiver.take() {
+            if let Err(_) =
+                tokio::time::timeout(Duration::from_secs(cfg.flush_timeout), receiver).await
             {
                 error!(
-                    "{}: Spent more than 3s in on_disable, killing it..\n{}",
-                    name, e
+                    "Pending API calls are not finished after {}s, dropping them..",
+                    cfg.flush_timeout
                 );
             }
         }
-        self.stop = true;
-        tokio::time::sleep(Duration::from_millis(500)).await;
-        std::process::exit(0);
+        info!("Flushing storage..");
+        if let Some(audit) = self.command_audit.take() {
+            if let Err(e) = audit.flush().await {
+                error!("Failed to flush command audit database:\n{}", e);
+            }
+        }
+        self.write_stream = None;
+        self.read_stream = None;
+        info!("Shutdown complete.");
     }
     fn init_inner_commands(&mut self) {
         self.command_manager
@@ -229,6 +320,13 @@ impl CountdownBot {
                 .with_plugin_name(&String::from("<bot>")),
         )
         .ok();
+        self.register_command(
+            Command::new("restart")
+                .console(true)
+                .description("Restart the bot.")
+                .with_plugin_name(&String::from("<bot>")),
+        )
+        .ok();
         self.register_command(
             Command::new("server_status")
                 .console(true)
@@ -257,6 +355,14 @@ impl CountdownBot {
                 .with_plugin_name(&String::from("<bot>")),
         )
         .ok();
+        self.register_command(
+            Command::new("stats")
+                .console(true)
+                .private(true)
+                .description("查看指令使用统计(仅控制台与指定用户) | stats [天数(可选)]")
+                .with_plugin_name(&String::from("<bot>")),
+        )
+        .ok();
         self.register_command(
             Command::new("plugins")
                 .enable_all()
@@ -266,3 +372,20 @@ impl CountdownBot {
         .ok();
     }
 }
+
+// 使用相同的参数重新执行当前程序，应在tokio运行时关闭后调用
+// Unix下通过exec替换当前进程，仅在失败时返回
+pub fn restart_process() -> std::io::Result<()> {
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    command.args(std::env::args_os().skip(1));
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        return Err(command.exec());
+    }
+    #[cfg(not(unix))]
+    {
+        command.spawn()?;
+        return Ok(());
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
 use super::CountdownBot;
+use crate::countdown_bot::artifact::{run_cleanup_loop, ArtifactHandler, ARTIFACT_ROUTE};
+use crate::countdown_bot::audit::CommandStatsHandler;
 use crate::countdown_bot::bot::ReceiverMap;
 use crate::countdown_bot::client::{APICallRequest, APICallResponse, CountdownBotClient};
 use crate::countdown_bot::command::{CommandSender, ConsoleSender};
@@ -17,6 +19,23 @@ impl CountdownBot {
     pub async fn run(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
         // 启动salvo服务器
         {
+            let token = self.config.command_audit.web_stats_token.clone();
+            if let (Some(audit), false) = (self.command_audit.clone(), token.is_empty()) {
+                let handler = CommandStatsHandler {
+                    audit,
+                    default_days: self.config.command_audit.stats_default_days,
+                    token,
+                };
+                self.get_salvo_router()
+                    .routers_mut()
+                    .push(salvo::Router::with_path("/bot/command_stats").get(handler));
+            }
+            let handler = ArtifactHandler {
+                store: self.get_artifact_store(),
+            };
+            self.get_salvo_router()
+                .routers_mut()
+                .push(salvo::Router::with_path(format!("/{}/<id>", ARTIFACT_ROUTE)).get(handler));
             let config = &self.config.web_server;
             let bind = format!("{}:{}", config.bind_ip, config.bind_port);
             let router = self.salvo_router.take().unwrap();
@@ -32,14 +51,18 @@ impl CountdownBot {
         use tokio_tungstenite::connect_async;
         use url::Url;
         let url_event = {
-            let mut local = Url::parse(&self.config.server_url)?.join("event").unwrap();
+            let mut local = Url::parse(&self.config.server_url)?
+                .join(&self.config.event_path)
+                .unwrap();
             local.set_query(Some(
                 format!("access_token={}", self.config.access_token).as_str(),
             ));
             local
         };
         let url_call = {
-            let mut local = Url::parse(&self.config.server_url)?.join("api").unwrap();
+            let mut local = Url::parse(&self.config.server_url)?
+                .join(&self.config.api_path)
+                .unwrap();
             local.set_query(Some(
                 format!("access_token={}", self.config.access_token).as_str(),
             ));
@@ -57,10 +80,20 @@ impl CountdownBot {
         let (stop_tx, stop_rx) = tokio::sync::watch::channel::<bool>(false);
         self.stop_signal_sender = Some(stop_tx);
         self.stop_signal_receiver = Some(stop_rx.clone());
+        tokio::spawn(run_cleanup_loop(self.get_artifact_store(), stop_rx.clone()));
         self.schedule_loop_manager
             .as_mut()
             .unwrap()
             .set_stop_signal_receiver(stop_rx.clone());
+        self.schedule_loop_manager
+            .as_mut()
+            .unwrap()
+            .set_inflight_tracker(self.inflight.clone());
+        // API调用在其他任务结束后才停止，以便发出剩余的消息
+        let (api_stop_tx, api_stop_rx) = tokio::sync::watch::channel::<bool>(false);
+        let (api_done_tx, api_done_rx) = tokio::sync::oneshot::channel::<()>();
+        self.api_stop_sender = Some(api_stop_tx);
+        self.api_done_receiver = Some(api_done_rx);
         let (console_tx, mut console_rx) = mpsc::unbounded_channel::<String>();
         {
             use tokio::io::{AsyncBufReadExt, BufReader};
@@ -86,7 +119,10 @@ impl CountdownBot {
             });
         }
 
-        self.client = Some(CountdownBotClient::new(call_tx.clone()));
+        self.client = Some(
+            CountdownBotClient::new(call_tx.clone(), self.protocol.clone())
+                .with_text_render(self.config.text_render.clone()),
+        );
         {
             for (name, wrapper) in self
                 .plugin_manager
@@ -124,23 +160,30 @@ impl CountdownBot {
             tokio::spawn(loop_manager.run());
         }
         {
-            let local_stop_rx = stop_rx.clone();
             let local_cfg = self.config.clone();
             tokio::spawn(async move {
-                let mut stop_rx = local_stop_rx;
+                let mut stop_rx = api_stop_rx;
                 let mut receiver_map: ReceiverMap = HashMap::new();
                 let cfg = local_cfg;
+                let mut flushing = false;
+                let mut call_closed = false;
                 loop {
                     match connect_async(url_call.clone()).await {
                         Ok((stream, _resp)) => {
                             info!("API handler connected.");
                             let (mut call_write, mut call_read) = stream.split();
                             loop {
+                                if call_closed && receiver_map.is_empty() {
+                                    info!("Shutting down API handler..");
+                                    api_done_tx.send(()).ok();
+                                    return;
+                                }
                                 tokio::select! {
-                                    _   = stop_rx.changed() => {
+                                    _   = stop_rx.changed(), if !flushing => {
                                         if *stop_rx.borrow() {
-                                            info!("Shutting down API handler..");
-                                            return;
+                                            info!("Flushing {} pending API calls..", receiver_map.len());
+                                            flushing = true;
+                                            call_rx.close();
                                         }
                                     }
                                     Some(result) = call_read.next() => {
@@ -170,11 +213,17 @@ impl CountdownBot {
                                                     ))),
                                                 }).ok();
                                             }
+                                        } else if json.get("echo").is_none() {
+                                            // v12单一连接时，事件也会出现在此连接上
+                                            trace!("Ignored non-response message on API connection");
                                         } else {
                                             error!("Invalid call response: {:?}", &json);
                                         }
                                     }
-                                    call_req = call_rx.recv() => {
+                                    call_req = call_rx.recv(), if !call_closed => {
+                                        if call_req.is_none() {
+                                            call_closed = true;
+                                        }
                                         if let Some(req) = call_req{
                                             receiver_map.insert(req.token.clone(), req.sender);
                                             if let Err(err) = call_write
@@ -193,11 +242,22 @@ impl CountdownBot {
                                             }
                                         }
                                     }
+                                    else => break,
                                 };
                             }
+                            if flushing {
+                                error!("API handler disconnected while shutting down, dropping pending calls..");
+                                api_done_tx.send(()).ok();
+                                return;
+                            }
                         }
                         Err(err) => {
                             error!("Error occurred: {}", err);
+                            if flushing || *stop_rx.borrow() {
+                                error!("API handler disconnected while shutting down, dropping pending calls..");
+                                api_done_tx.send(()).ok();
+                                return;
+                            }
                             info!("Reconnecting after {} seconds..", cfg.reconnect_interval);
                             tokio::time::sleep(core::time::Duration::from_secs(
                                 cfg.reconnect_interval.into(),
@@ -230,6 +290,7 @@ impl CountdownBot {
                             _   = stop_rx.changed() => {
                                 if *stop_rx.borrow() {
                                     self.shutdown().await;
+                                    break;
                                 }
                             }
                             Some(result) = self.read_stream.as_mut().unwrap().next() => {
@@ -240,6 +301,13 @@ impl CountdownBot {
                                             &raw_string.as_str(),
                                         ) {
                                             Ok(json) => {
+                                                let json = match self.protocol.adapt_event(json) {
+                                                    Ok(v) => v,
+                                                    Err(e) => {
+                                                        error!("Failed to adapt event: {}", e);
+                                                        continue;
+                                                    }
+                                                };
                                                 match EventContainer::from_json(&json) {
                                                     Ok(event) => {self.dispatch_event(event).await;}
                                                     Err(e) => {
@@ -270,10 +338,17 @@ impl CountdownBot {
                         "Reconnecting after {} seconds..",
                         self.config.reconnect_interval
                     );
-                    tokio::time::sleep(core::time::Duration::from_secs(
-                        self.config.reconnect_interval.into(),
-                    ))
-                    .await;
+                    let mut stop_rx = self.stop_signal_receiver.as_ref().unwrap().clone();
+                    tokio::select! {
+                        _ = tokio::time::sleep(core::time::Duration::from_secs(
+                            self.config.reconnect_interval.into(),
+                        )) => {}
+                        _ = stop_rx.changed() => {
+                            if *stop_rx.borrow() {
+                                self.shutdown().await;
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use serde::Deserialize;
 use serde_json::Value;
 
@@ -6,7 +7,7 @@ use crate::{
     declare_api_call,
 };
 
-use super::CountdownBotClient;
+use super::{CountdownBotClient, ResultType};
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetImageResponse {
     pub size: i64,
@@ -103,6 +104,16 @@ pub struct GetGroupFilesResponse {
 pub struct GetGroupFileUrlResponse {
     pub url: String,
 }
+#[derive(Deserialize, Debug, Clone)]
+pub struct EssenceMessage {
+    pub sender_id: i64,
+    pub sender_nick: String,
+    pub sender_time: i64,
+    pub operator_id: i64,
+    pub operator_nick: String,
+    pub operator_time: i64,
+    pub message_id: i64,
+}
 impl CountdownBotClient {
     declare_api_call!(
         set_group_portrait,
@@ -140,4 +151,31 @@ impl CountdownBotClient {
         (file_id, &str),
         (busid, i32)
     );
+    // file为go-cqhttp所在主机上的本地路径，上传到群文件根目录
+    declare_api_call!(
+        upload_group_file,
+        (),
+        (group_id, i64),
+        (file, &str),
+        (name, &str)
+    );
+    declare_api_call!(set_essence_msg, (), (message_id, i64));
+    declare_api_call!(delete_essence_msg, (), (message_id, i64));
+    declare_api_call!(get_essence_msg_list, Vec<EssenceMessage>, (group_id, i64));
+    // 在群文件根目录及其下一级文件夹中按文件名查找
+    pub async fn find_group_file(&self, group_id: i64, name: &str) -> ResultType<GroupFile> {
+        let root = self.get_group_root_files(group_id).await?;
+        if let Some(file) = root.files.into_iter().find(|f| f.file_name == name) {
+            return Ok(file);
+        }
+        for folder in root.folders.iter() {
+            let files = self
+                .get_group_files_by_folder(group_id, &folder.folder_id)
+                .await?;
+            if let Some(file) = files.files.into_iter().find(|f| f.file_name == name) {
+                return Ok(file);
+            }
+        }
+        Err(anyhow!("未找到群文件: {}", name).into())
+    }
 }
@@ -1,14 +1,15 @@
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::{
     countdown_bot::event::{
-        message::{GroupSenderRole, SenderSex},
+        message::{GroupMessageEvent, GroupSenderRole, SenderSex},
         request::GroupRequestSubType,
     },
     declare_api_call,
 };
 
-use super::CountdownBotClient;
+use super::{CountdownBotClient, ResultType};
 
 impl CountdownBotClient {
     declare_api_call!(
@@ -74,6 +75,40 @@ impl CountdownBotClient {
         (no_cache, bool)
     );
     declare_api_call!(get_group_member_list, Vec<GroupMemberInfo>, (group_id, i64));
+    /*
+    通过get_group_member_info查询群成员的群身份
+        OneBot v12的实现未在扩展字段中提供群身份时返回None
+    */
+    pub async fn get_member_role(
+        &self,
+        group_id: i64,
+        user_id: i64,
+        no_cache: bool,
+    ) -> ResultType<Option<GroupSenderRole>> {
+        let data = self
+            .call(
+                "get_group_member_info",
+                &json!({"group_id": group_id, "user_id": user_id, "no_cache": no_cache}),
+            )
+            .await?;
+        Ok(data
+            .get("role")
+            .cloned()
+            .and_then(|v| serde_json::from_value(v).ok()))
+    }
+    /*
+    消息发送者的群身份，消息事件中没有时通过get_member_role查询
+        仍无法获取时返回None
+    */
+    pub async fn get_sender_role(
+        &self,
+        evt: &GroupMessageEvent,
+    ) -> ResultType<Option<GroupSenderRole>> {
+        if let Some(role) = &evt.sender.role {
+            return Ok(Some(role.clone()));
+        }
+        self.get_member_role(evt.group_id, evt.user_id, false).await
+    }
     declare_api_call!(
         get_group_honor_info,
         GroupHonorFetchResp,
@@ -82,6 +117,10 @@ impl CountdownBotClient {
     );
 }
 
+fn default_member_role() -> GroupSenderRole {
+    GroupSenderRole::Member
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GroupMemberInfo {
     pub group_id: i64,
@@ -94,6 +133,8 @@ pub struct GroupMemberInfo {
     pub join_time: i64,
     pub last_sent_time: i64,
     pub level: String,
+    // OneBot v12下可能无法获取，此时视为普通成员，需要区分时使用get_member_role
+    #[serde(default = "default_member_role")]
     pub role: GroupSenderRole,
     pub unfriendly: bool,
     pub title: Option<String>,
@@ -64,6 +64,8 @@ impl CountdownBotClient {
         (auto_escape, bool)
     );
     declare_api_call!(delete_message, (), (message_id, i64));
+    // go-cqhttp使用的撤回消息API
+    declare_api_call!(delete_msg, (), (message_id, i64));
     pub async fn msgseg_send_private_msg(
         &self,
         uid: i64,
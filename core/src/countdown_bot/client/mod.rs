@@ -1,10 +1,19 @@
+use std::sync::Arc;
+
 use self::message::{ComposedMessageId, MessageIdResp};
-use log::{debug, info};
+use log::{debug, error, info};
 use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Value;
 use tokio::sync::{mpsc, oneshot};
 
-use super::{command::SenderType, event::message::MessageEvent, message::wrapper::Message};
+use super::{
+    command::SenderType,
+    config::TextRenderProps,
+    event::message::MessageEvent,
+    message::{cq_code::unescape, wrapper::Message},
+    protocol::{v12, ProtocolVersion, SharedProtocol},
+    text_render::render_text_image,
+};
 
 pub type RequestReceiver = mpsc::UnboundedReceiver<APICallRequest>;
 pub type RequestSender = mpsc::UnboundedSender<APICallRequest>;
@@ -38,16 +47,64 @@ pub struct APICallResponse {
 #[derive(Clone)]
 pub struct CountdownBotClient {
     request_sender: RequestSender,
+    protocol: SharedProtocol,
+    text_render: Option<Arc<TextRenderProps>>,
 }
 unsafe impl std::marker::Send for CountdownBotClient {}
 impl CountdownBotClient {
-    pub fn new(request_sender: RequestSender) -> CountdownBotClient {
-        CountdownBotClient { request_sender }
+    pub fn new(request_sender: RequestSender, protocol: SharedProtocol) -> CountdownBotClient {
+        CountdownBotClient {
+            request_sender,
+            protocol,
+            text_render: None,
+        }
+    }
+    // 设置后，超过阈值的群消息会以图片形式发送
+    pub fn with_text_render(mut self, props: TextRenderProps) -> Self {
+        self.text_render = Some(Arc::new(props));
+        self
+    }
+    pub fn get_protocol_version(&self) -> ProtocolVersion {
+        self.protocol.get()
     }
+    // 插件总是以v11的格式调用API，在v12下转换后再发出
     pub async fn call(
         &self,
         action: &str,
         params: &Value,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        if self.protocol.get() == ProtocolVersion::V11 {
+            return self.call_raw(action, params).await;
+        }
+        let (v12_action, mut v12_params) = v12::adapt_request(action, params);
+        for (index, upload) in v12::pending_uploads(&v12_params) {
+            let resp = self.call_raw("upload_file", &upload).await?;
+            v12::apply_upload(&mut v12_params, index, &resp)?;
+        }
+        let resp = self.call_raw(&v12_action, &v12_params).await?;
+        Ok(v12::adapt_response(action, params, resp))
+    }
+    pub fn sync_call(
+        &self,
+        action: &str,
+        params: &Value,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        if self.protocol.get() == ProtocolVersion::V11 {
+            return self.sync_call_raw(action, params);
+        }
+        let (v12_action, mut v12_params) = v12::adapt_request(action, params);
+        for (index, upload) in v12::pending_uploads(&v12_params) {
+            let resp = self.sync_call_raw("upload_file", &upload)?;
+            v12::apply_upload(&mut v12_params, index, &resp)?;
+        }
+        let resp = self.sync_call_raw(&v12_action, &v12_params)?;
+        Ok(v12::adapt_response(action, params, resp))
+    }
+    // 不经协议转换，直接发出调用
+    pub async fn call_raw(
+        &self,
+        action: &str,
+        params: &Value,
     ) -> Result<Value, Box<dyn std::error::Error>> {
         let (tx, rx) = oneshot::channel::<SenderContainer>();
         let token = uuid::Uuid::new_v4().to_string();
@@ -69,7 +126,7 @@ impl CountdownBotClient {
             Err(e) => return Err(Box::new(e)),
         }
     }
-    pub fn sync_call(
+    pub fn sync_call_raw(
         &self,
         action: &str,
         params: &Value,
@@ -154,6 +211,18 @@ impl CountdownBotClient {
                     .await
             }
             SenderType::Group(evt) => {
+                if self.should_send_as_image(evt.group_id, text, auto_escape) {
+                    // 不转义发送的文本中已经包含CQ转义，渲染前需要还原
+                    let plain = if auto_escape {
+                        text.to_string()
+                    } else {
+                        unescape(text)
+                    };
+                    match self.quick_send_text_image(sender, &plain).await {
+                        Ok(v) => return Ok(v),
+                        Err(e) => error!("Failed to render text as image, sending as text: {}", e),
+                    }
+                }
                 self.quick_send_ex(&MessageEvent::Group(evt.clone()), text, auto_escape)
                     .await
             }
@@ -163,6 +232,32 @@ impl CountdownBotClient {
             }
         }
     }
+    // 包含CQ码的消息无法渲染，总是以文本发送
+    fn should_send_as_image(&self, group_id: i64, text: &str, auto_escape: bool) -> bool {
+        match self
+            .text_render
+            .as_ref()
+            .and_then(|v| v.threshold_of(group_id))
+        {
+            Some(threshold) => {
+                text.chars().count() > threshold && (auto_escape || !text.contains("[CQ:"))
+            }
+            None => false,
+        }
+    }
+    // 将文本渲染为图片后发送，支持简单的Markdown标题、列表、表格与代码块
+    pub async fn quick_send_text_image(
+        &self,
+        sender: &SenderType,
+        text: &str,
+    ) -> ResultType<ComposedMessageId> {
+        let props = self.text_render.as_deref().cloned().unwrap_or_default();
+        let text = text.to_string();
+        let segment =
+            tokio::task::spawn_blocking(move || render_text_image(&text, &props)).await??;
+        self.msgseg_quicksend(sender, &Message::Segment(vec![segment]))
+            .await
+    }
     pub fn quick_send_by_sender_ex_sync(
         &self,
         sender: &SenderType,
@@ -23,6 +23,21 @@ pub trait CommandHandler {
     ) -> Result<(), Box<dyn std::error::Error>>;
 }
 pub type WrappedCommandHandler = Mutex<Box<dyn CommandHandler + Send>>;
+// 回复消息时，消息开头会带有回复与@的CQ码，去掉它们以便识别指令
+// 插件可通过原始消息中的reply消息段获取被回复的消息
+pub fn strip_reply_prefix(line: &str) -> &str {
+    if !line.starts_with("[CQ:reply,") {
+        return line;
+    }
+    let mut rest = line;
+    while rest.starts_with("[CQ:reply,") || rest.starts_with("[CQ:at,") {
+        match rest.find(']') {
+            Some(end) => rest = rest[end + 1..].trim_start(),
+            None => return line,
+        }
+    }
+    return rest;
+}
 // #[derive(Debug)]
 pub struct Command {
     pub command_name: String,
@@ -249,6 +264,14 @@ impl SenderType {
             SenderType::Guild(v) => format!("guild:{},channel:{}", v.guild_id, v.channel_id),
         }
     }
+    pub fn generate_context(&self) -> String {
+        match self {
+            SenderType::Console(_) => "console".to_string(),
+            SenderType::Private(_) => "private".to_string(),
+            SenderType::Group(v) => format!("group:{}", v.group_id),
+            SenderType::Guild(v) => format!("guild:{},channel:{}", v.guild_id, v.channel_id),
+        }
+    }
     pub fn generate_sender_message(&self) -> String {
         match self {
             SenderType::Console(_) => "Console".to_string(),
@@ -1,4 +1,8 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+
+use super::protocol::ProtocolSetting;
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct WebServerProps {
     pub bind_ip: String,
@@ -7,17 +11,87 @@ pub struct WebServerProps {
     pub enable: bool,
 }
 #[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CommandAuditProps {
+    pub enable: bool,
+    pub database_file: String,
+    pub stats_default_days: i64,
+    pub stats_top_count: i64,
+    // 除控制台外，可以在私聊中使用stats指令的用户
+    pub stats_users: Vec<i64>,
+    // 为空时不提供 /bot/command_stats ，否则请求需携带 ?token=<此值>
+    pub web_stats_token: String,
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ArtifactProps {
+    // 插件存储的临时文件所在目录，相对于运行目录，启动时会被清空
+    pub storage_dir: String,
+    // 签名下载地址使用的密钥，为空时每次启动随机生成
+    pub secret: String,
+    // 默认与最长有效期(秒)
+    pub default_ttl: u64,
+    pub max_ttl: u64,
+    // 单个文件、单个插件与全部文件的大小上限(字节)
+    pub max_file_size: u64,
+    pub plugin_quota: u64,
+    pub total_quota: u64,
+    // 清理过期文件的间隔(秒)
+    pub cleanup_interval: u64,
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TextRenderProps {
+    // 需要支持中文，可以使用 fc-list :lang=zh 查看可用的字体
+    pub font_family: String,
+    pub monospace_font_family: String,
+    pub font_size: u32,
+    // 图像的最大宽度(像素)，超出时自动折行
+    pub max_width: u32,
+    // 超过此字符数的群消息自动以图片发送，为0时不启用
+    pub auto_image_threshold: usize,
+    // 按群覆盖的字符数阈值，为0时在该群禁用
+    pub group_auto_image_threshold: HashMap<i64, usize>,
+}
+impl TextRenderProps {
+    pub fn threshold_of(&self, group_id: i64) -> Option<usize> {
+        Some(
+            self.group_auto_image_threshold
+                .get(&group_id)
+                .cloned()
+                .unwrap_or(self.auto_image_threshold),
+        )
+        .filter(|v| *v > 0)
+    }
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ShutdownProps {
+    // 等待正在执行的指令/事件处理完成的时间(秒)
+    pub drain_timeout: u64,
+    // 等待未发出的API调用完成的时间(秒)
+    pub flush_timeout: u64,
+    pub plugin_disable_timeout: u64,
+}
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CountdownBotConfig {
     pub debug: bool,
     pub server_url: String,
     pub access_token: String,
+    // auto/v11/v12, auto时根据连接后的第一个meta事件判断
+    pub protocol: ProtocolSetting,
+    // 事件与API的WebSocket路径，v12的单一连接可将两者都设置为空字符串
+    pub event_path: String,
+    pub api_path: String,
     pub reconnect_interval: u32,
     pub command_prefix: Vec<String>,
+    // 是否识别回复消息中的指令(去掉开头的回复与@后匹配指令前缀)
+    pub command_in_reply: bool,
     pub ignored_plugins: Vec<String>,
     pub blacklist_users: Vec<i64>,
     pub command_cooldown: u64,
     pub web_server: WebServerProps,
     pub logging_level: String,
+    pub command_audit: CommandAuditProps,
+    pub shutdown: ShutdownProps,
+    pub artifact: ArtifactProps,
+    pub text_render: TextRenderProps,
 }
 impl Default for WebServerProps {
     fn default() -> Self {
@@ -29,19 +103,74 @@ impl Default for WebServerProps {
         }
     }
 }
+impl Default for CommandAuditProps {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            database_file: "command_audit.db".to_string(),
+            stats_default_days: 7,
+            stats_top_count: 5,
+            stats_users: vec![],
+            web_stats_token: String::new(),
+        }
+    }
+}
+impl Default for ArtifactProps {
+    fn default() -> Self {
+        Self {
+            storage_dir: "artifacts".to_string(),
+            secret: String::new(),
+            default_ttl: 60 * 60,
+            max_ttl: 24 * 60 * 60,
+            max_file_size: 32 << 20,
+            plugin_quota: 128 << 20,
+            total_quota: 512 << 20,
+            cleanup_interval: 60,
+        }
+    }
+}
+impl Default for TextRenderProps {
+    fn default() -> Self {
+        Self {
+            font_family: "Noto Sans CJK SC".to_string(),
+            monospace_font_family: "Noto Sans Mono CJK SC".to_string(),
+            font_size: 16,
+            max_width: 800,
+            auto_image_threshold: 0,
+            group_auto_image_threshold: HashMap::new(),
+        }
+    }
+}
+impl Default for ShutdownProps {
+    fn default() -> Self {
+        Self {
+            drain_timeout: 10,
+            flush_timeout: 5,
+            plugin_disable_timeout: 3,
+        }
+    }
+}
 impl Default for CountdownBotConfig {
     fn default() -> CountdownBotConfig {
         CountdownBotConfig {
             debug: false,
             access_token: String::from(""),
             server_url: String::from("ws://127.0.0.1:2333"),
+            protocol: ProtocolSetting::Auto,
+            event_path: String::from("event"),
+            api_path: String::from("api"),
             reconnect_interval: 5,
             command_prefix: vec![String::from("--"), String::from("!!")],
+            command_in_reply: false,
             ignored_plugins: vec![],
             blacklist_users: vec![],
             command_cooldown: 0,
             web_server: WebServerProps::default(),
             logging_level: "info".to_string(),
+            command_audit: CommandAuditProps::default(),
+            shutdown: ShutdownProps::default(),
+            artifact: ArtifactProps::default(),
+            text_render: TextRenderProps::default(),
         }
     }
 }
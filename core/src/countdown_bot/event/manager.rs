@@ -1,4 +1,5 @@
 use crate::countdown_bot::client::ResultType;
+use crate::countdown_bot::inflight::InflightTracker;
 use crate::countdown_bot::plugin::BotPluginWrapped;
 
 use super::OOPEventContainer;
@@ -52,14 +53,20 @@ impl EventManager {
             .unwrap()
             .push(EventListenerWrapper { plugin, listener });
     }
-    pub async fn dispatch_event(&self, event: WrappedOOPEventContainer) {
+    pub async fn dispatch_event(
+        &self,
+        event: WrappedOOPEventContainer,
+        inflight: &InflightTracker,
+    ) {
         let tid = event.read().await.event.type_id();
         if let Some(listeners) = self.listeners.get(&tid) {
             for item in listeners.iter() {
                 let plugin = item.plugin.clone();
                 let event = event.clone();
                 let listener = item.listener.clone();
+                let inflight_guard = inflight.enter();
                 tokio::spawn(async move {
+                    let _inflight_guard = inflight_guard;
                     let raw_value = event.read().await.raw_value.clone();
                     let handle_result = listener.lock().await.on_event(event, plugin).await;
                     if let Err(e) = handle_result {
@@ -0,0 +1,37 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+// 记录正在执行的指令、事件及定时任务数量，用于关闭时等待它们完成
+#[derive(Clone, Default)]
+pub struct InflightTracker {
+    count: Arc<AtomicUsize>,
+}
+pub struct InflightGuard {
+    count: Arc<AtomicUsize>,
+}
+impl InflightTracker {
+    pub fn enter(&self) -> InflightGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InflightGuard {
+            count: self.count.clone(),
+        }
+    }
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+    pub async fn wait_idle(&self) {
+        while self.count() > 0 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,109 @@
+use serde_json::{json, Map, Value};
+
+// 还原CQ码转义，如 &#91; 还原为 [
+pub fn unescape(s: &str) -> String {
+    s.replace("&#91;", "[")
+        .replace("&#93;", "]")
+        .replace("&#44;", ",")
+        .replace("&amp;", "&")
+}
+
+// 将CQ码字符串解析为OneBot v11的消息段数组(JSON)
+// 参数值均以字符串形式保存
+pub fn parse_cq_code(text: &str) -> Vec<Value> {
+    let mut segments = vec![];
+    let mut rest = text;
+    while !rest.is_empty() {
+        match rest.find("[CQ:") {
+            Some(begin) => {
+                if begin > 0 {
+                    segments.push(json!({
+                        "type": "text",
+                        "data": {"text": unescape(&rest[..begin])}
+                    }));
+                }
+                let after = &rest[begin..];
+                match after.find(']') {
+                    Some(end) => {
+                        let body = &after[4..end];
+                        let mut parts = body.split(',');
+                        let seg_type = parts.next().unwrap_or("").to_string();
+                        let mut data = Map::new();
+                        for part in parts {
+                            if let Some((k, v)) = part.split_once('=') {
+                                data.insert(k.to_string(), Value::String(unescape(v)));
+                            }
+                        }
+                        segments.push(json!({
+                            "type": seg_type,
+                            "data": data
+                        }));
+                        rest = &after[end + 1..];
+                    }
+                    None => {
+                        // 不完整的CQ码按纯文本处理
+                        segments.push(json!({
+                            "type": "text",
+                            "data": {"text": unescape(after)}
+                        }));
+                        rest = "";
+                    }
+                }
+            }
+            None => {
+                segments.push(json!({
+                    "type": "text",
+                    "data": {"text": unescape(rest)}
+                }));
+                rest = "";
+            }
+        }
+    }
+    return segments;
+}
+
+// 转义CQ码中的特殊字符，is_param为true时同时转义逗号
+// 以不转义(auto_escape=false)的方式发送用户提供的文本前，需要先转义
+pub fn escape(s: &str, is_param: bool) -> String {
+    let out = s
+        .replace('&', "&amp;")
+        .replace('[', "&#91;")
+        .replace(']', "&#93;");
+    if is_param {
+        out.replace(',', "&#44;")
+    } else {
+        out
+    }
+}
+
+// 将v11的消息段数组(JSON)转换为CQ码字符串
+pub fn to_cq_code(segments: &[Value]) -> String {
+    let mut out = String::new();
+    for seg in segments.iter() {
+        let seg_type = seg.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let data = seg.get("data").and_then(|v| v.as_object());
+        if seg_type == "text" {
+            let text = data
+                .and_then(|v| v.get("text"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            out.push_str(&escape(text, false));
+            continue;
+        }
+        out.push_str("[CQ:");
+        out.push_str(seg_type);
+        for (k, v) in data.into_iter().flatten() {
+            let val = match v {
+                Value::Null => continue,
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            out.push(',');
+            out.push_str(k);
+            out.push('=');
+            out.push_str(&escape(&val, true));
+        }
+        out.push(']');
+    }
+    return out;
+}
@@ -1,3 +1,4 @@
+pub mod cq_code;
 pub mod r#macro;
 pub mod segment;
 pub mod segment_impl;
@@ -24,6 +24,7 @@ impl<'de> Deserialize<'de> for MessageSegment {
             "image" => MessageSegment::Image(from_value(v).map_err(Error::custom)?),
             "record" => MessageSegment::Record(from_value(v).map_err(Error::custom)?),
             "video" => MessageSegment::Video(from_value(v).map_err(Error::custom)?),
+            "at" => MessageSegment::At(from_value(v).map_err(Error::custom)?),
             "rps" => MessageSegment::RPS,
             "dice" => MessageSegment::Dice,
             "shake" => MessageSegment::Shake,
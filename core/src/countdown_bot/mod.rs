@@ -1,10 +1,16 @@
+pub mod artifact;
+pub mod audit;
 pub mod bot;
 pub mod client;
 pub mod command;
 pub mod config;
 pub mod event;
+pub mod inflight;
 pub mod message;
 pub mod plugin;
+pub mod protocol;
+pub mod sandbox;
 pub mod schedule_loop;
 pub mod state_hook;
+pub mod text_render;
 pub mod utils;
@@ -0,0 +1,87 @@
+use std::sync::{Arc, RwLock};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::client::ResultType;
+
+pub mod v12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolVersion {
+    V11,
+    V12,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtocolSetting {
+    Auto,
+    V11,
+    V12,
+}
+// 根据meta事件判断OneBot协议版本
+// v11: post_type = meta_event, meta_event_type = lifecycle
+// v12: type = meta, detail_type = connect
+pub fn detect_protocol(json: &Value) -> Option<ProtocolVersion> {
+    let str_field = |name: &str| json.get(name).and_then(|v| v.as_str());
+    if str_field("post_type") == Some("meta_event")
+        && str_field("meta_event_type") == Some("lifecycle")
+    {
+        return Some(ProtocolVersion::V11);
+    }
+    if str_field("type") == Some("meta") && str_field("detail_type") == Some("connect") {
+        let onebot_version = json
+            .get("version")
+            .and_then(|v| v.get("onebot_version"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("12");
+        return Some(if onebot_version.starts_with("11") {
+            ProtocolVersion::V11
+        } else {
+            ProtocolVersion::V12
+        });
+    }
+    return None;
+}
+// 事件循环与API客户端共享的协议状态
+// 插件始终使用v11格式的事件与API，由此处进行转换
+#[derive(Clone, Debug)]
+pub struct SharedProtocol {
+    version: Arc<RwLock<ProtocolVersion>>,
+    setting: ProtocolSetting,
+}
+impl SharedProtocol {
+    pub fn new(setting: ProtocolSetting) -> Self {
+        Self {
+            version: Arc::new(RwLock::new(match setting {
+                ProtocolSetting::V12 => ProtocolVersion::V12,
+                _ => ProtocolVersion::V11,
+            })),
+            setting,
+        }
+    }
+    pub fn get(&self) -> ProtocolVersion {
+        return *self.version.read().unwrap();
+    }
+    fn observe_event(&self, json: &Value) {
+        if self.setting != ProtocolSetting::Auto {
+            return;
+        }
+        if let Some(detected) = detect_protocol(json) {
+            let mut version = self.version.write().unwrap();
+            if *version != detected {
+                info!("Detected OneBot protocol: {:?}", detected);
+                *version = detected;
+            }
+        }
+    }
+    // 将收到的事件转换为v11格式
+    pub fn adapt_event(&self, json: Value) -> ResultType<Value> {
+        self.observe_event(&json);
+        match self.get() {
+            ProtocolVersion::V11 => Ok(json),
+            ProtocolVersion::V12 => v12::adapt_event(json),
+        }
+    }
+}
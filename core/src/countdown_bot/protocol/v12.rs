@@ -0,0 +1,475 @@
+// OneBot v12 <-> v11 转换
+// 事件被转换为v11(go-cqhttp)的字段布局，API调用在发出前转换为v12的动作与参数
+use anyhow::anyhow;
+use serde_json::{json, Map, Value};
+
+use crate::countdown_bot::{
+    client::ResultType,
+    message::cq_code::{parse_cq_code, to_cq_code},
+};
+
+fn id_to_i64(v: &Value) -> i64 {
+    match v {
+        Value::Number(n) => n.as_i64().unwrap_or(-1),
+        Value::String(s) => s.parse::<i64>().unwrap_or(-1),
+        _ => -1,
+    }
+}
+fn id_to_string(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+fn field<'a>(v: &'a Value, name: &str) -> &'a Value {
+    v.get(name).unwrap_or(&Value::Null)
+}
+fn str_field<'a>(v: &'a Value, name: &str) -> &'a str {
+    v.get(name).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+/*
+v12标准中没有群身份，部分实现在扩展字段中提供，如 role 或 qq.role
+无法获取时返回Null，而不是假定为普通成员
+*/
+fn role_field(v: &Value) -> Value {
+    let role = v.as_object().and_then(|obj| {
+        obj.iter()
+            .filter(|(k, _)| *k == "role" || k.ends_with(".role"))
+            .filter_map(|(_, v)| v.as_str())
+            .find(|v| matches!(*v, "owner" | "admin" | "member"))
+    });
+    match role {
+        Some(v) => json!(v),
+        None => Value::Null,
+    }
+}
+
+fn adapt_incoming_segment(seg: &Value) -> Value {
+    let data = field(seg, "data");
+    match str_field(seg, "type") {
+        "text" => json!({"type": "text", "data": {"text": str_field(data, "text")}}),
+        "mention" => json!({
+            "type": "at",
+            "data": {"qq": id_to_string(field(data, "user_id"))}
+        }),
+        "mention_all" => json!({"type": "at", "data": {"qq": "all"}}),
+        "image" => json!({
+            "type": "image",
+            "data": {
+                "file": id_to_string(field(data, "file_id")),
+                "url": data.get("url")
+            }
+        }),
+        "voice" | "audio" => json!({
+            "type": "record",
+            "data": {"file": id_to_string(field(data, "file_id"))}
+        }),
+        "video" => json!({
+            "type": "video",
+            "data": {"file": id_to_string(field(data, "file_id"))}
+        }),
+        "location" => json!({
+            "type": "location",
+            "data": {
+                "lat": id_to_string(field(data, "latitude")),
+                "lon": id_to_string(field(data, "longitude")),
+                "title": data.get("title"),
+                "content": data.get("content")
+            }
+        }),
+        "reply" => json!({
+            "type": "reply",
+            "data": {"id": id_to_i64(field(data, "message_id"))}
+        }),
+        // v11中没有对应的消息段，以文本形式保留
+        other => json!({"type": "text", "data": {"text": format!("[{}]", other)}}),
+    }
+}
+
+fn adapt_incoming_message(message: &Value) -> Value {
+    match message {
+        Value::Array(segs) => Value::Array(segs.iter().map(adapt_incoming_segment).collect()),
+        Value::String(s) => Value::Array(vec![json!({"type": "text", "data": {"text": s}})]),
+        _ => Value::Array(vec![]),
+    }
+}
+
+fn adapt_message_event(src: &Value, out: &mut Map<String, Value>) -> ResultType<()> {
+    let message = adapt_incoming_message(field(src, "message"));
+    let raw_message = match &message {
+        Value::Array(segs) => to_cq_code(segs),
+        _ => str_field(src, "alt_message").to_string(),
+    };
+    let user_id = field(src, "user_id");
+    match str_field(src, "detail_type") {
+        "private" => {
+            out.insert("message_type".into(), json!("private"));
+            out.insert("sub_type".into(), json!("friend"));
+            out.insert(
+                "message_id".into(),
+                json!(id_to_i64(field(src, "message_id"))),
+            );
+            out.insert("user_id".into(), json!(id_to_i64(user_id)));
+            out.insert("sender".into(), json!({"user_id": id_to_i64(user_id)}));
+        }
+        "group" => {
+            out.insert("message_type".into(), json!("group"));
+            out.insert("sub_type".into(), json!("normal"));
+            out.insert(
+                "message_id".into(),
+                json!(id_to_i64(field(src, "message_id"))),
+            );
+            out.insert("group_id".into(), json!(id_to_i64(field(src, "group_id"))));
+            out.insert("user_id".into(), json!(id_to_i64(user_id)));
+            out.insert("anonymous".into(), Value::Null);
+            out.insert(
+                "sender".into(),
+                json!({"user_id": id_to_i64(user_id), "role": role_field(src)}),
+            );
+        }
+        "channel" => {
+            out.insert("message_type".into(), json!("guild"));
+            out.insert("sub_type".into(), json!("channel"));
+            out.insert(
+                "message_id".into(),
+                json!(id_to_string(field(src, "message_id"))),
+            );
+            out.insert(
+                "guild_id".into(),
+                json!(id_to_string(field(src, "guild_id"))),
+            );
+            out.insert(
+                "channel_id".into(),
+                json!(id_to_string(field(src, "channel_id"))),
+            );
+            out.insert("user_id".into(), json!(id_to_string(user_id)));
+            out.insert(
+                "sender".into(),
+                json!({
+                    "user_id": user_id.as_str().and_then(|v| v.parse::<i64>().ok()),
+                    "tiny_id": id_to_string(user_id)
+                }),
+            );
+        }
+        other => return Err(anyhow!("Unsupported message detail_type: {}", other).into()),
+    };
+    out.insert("message".into(), message);
+    out.insert("raw_message".into(), json!(raw_message));
+    out.insert("font".into(), json!(0));
+    Ok(())
+}
+
+fn adapt_notice_event(src: &Value, out: &mut Map<String, Value>, self_id: i64) {
+    for key in ["group_id", "user_id", "operator_id", "message_id"] {
+        if let Some(v) = src.get(key) {
+            out.insert(key.into(), json!(id_to_i64(v)));
+        }
+    }
+    let sub_type = str_field(src, "sub_type");
+    let (notice_type, sub_type) = match str_field(src, "detail_type") {
+        "group_member_increase" => (
+            "group_increase",
+            if sub_type == "invite" {
+                "invite"
+            } else {
+                "approve"
+            },
+        ),
+        "group_member_decrease" => (
+            "group_decrease",
+            if sub_type == "kick" && id_to_i64(field(src, "user_id")) == self_id {
+                "kick_me"
+            } else if sub_type == "kick" {
+                "kick"
+            } else {
+                "leave"
+            },
+        ),
+        "group_message_delete" => ("group_recall", sub_type),
+        "friend_increase" => ("friend_add", sub_type),
+        "private_message_delete" => ("friend_recall", sub_type),
+        other => (other, sub_type),
+    };
+    out.insert("notice_type".into(), json!(notice_type));
+    out.insert("sub_type".into(), json!(sub_type));
+}
+
+fn adapt_meta_event(src: &Value, out: &mut Map<String, Value>) {
+    match str_field(src, "detail_type") {
+        "connect" => {
+            out.insert("meta_event_type".into(), json!("lifecycle"));
+            out.insert("sub_type".into(), json!("connect"));
+        }
+        "heartbeat" => {
+            out.insert("meta_event_type".into(), json!("heartbeat"));
+            out.insert(
+                "interval".into(),
+                json!(field(src, "interval").as_i64().unwrap_or(0)),
+            );
+            out.insert("status".into(), json!({}));
+        }
+        other => {
+            out.insert("meta_event_type".into(), json!(other));
+        }
+    }
+}
+
+// 将v12事件转换为v11格式，原有的其他字段会被保留
+pub fn adapt_event(json: Value) -> ResultType<Value> {
+    let mut out = json
+        .as_object()
+        .ok_or(anyhow!("Expected a JSON object!"))?
+        .clone();
+    let self_id = json
+        .get("self")
+        .map(|v| id_to_i64(field(v, "user_id")))
+        .unwrap_or(0);
+    out.insert("self_id".into(), json!(self_id.max(0)));
+    out.insert(
+        "time".into(),
+        json!(field(&json, "time").as_f64().unwrap_or(0.0) as u64),
+    );
+    match str_field(&json, "type") {
+        "message" => {
+            out.insert("post_type".into(), json!("message"));
+            adapt_message_event(&json, &mut out)?;
+        }
+        "notice" => {
+            out.insert("post_type".into(), json!("notice"));
+            adapt_notice_event(&json, &mut out, self_id);
+        }
+        "request" => {
+            out.insert("post_type".into(), json!("request"));
+            out.insert(
+                "request_type".into(),
+                json!(str_field(&json, "detail_type")),
+            );
+        }
+        "meta" => {
+            out.insert("post_type".into(), json!("meta_event"));
+            adapt_meta_event(&json, &mut out);
+        }
+        other => {
+            out.insert("post_type".into(), json!(other));
+        }
+    };
+    Ok(Value::Object(out))
+}
+
+fn adapt_outgoing_segment(seg: &Value) -> Value {
+    let data = field(seg, "data");
+    match str_field(seg, "type") {
+        "text" => json!({"type": "text", "data": {"text": str_field(data, "text")}}),
+        "at" => {
+            let qq = id_to_string(field(data, "qq"));
+            if qq == "all" {
+                json!({"type": "mention_all", "data": {}})
+            } else {
+                json!({"type": "mention", "data": {"user_id": qq}})
+            }
+        }
+        "image" => json!({"type": "image", "data": {"file_id": id_to_string(field(data, "file"))}}),
+        "record" => {
+            json!({"type": "voice", "data": {"file_id": id_to_string(field(data, "file"))}})
+        }
+        "video" => json!({"type": "video", "data": {"file_id": id_to_string(field(data, "file"))}}),
+        "reply" => json!({
+            "type": "reply",
+            "data": {"message_id": id_to_string(field(data, "id"))}
+        }),
+        "location" => json!({
+            "type": "location",
+            "data": {
+                "latitude": id_to_string(field(data, "lat")).parse::<f64>().unwrap_or(0.0),
+                "longitude": id_to_string(field(data, "lon")).parse::<f64>().unwrap_or(0.0),
+                "title": str_field(data, "title"),
+                "content": str_field(data, "content")
+            }
+        }),
+        // 其余消息段原样发送，由实现自行处理扩展类型
+        _ => seg.clone(),
+    }
+}
+
+fn adapt_outgoing_message(message: &Value, auto_escape: bool) -> Value {
+    let segments = match message {
+        Value::String(s) if auto_escape => vec![json!({"type": "text", "data": {"text": s}})],
+        Value::String(s) => parse_cq_code(s),
+        Value::Array(v) => v.clone(),
+        other => vec![other.clone()],
+    };
+    Value::Array(segments.iter().map(adapt_outgoing_segment).collect())
+}
+
+// 将v11的API调用转换为v12的动作名与参数
+// 没有对应关系的动作原样发出
+pub fn adapt_request(action: &str, params: &Value) -> (String, Value) {
+    let auto_escape = field(params, "auto_escape").as_bool().unwrap_or(false);
+    let message = || adapt_outgoing_message(field(params, "message"), auto_escape);
+    let id = |name: &str| id_to_string(field(params, name));
+    let (action, params) = match action {
+        "send_private_msg" => (
+            "send_message",
+            json!({"detail_type": "private", "user_id": id("user_id"), "message": message()}),
+        ),
+        "send_group_msg" => (
+            "send_message",
+            json!({"detail_type": "group", "group_id": id("group_id"), "message": message()}),
+        ),
+        "send_guild_channel_msg" => (
+            "send_message",
+            json!({
+                "detail_type": "channel",
+                "guild_id": id("guild_id"),
+                "channel_id": id("channel_id"),
+                "message": message()
+            }),
+        ),
+        "delete_msg" | "delete_message" => {
+            ("delete_message", json!({"message_id": id("message_id")}))
+        }
+        "get_login_info" => ("get_self_info", json!({})),
+        "get_stranger_info" => ("get_user_info", json!({"user_id": id("user_id")})),
+        "get_group_info" => ("get_group_info", json!({"group_id": id("group_id")})),
+        "get_group_member_info" => (
+            "get_group_member_info",
+            json!({"group_id": id("group_id"), "user_id": id("user_id")}),
+        ),
+        "get_group_member_list" => ("get_group_member_list", json!({"group_id": id("group_id")})),
+        "set_group_name" => (
+            "set_group_name",
+            json!({"group_id": id("group_id"), "group_name": field(params, "group_name")}),
+        ),
+        "set_group_leave" => ("leave_group", json!({"group_id": id("group_id")})),
+        "get_version_info" => ("get_version", json!({})),
+        other => (other, params.clone()),
+    };
+    (action.to_string(), params)
+}
+
+// 找出需要先通过upload_file上传的媒体消息段
+// 返回 (消息段下标, upload_file参数)
+pub fn pending_uploads(params: &Value) -> Vec<(usize, Value)> {
+    let mut out = vec![];
+    if let Some(Value::Array(segs)) = params.get("message") {
+        for (i, seg) in segs.iter().enumerate() {
+            let seg_type = str_field(seg, "type");
+            if !["image", "voice", "video"].contains(&seg_type) {
+                continue;
+            }
+            let file = str_field(field(seg, "data"), "file_id");
+            let name = format!("{}-{}", seg_type, i);
+            let upload = if let Some(data) = file.strip_prefix("base64://") {
+                json!({"type": "data", "name": name, "data": data})
+            } else if file.starts_with("http://") || file.starts_with("https://") {
+                json!({"type": "url", "name": name, "url": file})
+            } else if let Some(path) = file.strip_prefix("file://") {
+                json!({"type": "path", "name": name, "path": path})
+            } else {
+                continue;
+            };
+            out.push((i, upload));
+        }
+    }
+    return out;
+}
+
+pub fn apply_upload(params: &mut Value, index: usize, upload_resp: &Value) -> ResultType<()> {
+    let file_id = id_to_string(field(upload_resp, "file_id"));
+    let seg = params
+        .get_mut("message")
+        .and_then(|v| v.get_mut(index))
+        .ok_or(anyhow!("Invalid message segment index: {}", index))?;
+    seg["data"]["file_id"] = json!(file_id);
+    Ok(())
+}
+
+fn adapt_user_info(data: &Value) -> Value {
+    let mut out = data.as_object().cloned().unwrap_or_default();
+    out.insert("user_id".into(), json!(id_to_i64(field(data, "user_id"))));
+    out.insert("nickname".into(), json!(str_field(data, "user_name")));
+    out.insert("remark".into(), json!(str_field(data, "user_remark")));
+    out.insert("sex".into(), json!("unknown"));
+    out.insert("age".into(), json!(0));
+    Value::Object(out)
+}
+fn adapt_group_info(data: &Value) -> Value {
+    let mut out = data.as_object().cloned().unwrap_or_default();
+    out.insert("group_id".into(), json!(id_to_i64(field(data, "group_id"))));
+    out.insert("group_name".into(), json!(str_field(data, "group_name")));
+    out.insert("member_count".into(), json!(0));
+    out.insert("max_member_count".into(), json!(0));
+    Value::Object(out)
+}
+fn adapt_member_info(data: &Value, group_id: i64) -> Value {
+    let mut out = adapt_user_info(data)
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+    out.insert("group_id".into(), json!(group_id));
+    out.insert("card".into(), json!(str_field(data, "user_displayname")));
+    out.insert("join_time".into(), json!(0));
+    out.insert("last_sent_time".into(), json!(0));
+    out.insert("level".into(), json!(""));
+    // 无法获取群身份时不设置role，由GroupMemberInfo按普通成员处理
+    // 需要区分的插件可以使用get_member_role
+    let role = role_field(data);
+    if !role.is_null() {
+        out.insert("role".into(), role);
+    }
+    out.insert("unfriendly".into(), json!(false));
+    out.insert("title_expire_time".into(), json!(0));
+    out.insert("card_changeable".into(), json!(false));
+    Value::Object(out)
+}
+fn map_array(data: &Value, f: impl Fn(&Value) -> Value) -> Value {
+    match data {
+        Value::Array(v) => Value::Array(v.iter().map(f).collect()),
+        other => other.clone(),
+    }
+}
+
+// 将v12的响应转换为插件期望的v11格式，action与params为转换前的v11调用
+pub fn adapt_response(action: &str, params: &Value, data: Value) -> Value {
+    let group_id = id_to_i64(field(params, "group_id"));
+    match action {
+        "send_private_msg" | "send_group_msg" => {
+            json!({"message_id": id_to_i64(field(&data, "message_id"))})
+        }
+        "send_guild_channel_msg" => {
+            json!({"message_id": id_to_string(field(&data, "message_id"))})
+        }
+        "get_login_info" => json!({
+            "user_id": id_to_i64(field(&data, "user_id")),
+            "nickname": str_field(&data, "user_name")
+        }),
+        "get_stranger_info" => adapt_user_info(&data),
+        "get_friend_list" => map_array(&data, adapt_user_info),
+        "get_group_info" => adapt_group_info(&data),
+        "get_group_list" => map_array(&data, adapt_group_info),
+        "get_group_member_info" => adapt_member_info(&data, group_id),
+        "get_group_member_list" => map_array(&data, |v| adapt_member_info(v, group_id)),
+        "get_status" => {
+            let mut out = data.as_object().cloned().unwrap_or_default();
+            let online = match field(&data, "bots") {
+                Value::Array(bots) => bots.iter().any(|b| field(b, "online") == &json!(true)),
+                _ => false,
+            };
+            out.insert("online".into(), json!(online));
+            Value::Object(out)
+        }
+        "get_version_info" => {
+            let mut out = data.as_object().cloned().unwrap_or_default();
+            out.insert("app_name".into(), json!(str_field(&data, "impl")));
+            out.insert("app_version".into(), json!(str_field(&data, "version")));
+            out.insert(
+                "protocol_version".into(),
+                json!(str_field(&data, "onebot_version")),
+            );
+            Value::Object(out)
+        }
+        _ => data,
+    }
+}
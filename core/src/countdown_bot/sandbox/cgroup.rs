@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use bollard::Docker;
+
+// 宿主机上容器所在的cgroup，需要bot与容器引擎运行在同一主机上
+pub struct ContainerCgroup {
+    dir: PathBuf,
+    v2: bool,
+}
+
+impl ContainerCgroup {
+    // 通过容器主进程的 /proc/<pid>/cgroup 定位，容器需要处于运行状态
+    pub async fn locate(docker: &Docker, id: &str) -> Option<Self> {
+        let pid = docker
+            .inspect_container(id, None)
+            .await
+            .ok()?
+            .state?
+            .pid
+            .filter(|v| *v > 0)?;
+        let text = tokio::fs::read_to_string(format!("/proc/{}/cgroup", pid))
+            .await
+            .ok()?;
+        let cgroup = Self::from_proc_cgroup(&text)?;
+        if tokio::fs::metadata(&cgroup.dir).await.is_ok() {
+            Some(cgroup)
+        } else {
+            None
+        }
+    }
+    // 解析 /proc/<pid>/cgroup，cgroup v1 使用memory控制器所在的层级
+    pub fn from_proc_cgroup(text: &str) -> Option<Self> {
+        let mut unified = None;
+        for line in text.lines() {
+            let mut parts = line.splitn(3, ':');
+            let (hierarchy, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+            let path = path.trim_start_matches('/');
+            if controllers.split(',').any(|c| c == "memory") {
+                return Some(Self {
+                    dir: PathBuf::from("/sys/fs/cgroup/memory").join(path),
+                    v2: false,
+                });
+            }
+            if hierarchy == "0" && controllers.is_empty() {
+                unified = Some(Self {
+                    dir: PathBuf::from("/sys/fs/cgroup").join(path),
+                    v2: true,
+                });
+            }
+        }
+        unified
+    }
+    // 内存使用峰值(字节)，cgroup v2 需要内核 5.19 以上
+    pub async fn memory_peak(&self) -> Option<u64> {
+        let name = if self.v2 {
+            "memory.peak"
+        } else {
+            "memory.max_usage_in_bytes"
+        };
+        tokio::fs::read_to_string(self.dir.join(name))
+            .await
+            .ok()?
+            .trim()
+            .parse::<u64>()
+            .ok()
+    }
+    // 被OOM killer结束的进程数
+    pub async fn oom_kills(&self) -> Option<u64> {
+        let name = if self.v2 {
+            "memory.events"
+        } else {
+            "memory.oom_control"
+        };
+        let text = tokio::fs::read_to_string(self.dir.join(name)).await.ok()?;
+        parse_keyed_value(&text, "oom_kill")
+    }
+}
+
+// 解析形如 "key value" 的多行统计
+pub fn parse_keyed_value(text: &str, key: &str) -> Option<u64> {
+    text.lines().find_map(|line| {
+        let (k, v) = line.split_once(' ')?;
+        if k == key {
+            v.trim().parse::<u64>().ok()
+        } else {
+            None
+        }
+    })
+}
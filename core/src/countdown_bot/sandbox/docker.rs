@@ -0,0 +1,379 @@
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bollard::{
+    container::{
+        AttachContainerOptions, AttachContainerResults, Config, LogOutput, RemoveContainerOptions,
+    },
+    exec::{CreateExecOptions, StartExecResults},
+    image::CreateImageOptions,
+    models::{HostConfig, Mount, MountTypeEnum},
+    Docker,
+};
+use futures_util::stream::StreamExt;
+use log::{debug, info};
+use std::{sync::Arc, time::Instant};
+use tokio::io::AsyncWriteExt;
+
+use super::{
+    cgroup::ContainerCgroup,
+    pool::{ContainerPool, PoolConfig, PoolStats},
+    push_limited, InteractiveSession, ResourceLimits, SandboxBackend, SandboxBackendType,
+    SandboxConfig, SandboxRequest, SandboxResult,
+};
+use crate::countdown_bot::client::ResultType;
+
+// 容器以此常驻，命令通过exec执行，结束后容器仍存在，可以从宿主机读取其cgroup
+const IDLE_COMMAND: &str = "sleep infinity";
+// Docker与Podman共用，Podman通过其兼容Docker的socket访问
+pub struct DockerSandbox {
+    client: Docker,
+    pools: std::sync::Mutex<Vec<Arc<ContainerPool>>>,
+}
+
+impl DockerSandbox {
+    pub fn connect(config: &SandboxConfig) -> ResultType<Self> {
+        let socket = if !config.socket_path.is_empty() {
+            Some(config.socket_path.clone())
+        } else if config.backend == SandboxBackendType::Podman {
+            let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+                .map_err(|_| anyhow!("未设置XDG_RUNTIME_DIR，请手动指定Podman的socket路径"))?;
+            Some(format!("{}/podman/podman.sock", runtime_dir))
+        } else {
+            None
+        };
+        let client = match socket {
+            Some(path) => {
+                info!("Connecting to container engine at {}", path);
+                Docker::connect_with_unix(&path, 120, bollard::API_DEFAULT_VERSION)
+            }
+            None => Docker::connect_with_socket_defaults(),
+        }
+        .map_err(|e| anyhow!("初始化Docker时发生错误: {}", e))?;
+        Ok(Self {
+            client,
+            pools: Default::default(),
+        })
+    }
+    pub fn get_client(&self) -> &Docker {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl SandboxBackend for DockerSandbox {
+    async fn run(&self, request: &SandboxRequest) -> ResultType<SandboxResult> {
+        let docker = &self.client;
+        let pool = self
+            .pools
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.matches(request))
+            .cloned();
+        if let Some(pool) = pool {
+            if let Some(result) = pool.run(request).await {
+                return result;
+            }
+        }
+        let id = self
+            .create_container(request, IDLE_COMMAND.to_string(), false)
+            .await?;
+        let result = self.run_container(&id, request).await;
+        remove_container(docker, &id).await;
+        result
+    }
+    async fn spawn_interactive(&self, request: &SandboxRequest) -> ResultType<InteractiveSession> {
+        let docker = self.client.clone();
+        let id = self
+            .create_container(request, request.command.clone(), true)
+            .await?;
+        // 先附加再启动，避免丢失启动时的输出
+        let attached = docker
+            .attach_container(
+                &id,
+                Some(AttachContainerOptions::<String> {
+                    stdin: Some(true),
+                    stdout: Some(true),
+                    stderr: Some(true),
+                    stream: Some(true),
+                    ..Default::default()
+                }),
+            )
+            .await;
+        let AttachContainerResults {
+            mut output,
+            mut input,
+        } = match attached {
+            Ok(v) => v,
+            Err(e) => {
+                remove_container(&docker, &id).await;
+                return Err(anyhow!("附加到容器时发生错误: {}", e).into());
+            }
+        };
+        if let Err(e) = docker.start_container::<&str>(&id, None).await {
+            remove_container(&docker, &id).await;
+            return Err(anyhow!("启动容器时发生错误: {}", e).into());
+        }
+        let (session, mut inner) = InteractiveSession::new_pair();
+        let lifetime = request.timeout;
+        tokio::spawn(async move {
+            let deadline = tokio::time::sleep(lifetime);
+            tokio::pin!(deadline);
+            loop {
+                tokio::select! {
+                    data = inner.stdin.recv() => match data {
+                        Some(data) => {
+                            if input.write_all(&data).await.is_err() || input.flush().await.is_err() {
+                                break;
+                            }
+                        }
+                        None => break,
+                    },
+                    item = output.next() => match item {
+                        Some(Ok(LogOutput::Console { message }))
+                        | Some(Ok(LogOutput::StdOut { message }))
+                        | Some(Ok(LogOutput::StdErr { message })) => {
+                            inner.output.send(message.to_vec()).ok();
+                        }
+                        Some(Ok(_)) => {}
+                        _ => break,
+                    },
+                    _ = &mut inner.kill => break,
+                    _ = &mut deadline => break,
+                }
+            }
+            debug!("Interactive container {} finished", id);
+            remove_container(&docker, &id).await;
+            inner.done.send(()).ok();
+        });
+        Ok(session)
+    }
+    async fn warmup(
+        &self,
+        image: &str,
+        limits: &ResourceLimits,
+        config: &PoolConfig,
+    ) -> ResultType<()> {
+        if config.size == 0 {
+            return Ok(());
+        }
+        let docker = &self.client;
+        if docker.inspect_image(image).await.is_err() {
+            info!("Pulling image {}..", image);
+            let mut stream = Box::pin(docker.create_image(
+                Some(CreateImageOptions {
+                    from_image: image,
+                    ..Default::default()
+                }),
+                None,
+                None,
+            ));
+            while let Some(item) = stream.next().await {
+                item.map_err(|e| anyhow!("拉取镜像 {} 时发生错误: {}", image, e))?;
+            }
+        }
+        let pool = {
+            let mut pools = self.pools.lock().unwrap();
+            if pools
+                .iter()
+                .any(|p| p.image == image && &p.limits == limits)
+            {
+                return Ok(());
+            }
+            let pool = ContainerPool::new(docker.clone(), image, limits, config)?;
+            pools.push(pool.clone());
+            pool
+        };
+        info!("Warming up {} containers for {}", config.size, image);
+        pool.refill();
+        Ok(())
+    }
+    async fn shutdown(&self) {
+        let pools = std::mem::take(&mut *self.pools.lock().unwrap());
+        for pool in pools {
+            pool.shutdown().await;
+        }
+    }
+    fn pool_stats(&self) -> Vec<PoolStats> {
+        self.pools
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|p| p.stats())
+            .collect()
+    }
+}
+
+async fn remove_container(docker: &Docker, id: &str) {
+    docker
+        .remove_container(
+            id,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .ok();
+}
+
+impl DockerSandbox {
+    // 交互式容器不分配tty，以便分开处理输入输出
+    async fn create_container(
+        &self,
+        request: &SandboxRequest,
+        command: String,
+        interactive: bool,
+    ) -> ResultType<String> {
+        let limits = &request.limits;
+        let memory = limits.memory_mb * ((1 << 20) as i64);
+        let container = self
+            .client
+            .create_container::<String, String>(
+                None,
+                Config {
+                    image: Some(request.image.clone()),
+                    cmd: Some(vec!["sh".to_string(), "-c".to_string(), command]),
+                    open_stdin: Some(true),
+                    attach_stdin: Some(interactive),
+                    attach_stdout: Some(interactive),
+                    attach_stderr: Some(interactive),
+                    // detach
+                    tty: Some(!interactive),
+                    network_disabled: Some(true),
+                    working_dir: Some("/temp".to_string()),
+                    host_config: Some(HostConfig {
+                        mounts: Some(vec![Mount {
+                            target: Some("/temp".to_string()),
+                            source: Some(request.working_dir.to_str().unwrap().to_string()),
+                            read_only: Some(false),
+                            typ: Some(MountTypeEnum::BIND),
+                            ..Default::default()
+                        }]),
+                        memory: Some(memory),
+                        memory_swap: Some(memory),
+                        oom_kill_disable: Some(false),
+                        nano_cpus: Some((limits.cpu / 1e-9) as i64),
+                        pids_limit: Some(limits.pids),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| anyhow!("创建容器时发生错误: {}", e))?;
+        Ok(container.id)
+    }
+    async fn run_container(&self, id: &str, request: &SandboxRequest) -> ResultType<SandboxResult> {
+        let docker = &self.client;
+        docker
+            .start_container::<&str>(id, None)
+            .await
+            .map_err(|e| anyhow!("启动容器时发生错误: {}", e))?;
+        let cgroup = ContainerCgroup::locate(docker, id).await;
+        let mut result = exec_request(docker, id, request, cgroup.as_ref()).await?;
+        if let Some(cgroup) = cgroup.as_ref() {
+            result.memory_peak_bytes = cgroup.memory_peak().await;
+        }
+        Ok(result)
+    }
+}
+
+// 执行命令，返回输出、是否截断与退出码
+pub(crate) async fn exec(
+    docker: &Docker,
+    id: &str,
+    command: &str,
+    output_limit: usize,
+) -> ResultType<(Vec<u8>, bool, Option<i64>)> {
+    let exec = docker
+        .create_exec(
+            id,
+            CreateExecOptions {
+                cmd: Some(vec!["sh", "-c", command]),
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                working_dir: Some("/temp"),
+                ..Default::default()
+            },
+        )
+        .await?
+        .id;
+    let mut output = vec![];
+    let mut truncated = false;
+    if let StartExecResults::Attached {
+        output: mut stream, ..
+    } = docker.start_exec(&exec, None).await?
+    {
+        while let Some(Ok(item)) = stream.next().await {
+            let data = match item {
+                LogOutput::Console { message }
+                | LogOutput::StdOut { message }
+                | LogOutput::StdErr { message } => message,
+                _ => continue,
+            };
+            truncated |= push_limited(&mut output, &data, output_limit);
+        }
+    }
+    let exit_code = docker.inspect_exec(&exec).await?.exit_code;
+    Ok((output, truncated, exit_code))
+}
+
+// 容器状态中的oom_killed，无法获取时视为未被OOM killer结束
+async fn container_oom_killed(docker: &Docker, id: &str) -> bool {
+    match docker.inspect_container(id, None).await {
+        Ok(info) => info
+            .state
+            .and_then(|state| state.oom_killed)
+            .unwrap_or(false),
+        Err(e) => {
+            debug!("Failed to inspect container {}: {}", id, e);
+            false
+        }
+    }
+}
+
+// 在运行中的容器内执行请求的命令
+// exec中的进程被OOM killer结束时不会设置容器的oom_killed，因此比较执行前后cgroup中的oom_kill计数
+pub(crate) async fn exec_request(
+    docker: &Docker,
+    id: &str,
+    request: &SandboxRequest,
+    cgroup: Option<&ContainerCgroup>,
+) -> ResultType<SandboxResult> {
+    let oom_before = match cgroup {
+        Some(cgroup) => cgroup.oom_kills().await,
+        None => None,
+    };
+    let mut result = SandboxResult::default();
+    let begin = Instant::now();
+    match tokio::time::timeout(
+        request.timeout,
+        exec(docker, id, &request.command, request.limits.output_bytes),
+    )
+    .await
+    {
+        Ok(ret) => {
+            let (output, truncated, exit_code) = ret?;
+            result.time_ms = Some(begin.elapsed().as_millis() as u64);
+            result.output = output;
+            result.output_truncated = truncated;
+            result.exit_code = exit_code;
+        }
+        Err(_) => {
+            // 无法单独结束exec中的进程，容器会在之后被销毁
+            result.timed_out = true;
+        }
+    }
+    let oom_after = match cgroup {
+        Some(cgroup) => cgroup.oom_kills().await,
+        None => None,
+    };
+    result.oom_killed = match (oom_before, oom_after) {
+        (Some(before), Some(after)) => after > before,
+        // 无法读取cgroup时只能依赖容器的oom_killed，退出码137也可能是其他原因的SIGKILL，不作为判断依据
+        _ => container_oom_killed(docker, id).await,
+    };
+    debug!("Exec in container {} finished: {:?}", id, result.exit_code);
+    Ok(result)
+}
@@ -0,0 +1,186 @@
+use std::{path::Path, process::Stdio, time::Duration};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use log::info;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    process::Command,
+};
+
+use super::{
+    push_limited, InteractiveSession, SandboxBackend, SandboxBackendType, SandboxConfig,
+    SandboxRequest, SandboxResult,
+};
+use crate::countdown_bot::client::ResultType;
+
+// 适用于没有Docker的主机，使用bubblewrap或nsjail隔离进程
+// bubblewrap无法限制CPU占用，内存与进程数通过ulimit限制
+pub struct LocalSandbox {
+    config: SandboxConfig,
+}
+
+impl LocalSandbox {
+    pub fn new(config: &SandboxConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+    fn existing_binds(&self) -> Vec<&String> {
+        self.config
+            .readonly_binds
+            .iter()
+            .filter(|v| Path::new(v).exists())
+            .collect()
+    }
+    fn build_bwrap(&self, request: &SandboxRequest) -> Command {
+        let limits = &request.limits;
+        let mut cmd = Command::new(&self.config.bwrap_path);
+        cmd.args(["--unshare-all", "--die-with-parent", "--new-session"]);
+        for dir in self.existing_binds() {
+            cmd.args(["--ro-bind", dir, dir]);
+        }
+        cmd.args(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"]);
+        cmd.arg("--bind").arg(&request.working_dir).arg("/temp");
+        cmd.args(["--chdir", "/temp", "--", "sh", "-c"]);
+        cmd.arg(format!(
+            "ulimit -v {}; ulimit -u {}; ({}) 2>&1",
+            limits.memory_mb * 1024,
+            limits.pids,
+            request.command
+        ));
+        cmd
+    }
+    fn build_nsjail(&self, request: &SandboxRequest) -> Command {
+        let limits = &request.limits;
+        let mut cmd = Command::new(&self.config.nsjail_path);
+        cmd.args(["--mode", "o", "--quiet", "--iface_no_lo"]);
+        for dir in self.existing_binds() {
+            cmd.arg("-R").arg(dir);
+        }
+        cmd.arg("-B")
+            .arg(format!("{}:/temp", request.working_dir.display()));
+        cmd.args(["--cwd", "/temp"]);
+        cmd.arg("--time_limit")
+            .arg((request.timeout.as_secs() + 1).to_string());
+        if self.config.nsjail_use_cgroup {
+            cmd.arg("--cgroup_mem_max")
+                .arg((limits.memory_mb * (1 << 20)).to_string());
+            cmd.arg("--cgroup_pids_max").arg(limits.pids.to_string());
+            cmd.arg("--cgroup_cpu_ms_per_sec")
+                .arg(((limits.cpu * 1000.0) as i64).max(1).to_string());
+        } else {
+            cmd.arg("--rlimit_as").arg(limits.memory_mb.to_string());
+            cmd.arg("--rlimit_nproc").arg(limits.pids.to_string());
+        }
+        if !self.config.nsjail_seccomp_policy.is_empty() {
+            cmd.arg("--seccomp_policy")
+                .arg(&self.config.nsjail_seccomp_policy);
+        }
+        cmd.args(["--", "/bin/sh", "-c"]);
+        cmd.arg(format!("({}) 2>&1", request.command));
+        cmd
+    }
+    fn build_command(&self, request: &SandboxRequest) -> Command {
+        let cmd = match self.config.backend {
+            SandboxBackendType::Nsjail => self.build_nsjail(request),
+            _ => self.build_bwrap(request),
+        };
+        info!("Local sandbox command: {:?}", cmd);
+        cmd
+    }
+}
+
+#[async_trait]
+impl SandboxBackend for LocalSandbox {
+    async fn run(&self, request: &SandboxRequest) -> ResultType<SandboxResult> {
+        let mut cmd = self.build_command(request);
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("启动沙箱进程时发生错误: {}", e))?;
+        let mut stdout = child.stdout.take().unwrap();
+        let output_limit = request.limits.output_bytes;
+        // 超出限制后继续读取并丢弃，避免子进程阻塞在写入上
+        let reader = tokio::spawn(async move {
+            let mut output = vec![];
+            let mut truncated = false;
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = stdout.read(&mut buf).await {
+                if n == 0 {
+                    break;
+                }
+                truncated |= push_limited(&mut output, &buf[..n], output_limit);
+            }
+            (output, truncated)
+        });
+        let mut result = SandboxResult::default();
+        let begin = std::time::Instant::now();
+        match tokio::time::timeout(request.timeout, child.wait()).await {
+            Ok(status) => {
+                result.time_ms = Some(begin.elapsed().as_millis() as u64);
+                if let Ok(status) = status {
+                    use std::os::unix::process::ExitStatusExt;
+                    result.exit_code = status.code().map(|v| v as i64);
+                    result.signal = status.signal();
+                }
+            }
+            Err(_) => {
+                result.timed_out = true;
+                child.kill().await.ok();
+            }
+        };
+        if let Ok(Ok((output, truncated))) =
+            tokio::time::timeout(Duration::from_secs(1), reader).await
+        {
+            result.output = output;
+            result.output_truncated = truncated;
+        }
+        Ok(result)
+    }
+    async fn spawn_interactive(&self, request: &SandboxRequest) -> ResultType<InteractiveSession> {
+        let mut child = self
+            .build_command(request)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("启动沙箱进程时发生错误: {}", e))?;
+        let mut stdin = child.stdin.take().unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+        let (session, mut inner) = InteractiveSession::new_pair();
+        let lifetime = request.timeout;
+        tokio::spawn(async move {
+            let deadline = tokio::time::sleep(lifetime);
+            tokio::pin!(deadline);
+            let mut buf = [0u8; 4096];
+            loop {
+                tokio::select! {
+                    data = inner.stdin.recv() => match data {
+                        Some(data) => {
+                            if stdin.write_all(&data).await.is_err() || stdin.flush().await.is_err() {
+                                break;
+                            }
+                        }
+                        None => break,
+                    },
+                    n = stdout.read(&mut buf) => match n {
+                        Ok(n) if n > 0 => {
+                            inner.output.send(buf[..n].to_vec()).ok();
+                        }
+                        _ => break,
+                    },
+                    _ = &mut inner.kill => break,
+                    _ = &mut deadline => break,
+                }
+            }
+            child.kill().await.ok();
+            inner.done.send(()).ok();
+        });
+        Ok(session)
+    }
+}
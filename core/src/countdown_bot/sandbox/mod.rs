@@ -0,0 +1,203 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use super::client::ResultType;
+
+pub mod cgroup;
+pub mod docker;
+pub mod local;
+pub mod pool;
+
+use pool::{PoolConfig, PoolStats};
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxBackendType {
+    Docker,
+    // rootless Podman，通过其兼容Docker的API socket访问
+    Podman,
+    Bubblewrap,
+    Nsjail,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SandboxConfig {
+    pub backend: SandboxBackendType,
+    // Docker/Podman的socket路径，为空时使用默认位置
+    pub socket_path: String,
+    pub bwrap_path: String,
+    pub nsjail_path: String,
+    // nsjail的seccomp策略文件(kafel)，为空时不使用
+    pub nsjail_seccomp_policy: String,
+    // nsjail是否使用cgroup限制内存、CPU与进程数，需要相应权限
+    pub nsjail_use_cgroup: bool,
+    // 本地沙箱内只读挂载的系统目录，不存在的目录会被忽略
+    pub readonly_binds: Vec<String>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            backend: SandboxBackendType::Docker,
+            socket_path: String::new(),
+            bwrap_path: "bwrap".to_string(),
+            nsjail_path: "nsjail".to_string(),
+            nsjail_seccomp_policy: String::new(),
+            nsjail_use_cgroup: false,
+            readonly_binds: ["/usr", "/bin", "/lib", "/lib64", "/etc"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ResourceLimits {
+    pub memory_mb: i64,
+    // CPU核数，可以为小数
+    pub cpu: f64,
+    pub pids: i64,
+    // 收集的输出(标准输出与标准错误)的最大字节数
+    pub output_bytes: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            memory_mb: 50,
+            cpu: 0.4,
+            pids: 64,
+            output_bytes: 64 * 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SandboxRequest {
+    // 仅Docker/Podman使用
+    pub image: String,
+    // 以 sh -c 执行的命令
+    pub command: String,
+    // 挂载到沙箱内 /temp 的目录，命令在此目录下执行
+    pub working_dir: PathBuf,
+    pub limits: ResourceLimits,
+    pub timeout: Duration,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SandboxResult {
+    pub output: Vec<u8>,
+    pub output_truncated: bool,
+    pub exit_code: Option<i64>,
+    // 被信号终止时的信号编号，仅本地沙箱可获取
+    pub signal: Option<i32>,
+    pub timed_out: bool,
+    // 因超出内存限制被终止
+    pub oom_killed: bool,
+    pub time_ms: Option<u64>,
+    // 内存使用峰值，仅Docker/Podman在宿主机cgroup可读时可获取
+    pub memory_peak_bytes: Option<u64>,
+}
+
+impl SandboxResult {
+    pub fn output_string(&self) -> String {
+        String::from_utf8_lossy(&self.output).to_string()
+    }
+}
+
+// 长时间运行的交互式进程，request.timeout为其最长存活时间
+// 丢弃后进程会被结束
+pub struct InteractiveSession {
+    // 写入进程的标准输入
+    pub stdin: mpsc::UnboundedSender<Vec<u8>>,
+    // 进程的标准输出与标准错误，进程结束后关闭
+    pub output: mpsc::UnboundedReceiver<Vec<u8>>,
+    _kill: oneshot::Sender<()>,
+    // 后端完成清理(如删除容器)后关闭
+    done: oneshot::Receiver<()>,
+}
+
+// 交由后端的后台任务使用的另一端
+pub(crate) struct InteractiveSessionInner {
+    pub stdin: mpsc::UnboundedReceiver<Vec<u8>>,
+    pub output: mpsc::UnboundedSender<Vec<u8>>,
+    pub kill: oneshot::Receiver<()>,
+    pub done: oneshot::Sender<()>,
+}
+
+impl InteractiveSession {
+    pub(crate) fn new_pair() -> (Self, InteractiveSessionInner) {
+        let (stdin_tx, stdin_rx) = mpsc::unbounded_channel();
+        let (output_tx, output_rx) = mpsc::unbounded_channel();
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let (done_tx, done_rx) = oneshot::channel();
+        (
+            Self {
+                stdin: stdin_tx,
+                output: output_rx,
+                _kill: kill_tx,
+                done: done_rx,
+            },
+            InteractiveSessionInner {
+                stdin: stdin_rx,
+                output: output_tx,
+                kill: kill_rx,
+                done: done_tx,
+            },
+        )
+    }
+    // 结束进程，并等待后端完成清理
+    pub async fn kill(self) {
+        let Self {
+            stdin,
+            output,
+            _kill,
+            done,
+        } = self;
+        drop((stdin, output, _kill));
+        done.await.ok();
+    }
+}
+
+#[async_trait]
+pub trait SandboxBackend: Send + Sync {
+    async fn run(&self, request: &SandboxRequest) -> ResultType<SandboxResult>;
+    async fn spawn_interactive(&self, request: &SandboxRequest) -> ResultType<InteractiveSession>;
+    // 为指定镜像与资源限制预热容器池，仅Docker/Podman支持
+    async fn warmup(
+        &self,
+        _image: &str,
+        _limits: &ResourceLimits,
+        _config: &PoolConfig,
+    ) -> ResultType<()> {
+        Ok(())
+    }
+    fn pool_stats(&self) -> Vec<PoolStats> {
+        vec![]
+    }
+    // 释放容器池等资源，在插件关闭时调用
+    async fn shutdown(&self) {}
+}
+
+pub type SandboxWrapped = Arc<dyn SandboxBackend>;
+
+pub fn create_sandbox(config: &SandboxConfig) -> ResultType<SandboxWrapped> {
+    Ok(match config.backend {
+        SandboxBackendType::Docker | SandboxBackendType::Podman => {
+            Arc::new(docker::DockerSandbox::connect(config)?)
+        }
+        SandboxBackendType::Bubblewrap | SandboxBackendType::Nsjail => {
+            Arc::new(local::LocalSandbox::new(config))
+        }
+    })
+}
+
+pub(crate) fn push_limited(buf: &mut Vec<u8>, data: &[u8], limit: usize) -> bool {
+    let remain = limit.saturating_sub(buf.len());
+    buf.extend_from_slice(&data[..data.len().min(remain)]);
+    data.len() > remain
+}
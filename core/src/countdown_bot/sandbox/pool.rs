@@ -0,0 +1,378 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::anyhow;
+use bollard::{
+    container::{Config, RemoveContainerOptions},
+    models::{HostConfig, Mount, MountTypeEnum},
+    Docker,
+};
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cgroup::ContainerCgroup,
+    docker::{exec, exec_request},
+    ResourceLimits, SandboxRequest, SandboxResult,
+};
+use crate::countdown_bot::client::ResultType;
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PoolConfig {
+    // 预热的容器数量，为0时不使用容器池
+    pub size: usize,
+    // 每个容器最多使用的次数，达到后销毁并重新创建
+    pub max_uses: u32,
+    // 空闲时暂停容器，避免占用CPU
+    pub pause_idle: bool,
+    // 创建容器后执行一次的预热命令，为空则不执行
+    pub warmup_command: String,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 2,
+            max_uses: 1,
+            pause_idle: true,
+            warmup_command: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PoolStats {
+    pub image: String,
+    pub memory_mb: i64,
+    pub size: usize,
+    pub idle: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub created: u64,
+    pub recycled: u64,
+}
+
+impl std::fmt::Display for PoolStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "容器池 {}({}MB): 空闲 {}/{}，命中 {}，未命中 {}，已创建 {}，已回收 {}",
+            self.image,
+            self.memory_mb,
+            self.idle,
+            self.size,
+            self.hits,
+            self.misses,
+            self.created,
+            self.recycled
+        )
+    }
+}
+
+struct PooledContainer {
+    id: String,
+    // 挂载到容器内 /temp 的目录
+    dir: PathBuf,
+    uses: u32,
+    // 宿主机上的cgroup，用于统计OOM
+    cgroup: Option<ContainerCgroup>,
+}
+
+// 同一镜像与资源限制的预热容器，容器以 sleep 常驻，每次使用时通过exec执行命令
+pub struct ContainerPool {
+    docker: Docker,
+    pub image: String,
+    pub limits: ResourceLimits,
+    config: PoolConfig,
+    root_dir: PathBuf,
+    idle: Mutex<VecDeque<PooledContainer>>,
+    // 正在创建中的容器数量
+    creating: AtomicUsize,
+    // 已关闭的容器池不再补充容器，归还的容器直接销毁
+    closed: AtomicBool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    created: AtomicU64,
+    recycled: AtomicU64,
+}
+
+impl ContainerPool {
+    pub fn new(
+        docker: Docker,
+        image: &str,
+        limits: &ResourceLimits,
+        config: &PoolConfig,
+    ) -> ResultType<Arc<Self>> {
+        let root_dir =
+            std::env::temp_dir().join(format!("countdown_pool_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root_dir)?;
+        Ok(Arc::new(Self {
+            docker,
+            image: image.to_string(),
+            limits: limits.clone(),
+            config: config.clone(),
+            root_dir,
+            idle: Mutex::new(VecDeque::new()),
+            creating: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            created: AtomicU64::new(0),
+            recycled: AtomicU64::new(0),
+        }))
+    }
+    pub fn matches(&self, request: &SandboxRequest) -> bool {
+        self.image == request.image && self.limits == request.limits
+    }
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            image: self.image.clone(),
+            memory_mb: self.limits.memory_mb,
+            size: self.config.size,
+            idle: self.idle.lock().unwrap().len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            created: self.created.load(Ordering::Relaxed),
+            recycled: self.recycled.load(Ordering::Relaxed),
+        }
+    }
+    // 补充容器直到达到设定数量
+    pub fn refill(self: &Arc<Self>) {
+        if self.closed.load(Ordering::SeqCst) {
+            return;
+        }
+        loop {
+            let current = self.idle.lock().unwrap().len() + self.creating.load(Ordering::SeqCst);
+            if current >= self.config.size {
+                break;
+            }
+            self.creating.fetch_add(1, Ordering::SeqCst);
+            let pool = self.clone();
+            tokio::spawn(async move {
+                match pool.create().await {
+                    Ok(container) if pool.closed.load(Ordering::SeqCst) => {
+                        pool.destroy(container).await
+                    }
+                    Ok(container) => pool.idle.lock().unwrap().push_back(container),
+                    Err(e) => error!("Failed to create pooled container: {}", e),
+                }
+                pool.creating.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    }
+    async fn create(&self) -> ResultType<PooledContainer> {
+        let dir = self.root_dir.join(uuid::Uuid::new_v4().to_string());
+        tokio::fs::create_dir_all(&dir).await?;
+        let limits = &self.limits;
+        let memory = limits.memory_mb * ((1 << 20) as i64);
+        let id = self
+            .docker
+            .create_container::<String, String>(
+                None,
+                Config {
+                    image: Some(self.image.clone()),
+                    cmd: Some(vec!["sleep".to_string(), "infinity".to_string()]),
+                    network_disabled: Some(true),
+                    working_dir: Some("/temp".to_string()),
+                    host_config: Some(HostConfig {
+                        mounts: Some(vec![Mount {
+                            target: Some("/temp".to_string()),
+                            source: Some(dir.to_str().unwrap().to_string()),
+                            read_only: Some(false),
+                            typ: Some(MountTypeEnum::BIND),
+                            ..Default::default()
+                        }]),
+                        memory: Some(memory),
+                        memory_swap: Some(memory),
+                        oom_kill_disable: Some(false),
+                        nano_cpus: Some((limits.cpu / 1e-9) as i64),
+                        pids_limit: Some(limits.pids),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| anyhow!("创建容器时发生错误: {}", e))?
+            .id;
+        let mut container = PooledContainer {
+            id,
+            dir,
+            uses: 0,
+            cgroup: None,
+        };
+        if let Err(e) = self.prepare(&mut container).await {
+            self.destroy(container).await;
+            return Err(e);
+        }
+        self.created.fetch_add(1, Ordering::Relaxed);
+        debug!("Pooled container {} created", container.id);
+        Ok(container)
+    }
+    async fn prepare(&self, container: &mut PooledContainer) -> ResultType<()> {
+        self.docker
+            .start_container::<&str>(&container.id, None)
+            .await
+            .map_err(|e| anyhow!("启动容器时发生错误: {}", e))?;
+        container.cgroup = ContainerCgroup::locate(&self.docker, &container.id).await;
+        if !self.config.warmup_command.is_empty() {
+            exec(
+                &self.docker,
+                &container.id,
+                &self.config.warmup_command,
+                usize::MAX,
+            )
+            .await?;
+        }
+        if self.config.pause_idle {
+            self.docker.pause_container(&container.id).await?;
+        }
+        Ok(())
+    }
+    async fn destroy(&self, container: PooledContainer) {
+        self.docker
+            .remove_container(
+                &container.id,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .ok();
+        tokio::fs::remove_dir_all(&container.dir).await.ok();
+        self.recycled.fetch_add(1, Ordering::Relaxed);
+    }
+    // 取出一个健康的容器，没有可用容器时返回None
+    async fn acquire(&self) -> Option<PooledContainer> {
+        loop {
+            let container = self.idle.lock().unwrap().pop_front()?;
+            if self.config.pause_idle {
+                if let Err(e) = self.docker.unpause_container(&container.id).await {
+                    error!("Failed to unpause container {}: {}", container.id, e);
+                    self.destroy(container).await;
+                    continue;
+                }
+            }
+            let running = self
+                .docker
+                .inspect_container(&container.id, None)
+                .await
+                .ok()
+                .and_then(|v| v.state)
+                .and_then(|v| v.running)
+                .unwrap_or(false);
+            if running {
+                return Some(container);
+            }
+            info!("Pooled container {} is unhealthy, recycling", container.id);
+            self.destroy(container).await;
+        }
+    }
+    // 删除空闲容器与容器池的工作目录，正在使用中的容器会在归还时销毁
+    pub async fn shutdown(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        while self.creating.load(Ordering::SeqCst) > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        let containers = std::mem::take(&mut *self.idle.lock().unwrap());
+        info!(
+            "Removing {} pooled containers of {}",
+            containers.len(),
+            self.image
+        );
+        for container in containers {
+            self.destroy(container).await;
+        }
+        tokio::fs::remove_dir_all(&self.root_dir).await.ok();
+    }
+    // 容器池无可用容器时返回None，由调用者创建新容器执行
+    // 容器池中的容器会被多次使用，因此不统计内存峰值
+    pub async fn run(
+        self: &Arc<Self>,
+        request: &SandboxRequest,
+    ) -> Option<ResultType<SandboxResult>> {
+        let container = match self.acquire().await {
+            Some(v) => v,
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                self.refill();
+                return None;
+            }
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        let result = self.run_in(&container, request).await;
+        let mut container = container;
+        container.uses += 1;
+        let reusable = matches!(&result, Ok(r) if !r.timed_out && !r.oom_killed)
+            && !self.closed.load(Ordering::SeqCst)
+            && container.uses < self.config.max_uses
+            && clear_dir(&container.dir).await.is_ok()
+            && (!self.config.pause_idle
+                || self.docker.pause_container(&container.id).await.is_ok());
+        if reusable {
+            self.idle.lock().unwrap().push_back(container);
+        } else {
+            self.destroy(container).await;
+        }
+        self.refill();
+        Some(result)
+    }
+    async fn run_in(
+        &self,
+        container: &PooledContainer,
+        request: &SandboxRequest,
+    ) -> ResultType<SandboxResult> {
+        copy_dir(&request.working_dir, &container.dir).await?;
+        let result = exec_request(
+            &self.docker,
+            &container.id,
+            request,
+            container.cgroup.as_ref(),
+        )
+        .await?;
+        copy_dir(&container.dir, &request.working_dir).await?;
+        Ok(result)
+    }
+}
+
+// 只复制普通文件与目录，容器内创建的符号链接等特殊文件会被跳过
+// 否则复制时会跟随链接读取或覆盖宿主机上的文件
+async fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut stack = vec![(from.to_path_buf(), to.to_path_buf())];
+    while let Some((src, dst)) = stack.pop() {
+        tokio::fs::create_dir_all(&dst).await?;
+        let mut entries = tokio::fs::read_dir(&src).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let target = dst.join(entry.file_name());
+            // DirEntry::file_type不跟随符号链接
+            let file_type = entry.file_type().await?;
+            if let Ok(meta) = tokio::fs::symlink_metadata(&target).await {
+                if !meta.is_file() && !(meta.is_dir() && file_type.is_dir()) {
+                    if meta.is_dir() {
+                        tokio::fs::remove_dir_all(&target).await?;
+                    } else {
+                        tokio::fs::remove_file(&target).await?;
+                    }
+                }
+            }
+            if file_type.is_dir() {
+                stack.push((entry.path(), target));
+            } else if file_type.is_file() {
+                tokio::fs::copy(entry.path(), target).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn clear_dir(dir: &Path) -> std::io::Result<()> {
+    tokio::fs::remove_dir_all(dir).await?;
+    tokio::fs::create_dir_all(dir).await
+}
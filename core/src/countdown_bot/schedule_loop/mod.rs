@@ -6,7 +6,7 @@ use tokio::sync::Mutex;
 
 use self::handler::ScheduleLoopHandler;
 
-use super::{bot::StopSignalReceiverType, plugin::BotPluginWrapped};
+use super::{bot::StopSignalReceiverType, inflight::InflightTracker, plugin::BotPluginWrapped};
 pub mod handler;
 // #[derive(Clone)]
 pub struct ScheduleItemWrapper {
@@ -21,6 +21,7 @@ pub struct ScheduleLoopManager {
     pub schedules: Vec<ScheduleItemWrapper>,
     current_plugin: Option<BotPluginWrapped>,
     pub stop_signal_receiver: Option<StopSignalReceiverType>,
+    inflight: InflightTracker,
 }
 impl ScheduleLoopManager {
     pub fn set_current_plugin(&mut self, plugin_wrapper: BotPluginWrapped) {
@@ -29,11 +30,15 @@ impl ScheduleLoopManager {
     pub fn set_stop_signal_receiver(&mut self, receiver: StopSignalReceiverType) {
         self.stop_signal_receiver = Some(receiver);
     }
+    pub fn set_inflight_tracker(&mut self, inflight: InflightTracker) {
+        self.inflight = inflight;
+    }
     pub fn new() -> Self {
         Self {
             schedules: vec![],
             current_plugin: None,
             stop_signal_receiver: None,
+            inflight: InflightTracker::default(),
         }
     }
     pub fn register(
@@ -69,7 +74,9 @@ impl ScheduleLoopManager {
                     let plugin_inst = item.plugin.clone();
                     let name_cloned = item.name.clone();
                     let handler_ref = item.handler.clone();
+                    let inflight_guard = self.inflight.enter();
                     tokio::spawn(async move {
+                        let _inflight_guard = inflight_guard;
                         if let Err(e) = handler_ref
                             .lock()
                             .await
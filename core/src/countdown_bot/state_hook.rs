@@ -1,6 +1,6 @@
 use std::collections::BTreeSet;
 
-use super::{client::ResultType, plugin::PluginManager};
+use super::{audit::CommandAuditLog, client::ResultType, plugin::PluginManager};
 #[derive(Default)]
 pub struct StateHookManager {
     pub hooks: BTreeSet<String>,
@@ -14,7 +14,17 @@ impl StateHookManager {
     pub fn register_state_hook(&mut self) {
         self.hooks.insert(self.curr_plugin.clone());
     }
-    pub async fn create_state(&self, plugin_manager: &PluginManager) -> ResultType<String> {
+    /*
+    依次调用各插件的on_state_hook
+        启用了指令日志时，在每个插件的状态后附加最近days天的指令调用统计
+    */
+    pub async fn create_state(
+        &self,
+        plugin_manager: &PluginManager,
+        audit: Option<&CommandAuditLog>,
+        days: i64,
+    ) -> ResultType<String> {
+        let since = chrono::Local::now().timestamp() - days * 24 * 60 * 60;
         let mut buf: Vec<String> = vec![];
         for plugin_name in self.hooks.iter() {
             let plugin = plugin_manager.plugins.get(plugin_name).unwrap();
@@ -28,6 +38,14 @@ impl StateHookManager {
                     .on_state_hook()
                     .await?,
             );
+            if let Some(audit) = audit {
+                if let Some(usage) = audit.single_plugin_usage(plugin_name, since).await? {
+                    buf.push(format!(
+                        "近{}天指令调用: {}次, 失败{}次, 平均耗时{:.0}ms",
+                        days, usage.calls, usage.failures, usage.avg_duration_ms
+                    ));
+                }
+            }
         }
         return Ok(buf.join("\n"));
     }
@@ -0,0 +1,463 @@
+use std::fmt::Write;
+
+use anyhow::anyhow;
+
+use super::{
+    config::TextRenderProps,
+    message::segment::{ImageData, MessageSegment},
+};
+
+const PADDING: f64 = 16.0;
+const LINE_SPACING: f64 = 1.5;
+const CELL_PADDING: f64 = 6.0;
+const HEADING_SCALE: [f64; 3] = [1.5, 1.3, 1.15];
+// 图像的最大高度(像素)，超出时渲染失败，由调用方改为发送文本
+const MAX_HEIGHT: f64 = 8192.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextBlock {
+    // (级别, 文本)，级别从1开始
+    Heading(usize, String),
+    Paragraph(String),
+    // (标记, 文本)，无序列表的标记为•
+    ListItem(String, String),
+    Code(Vec<String>),
+    // 第一行为表头
+    Table(Vec<Vec<String>>),
+    Rule,
+    Blank,
+}
+
+fn is_table_separator(cells: &[String]) -> bool {
+    cells
+        .iter()
+        .all(|v| !v.is_empty() && v.chars().all(|c| c == '-' || c == ':' || c == ' '))
+}
+
+fn split_table_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(|v| v.trim().to_string()).collect()
+}
+
+/*
+将文本解析为段落、标题、列表、代码块与表格
+仅支持常见的类Markdown写法，其余行均按原样作为段落，保留换行
+*/
+pub fn parse_blocks(text: &str) -> Vec<TextBlock> {
+    let mut result = vec![];
+    let lines = text.lines().map(|v| v.trim_end()).collect::<Vec<&str>>();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            let mut code = vec![];
+            i += 1;
+            while i < lines.len() && !lines[i].trim_start().starts_with("```") {
+                code.push(lines[i].replace('\t', "    "));
+                i += 1;
+            }
+            result.push(TextBlock::Code(code));
+            i += 1;
+            continue;
+        }
+        if trimmed.starts_with('|') {
+            let mut rows = vec![];
+            while i < lines.len() && lines[i].trim_start().starts_with('|') {
+                let cells = split_table_row(lines[i]);
+                if !is_table_separator(&cells) {
+                    rows.push(cells);
+                }
+                i += 1;
+            }
+            result.push(TextBlock::Table(rows));
+            continue;
+        }
+        i += 1;
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        let block = if trimmed.is_empty() {
+            TextBlock::Blank
+        } else if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            TextBlock::Heading(level, trimmed[level..].trim().to_string())
+        } else if trimmed.len() >= 3
+            && ["-", "*", "_"]
+                .iter()
+                .any(|c| trimmed.chars().all(|v| v.to_string() == *c))
+        {
+            TextBlock::Rule
+        } else if let Some(v) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|p| trimmed.strip_prefix(p))
+        {
+            TextBlock::ListItem("•".to_string(), v.trim().to_string())
+        } else if let Some((number, rest)) = trimmed
+            .split_once(". ")
+            .filter(|(a, _)| !a.is_empty() && a.len() <= 3 && a.chars().all(|c| c.is_ascii_digit()))
+        {
+            TextBlock::ListItem(format!("{}.", number), rest.trim().to_string())
+        } else {
+            TextBlock::Paragraph(line.replace('\t', "    "))
+        };
+        result.push(block);
+    }
+    result
+}
+
+// 中日韩文字、全角符号与表情按一个字宽计算，其余按半个字宽左右计算
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD)
+}
+
+fn char_width(c: char, font_size: f64, monospace: bool) -> f64 {
+    if is_wide(c) {
+        font_size
+    } else if monospace {
+        font_size * 0.6
+    } else {
+        font_size * 0.55
+    }
+}
+
+fn text_width(text: &str, font_size: f64, monospace: bool) -> f64 {
+    text.chars()
+        .map(|c| char_width(c, font_size, monospace))
+        .sum()
+}
+
+// 按宽度折行，尽量在空格处断开英文单词
+fn wrap_text(text: &str, max_width: f64, font_size: f64, monospace: bool) -> Vec<String> {
+    let mut result = vec![];
+    let mut line: Vec<char> = vec![];
+    let mut width = 0.0;
+    for c in text.chars() {
+        let w = char_width(c, font_size, monospace);
+        if width + w > max_width && !line.is_empty() {
+            let break_at = if c == ' ' || is_wide(c) {
+                None
+            } else {
+                line.iter()
+                    .rposition(|v| *v == ' ')
+                    .filter(|v| *v > 0 && line[v + 1..].iter().all(|c| !is_wide(*c)))
+            };
+            match break_at {
+                Some(pos) => {
+                    result.push(line[..pos].iter().collect());
+                    line = line[pos + 1..].to_vec();
+                }
+                None => result.push(std::mem::take(&mut line).into_iter().collect()),
+            }
+            width = line
+                .iter()
+                .map(|v| char_width(*v, font_size, monospace))
+                .sum();
+            if c == ' ' && line.is_empty() {
+                continue;
+            }
+        }
+        line.push(c);
+        width += w;
+    }
+    result.push(line.into_iter().collect());
+    result
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+struct SvgWriter<'a> {
+    props: &'a TextRenderProps,
+    body: String,
+}
+
+impl<'a> SvgWriter<'a> {
+    // top为该行的上边缘
+    fn text(&mut self, x: f64, top: f64, text: &str, font_size: f64, bold: bool, monospace: bool) {
+        if text.is_empty() {
+            return;
+        }
+        let line_height = font_size * LINE_SPACING;
+        write!(
+            self.body,
+            r#"<text x="{:.1}" y="{:.1}" font-size="{:.1}" font-family="{}"{} xml:space="preserve">{}</text>"#,
+            x,
+            top + (line_height + font_size * 0.7) / 2.0,
+            font_size,
+            escape_xml(if monospace {
+                &self.props.monospace_font_family
+            } else {
+                &self.props.font_family
+            }),
+            if bold { r#" font-weight="bold""# } else { "" },
+            escape_xml(text)
+        )
+        .unwrap();
+    }
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: &str, stroke: Option<&str>) {
+        write!(
+            self.body,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"{}/>"#,
+            x,
+            y,
+            width,
+            height,
+            fill,
+            stroke
+                .map(|v| format!(r#" stroke="{}" stroke-width="1""#, v))
+                .unwrap_or_default()
+        )
+        .unwrap();
+    }
+}
+
+// 不折行时各块所需的宽度
+fn natural_width(block: &TextBlock, font_size: f64) -> f64 {
+    match block {
+        TextBlock::Heading(level, text) => {
+            text_width(text, font_size * HEADING_SCALE[(level - 1).min(2)], false)
+        }
+        TextBlock::Paragraph(text) => text_width(text, font_size, false),
+        TextBlock::ListItem(marker, text) => {
+            text_width(marker, font_size, false) + font_size + text_width(text, font_size, false)
+        }
+        TextBlock::Code(lines) => {
+            lines
+                .iter()
+                .map(|v| text_width(v, font_size, true))
+                .fold(0.0, f64::max)
+                + CELL_PADDING * 2.0
+        }
+        TextBlock::Table(rows) => column_widths(rows, font_size).iter().sum(),
+        TextBlock::Rule | TextBlock::Blank => 0.0,
+    }
+}
+
+fn column_widths(rows: &[Vec<String>], font_size: f64) -> Vec<f64> {
+    let columns = rows.iter().map(|v| v.len()).max().unwrap_or(0);
+    (0..columns)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|v| text_width(v, font_size, false))
+                .fold(font_size, f64::max)
+                + CELL_PADDING * 2.0
+        })
+        .collect()
+}
+
+lazy_static::lazy_static! {
+    // 加载系统字体较慢，只在第一次渲染时加载
+    static ref FONT_DB: usvg::fontdb::Database = {
+        let mut db = usvg::fontdb::Database::new();
+        db.load_system_fonts();
+        db
+    };
+}
+
+// 将SVG渲染为PNG，font_family为空时使用usvg的默认字体
+pub fn svg_to_png(svg: &str, font_family: &str) -> anyhow::Result<Vec<u8>> {
+    let mut opt = usvg::Options::default();
+    if !font_family.is_empty() {
+        opt.font_family = font_family.to_string();
+    }
+    let mut opt_ref = opt.to_ref();
+    opt_ref.fontdb = &FONT_DB;
+    let tree = usvg::Tree::from_data(svg.as_bytes(), &opt_ref)
+        .map_err(|e| anyhow!("生成SVG失败: {}", e))?;
+    let size = tree.svg_node().size.to_screen_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).ok_or(anyhow!(
+        "图像尺寸无效: {}x{}",
+        size.width(),
+        size.height()
+    ))?;
+    resvg::render(
+        &tree,
+        usvg::FitTo::Original,
+        tiny_skia::Transform::default(),
+        pixmap.as_mut(),
+    )
+    .ok_or(anyhow!("渲染图像失败!"))?;
+    pixmap
+        .encode_png()
+        .map_err(|e| anyhow!("编码PNG失败: {}", e))
+}
+
+/*
+将文本渲染为PNG
+图像宽度随内容变化，但不超过配置的最大宽度，超出部分自动折行
+*/
+pub fn render_text_png(text: &str, props: &TextRenderProps) -> anyhow::Result<Vec<u8>> {
+    let blocks = parse_blocks(text);
+    if blocks.iter().all(|v| *v == TextBlock::Blank) {
+        return Err(anyhow!("文本不能为空!"));
+    }
+    let font_size = props.font_size.max(8) as f64;
+    let line_height = font_size * LINE_SPACING;
+    let max_content = (props.max_width as f64 - PADDING * 2.0).max(font_size * 8.0);
+    let content_width = blocks
+        .iter()
+        .map(|v| natural_width(v, font_size))
+        .fold(font_size, f64::max)
+        .min(max_content)
+        .ceil();
+    let mut writer = SvgWriter {
+        props,
+        body: String::new(),
+    };
+    let mut y = PADDING;
+    for block in blocks.iter() {
+        if y > MAX_HEIGHT {
+            return Err(anyhow!("文本过长，图像高度超过 {} 像素", MAX_HEIGHT));
+        }
+        match block {
+            TextBlock::Heading(level, text) => {
+                let size = font_size * HEADING_SCALE[(level - 1).min(2)];
+                for line in wrap_text(text, content_width, size, false) {
+                    writer.text(PADDING, y, &line, size, true, false);
+                    y += size * LINE_SPACING;
+                }
+            }
+            TextBlock::Paragraph(text) => {
+                for line in wrap_text(text, content_width, font_size, false) {
+                    writer.text(PADDING, y, &line, font_size, false, false);
+                    y += line_height;
+                }
+            }
+            TextBlock::ListItem(marker, text) => {
+                let indent = text_width(marker, font_size, false) + font_size * 0.5;
+                writer.text(PADDING, y, marker, font_size, false, false);
+                for line in wrap_text(text, content_width - indent, font_size, false) {
+                    writer.text(PADDING + indent, y, &line, font_size, false, false);
+                    y += line_height;
+                }
+            }
+            TextBlock::Code(lines) => {
+                let wrapped = lines
+                    .iter()
+                    .flat_map(|v| wrap_text(v, content_width - CELL_PADDING * 2.0, font_size, true))
+                    .collect::<Vec<String>>();
+                let height = wrapped.len() as f64 * line_height + CELL_PADDING * 2.0;
+                writer.rect(
+                    PADDING,
+                    y,
+                    content_width,
+                    height,
+                    "#f4f4f4",
+                    Some("#e0e0e0"),
+                );
+                let mut line_top = y + CELL_PADDING;
+                for line in wrapped.iter() {
+                    writer.text(
+                        PADDING + CELL_PADDING,
+                        line_top,
+                        line,
+                        font_size,
+                        false,
+                        true,
+                    );
+                    line_top += line_height;
+                }
+                y += height + font_size * 0.5;
+            }
+            TextBlock::Table(rows) => {
+                let mut widths = column_widths(rows, font_size);
+                let total: f64 = widths.iter().sum();
+                if total > content_width {
+                    for v in widths.iter_mut() {
+                        *v *= content_width / total;
+                    }
+                }
+                for (index, row) in rows.iter().enumerate() {
+                    let cells = widths
+                        .iter()
+                        .enumerate()
+                        .map(|(i, w)| {
+                            wrap_text(
+                                row.get(i).map(|v| v.as_str()).unwrap_or(""),
+                                w - CELL_PADDING * 2.0,
+                                font_size,
+                                false,
+                            )
+                        })
+                        .collect::<Vec<Vec<String>>>();
+                    let height = cells.iter().map(|v| v.len()).max().unwrap_or(1) as f64
+                        * line_height
+                        + CELL_PADDING;
+                    let mut x = PADDING;
+                    for (lines, w) in cells.iter().zip(widths.iter()) {
+                        let fill = if index == 0 { "#f0f0f0" } else { "white" };
+                        writer.rect(x, y, *w, height, fill, Some("#c8c8c8"));
+                        let mut line_top = y + CELL_PADDING / 2.0;
+                        for line in lines.iter() {
+                            writer.text(
+                                x + CELL_PADDING,
+                                line_top,
+                                line,
+                                font_size,
+                                index == 0,
+                                false,
+                            );
+                            line_top += line_height;
+                        }
+                        x += w;
+                    }
+                    y += height;
+                }
+                y += font_size * 0.5;
+            }
+            TextBlock::Rule => {
+                writer.rect(
+                    PADDING,
+                    y + line_height / 2.0,
+                    content_width,
+                    1.0,
+                    "#c8c8c8",
+                    None,
+                );
+                y += line_height;
+            }
+            TextBlock::Blank => {
+                y += line_height * 0.6;
+            }
+        }
+    }
+    let width = (content_width + PADDING * 2.0).ceil();
+    let height = (y + PADDING).ceil();
+    if height > MAX_HEIGHT {
+        return Err(anyhow!("文本过长，图像高度超过 {} 像素", MAX_HEIGHT));
+    }
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}"><rect width="100%" height="100%" fill="white"/>{}</svg>"#,
+        width, height, writer.body
+    );
+    svg_to_png(&svg, &props.font_family)
+}
+
+// 渲染为可以直接发送的图片消息段
+pub fn render_text_image(text: &str, props: &TextRenderProps) -> anyhow::Result<MessageSegment> {
+    let data = render_text_png(text, props)?;
+    Ok(MessageSegment::Image(ImageData {
+        file: format!("base64://{}", base64::encode(data)),
+        r#type: None,
+        url: None,
+        cache: None,
+        proxy: None,
+        timeout: None,
+        id: None,
+    }))
+}
@@ -1,7 +1,17 @@
+use anyhow::anyhow;
 use config::Config;
+use log::info;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use url::Url;
 
+use super::client::ResultType;
+
+lazy_static::lazy_static! {
+    static ref SCRIPT_EXPR:Regex = Regex::new(r###"JSON.parse\(decodeURIComponent\("(?P<script>.+)"\)\);window._feConfigVersion="###).unwrap();
+}
+
 pub fn load_config_or_save_default<'a, T>(data_path: &std::path::PathBuf) -> anyhow::Result<T>
 where
     T: Serialize + Deserialize<'a> + Default,
@@ -38,3 +48,77 @@ impl SubUrlWrapper {
         return suburl.to_string();
     }
 }
+// 剪贴板页面的大小上限(字节)
+const LUOGU_PAGE_SIZE_LIMIT: usize = 4 << 20;
+const LUOGU_HOSTS: [&str; 2] = ["www.luogu.com.cn", "luogu.com.cn"];
+
+fn is_luogu_url(url: &Url) -> bool {
+    (url.scheme() == "https" || url.scheme() == "http")
+        && url.port().is_none()
+        && url
+            .host_str()
+            .map(|v| LUOGU_HOSTS.contains(&v))
+            .unwrap_or(false)
+}
+// 是否为洛谷剪贴板的链接，如 https://www.luogu.com.cn/paste/xxxx
+pub fn is_luogu_pasteboard_url(url: &str) -> bool {
+    Url::parse(url.trim())
+        .map(|v| is_luogu_url(&v) && v.path().starts_with("/paste/"))
+        .unwrap_or(false)
+}
+// 读取洛谷剪贴板的内容，只允许访问洛谷的剪贴板页面
+pub async fn fetch_luogu_pasteboard(url: &str) -> ResultType<String> {
+    let url = url.trim();
+    if !is_luogu_pasteboard_url(url) {
+        return Err(anyhow!("请输入洛谷剪贴板链接: {}", url).into());
+    }
+    info!("Fetching {}", url);
+    // 重定向也不能离开洛谷
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= 5 {
+                attempt.error(anyhow!("重定向次数过多!"))
+            } else if is_luogu_url(attempt.url()) {
+                attempt.follow()
+            } else {
+                attempt.error(anyhow!("不允许重定向到 {}", attempt.url()))
+            }
+        }))
+        .build()
+        .map_err(|e| anyhow!("创建HTTP客户端时出错: {}", e))?;
+    let mut resp = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| anyhow!("下载网页时出错: {}", e))?
+        .error_for_status()
+        .map_err(|e| anyhow!("下载网页时出错: {}", e))?;
+    let mut buf = vec![];
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| anyhow!("下载网页时出错: {}", e))?
+    {
+        if buf.len() + chunk.len() > LUOGU_PAGE_SIZE_LIMIT {
+            return Err(anyhow!("网页过大: 超过上限 {} 字节", LUOGU_PAGE_SIZE_LIMIT).into());
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    let resp = String::from_utf8_lossy(&buf);
+    let groups = SCRIPT_EXPR
+        .captures(&resp)
+        .ok_or(anyhow!("无法在网页中找到数据部分！"))?;
+    let script = groups
+        .name("script")
+        .ok_or(anyhow!("未找到指定分组!"))?
+        .as_str();
+    let decoded = urlencoding::decode(script).map_err(|e| anyhow!("解码失败!\n{}", e))?;
+    let parsed_json = serde_json::from_str::<Value>(&decoded)
+        .map_err(|e| anyhow!("反序列化时发生错误: {}", e))?;
+    let entry = parsed_json
+        .pointer("/currentData/paste/data")
+        .ok_or(anyhow!("找不到指定元素!"))?
+        .as_str()
+        .ok_or(anyhow!("指定元素不是str!"))?;
+    return Ok(entry.to_string());
+}
@@ -12,3 +12,126 @@ fn serde_test2() {
     from_value::<GroupMuteSubType>(json!("ban")).unwrap();
     from_value::<GroupMuteSubType>(json!("lift_ban")).unwrap();
 }
+
+#[test]
+fn cq_code_test() {
+    use countdown_bot3::countdown_bot::message::cq_code::{parse_cq_code, to_cq_code};
+    let segs = parse_cq_code("a&#91;b[CQ:at,qq=123]c");
+    assert_eq!(segs.len(), 3);
+    assert_eq!(segs[0]["data"]["text"], json!("a[b"));
+    assert_eq!(segs[1]["data"]["qq"], json!("123"));
+    assert_eq!(to_cq_code(&segs), "a&#91;b[CQ:at,qq=123]c");
+}
+
+#[test]
+fn onebot_v12_event_test() {
+    use countdown_bot3::countdown_bot::event::EventContainer;
+    use countdown_bot3::countdown_bot::protocol::{
+        detect_protocol, v12::adapt_event, ProtocolVersion,
+    };
+    let connect = json!({"type": "meta", "detail_type": "connect", "sub_type": "", "time": 1.5});
+    assert_eq!(detect_protocol(&connect), Some(ProtocolVersion::V12));
+    let event = adapt_event(json!({
+        "id": "abc",
+        "self": {"platform": "qq", "user_id": "10000"},
+        "time": 1632847927.599013,
+        "type": "message",
+        "detail_type": "group",
+        "sub_type": "",
+        "message_id": "6283",
+        "message": [
+            {"type": "mention", "data": {"user_id": "10001"}},
+            {"type": "text", "data": {"text": " --help"}}
+        ],
+        "alt_message": "@10001 --help",
+        "group_id": "12467",
+        "user_id": "123456788"
+    }))
+    .unwrap();
+    assert_eq!(event["self_id"], json!(10000));
+    assert_eq!(event["group_id"], json!(12467));
+    assert_eq!(event["raw_message"], json!("[CQ:at,qq=10001] --help"));
+    EventContainer::from_json(&event).unwrap();
+}
+
+#[test]
+fn artifact_range_test() {
+    use countdown_bot3::countdown_bot::artifact::parse_range;
+    assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+    assert_eq!(parse_range("bytes=500-", 1000), Some((500, 999)));
+    assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+    assert_eq!(parse_range("bytes=900-2000", 1000), Some((900, 999)));
+    assert_eq!(parse_range("bytes=1000-", 1000), None);
+    assert_eq!(parse_range("bytes=0-1,5-9", 1000), None);
+    assert_eq!(parse_range("items=0-1", 1000), None);
+}
+
+#[test]
+fn text_render_blocks_test() {
+    use countdown_bot3::countdown_bot::text_render::{parse_blocks, TextBlock};
+    let blocks = parse_blocks("# 标题\n- 第一项\n2. 第二项\n| a | b |\n|---|---|\n| 1 | 2 |\n```\nfn main() {}\n```\n\n---\n普通文本");
+    assert_eq!(
+        blocks,
+        vec![
+            TextBlock::Heading(1, "标题".to_string()),
+            TextBlock::ListItem("•".to_string(), "第一项".to_string()),
+            TextBlock::ListItem("2.".to_string(), "第二项".to_string()),
+            TextBlock::Table(vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["1".to_string(), "2".to_string()]
+            ]),
+            TextBlock::Code(vec!["fn main() {}".to_string()]),
+            TextBlock::Blank,
+            TextBlock::Rule,
+            TextBlock::Paragraph("普通文本".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn cgroup_parse_test() {
+    use countdown_bot3::countdown_bot::sandbox::cgroup::{parse_keyed_value, ContainerCgroup};
+    assert!(ContainerCgroup::from_proc_cgroup("0::/system.slice/docker-abc.scope\n").is_some());
+    assert!(ContainerCgroup::from_proc_cgroup(
+        "4:memory:/docker/abc\n1:name=systemd:/docker/abc\n"
+    )
+    .is_some());
+    assert!(ContainerCgroup::from_proc_cgroup("").is_none());
+    let events = "low 0\nhigh 0\nmax 3\noom 1\noom_kill 2\n";
+    assert_eq!(parse_keyed_value(events, "oom_kill"), Some(2));
+    assert_eq!(parse_keyed_value(events, "oom_group_kill"), None);
+}
+
+#[test]
+fn luogu_url_test() {
+    use countdown_bot3::countdown_bot::utils::is_luogu_pasteboard_url as is_paste;
+    assert!(is_paste("https://www.luogu.com.cn/paste/abcd1234"));
+    assert!(is_paste("https://luogu.com.cn/paste/abcd1234"));
+    assert!(!is_paste("http://10.0.0.1/?luogu.com.cn/paste"));
+    assert!(!is_paste("https://luogu.com.cn.evil.com/paste/a"));
+    assert!(!is_paste("file:///etc/passwd"));
+}
+
+#[test]
+fn text_render_height_limit_test() {
+    use countdown_bot3::countdown_bot::{config::TextRenderProps, text_render::render_text_png};
+    let text = "一行\n".repeat(5000);
+    assert!(render_text_png(&text, &TextRenderProps::default()).is_err());
+}
+
+#[test]
+fn strip_reply_prefix_test() {
+    use countdown_bot3::countdown_bot::command::strip_reply_prefix;
+    assert_eq!(
+        strip_reply_prefix("[CQ:reply,id=123][CQ:at,qq=456] [CQ:at,qq=456] --kick"),
+        "--kick"
+    );
+    // 非回复消息保持不变
+    assert_eq!(
+        strip_reply_prefix("[CQ:at,qq=456] --kick"),
+        "[CQ:at,qq=456] --kick"
+    );
+    assert_eq!(strip_reply_prefix("--help"), "--help");
+    // CQ码不完整时保持原样
+    assert_eq!(strip_reply_prefix("[CQ:reply,id=1"), "[CQ:reply,id=1");
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use countdown_bot3::countdown_bot::sandbox::{pool::PoolConfig, ResourceLimits, SandboxConfig};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -8,6 +9,15 @@ pub struct LanguageSetting {
     pub executable_filename: String,
     pub compile: String,
     pub run: String,
+    // REPL模式下执行的命令，为空则不支持REPL
+    #[serde(default)]
+    pub repl: String,
+    // 编译阶段的资源限制
+    #[serde(default = "default_compile_limits")]
+    pub compile_limits: ResourceLimits,
+    // 运行阶段的资源限制
+    #[serde(default)]
+    pub limits: ResourceLimits,
 }
 impl LanguageSetting {
     pub fn source_file(&self, name: &str) -> String {
@@ -27,21 +37,72 @@ impl LanguageSetting {
         return self.run.clone().replace("{target}", target);
     }
 }
+// 输出超出长度或行数限制时的处理方式
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowStrategy {
+    // 截断输出
+    Truncate,
+    // 以合并转发消息发送完整输出
+    Forward,
+    // 上传为群文件，需要go-cqhttp与bot在同一主机上
+    File,
+    // 通过内置的web服务器提供有时效的链接
+    Link,
+}
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct DockerRunnerConfig {
     pub docker_image: String,
     pub outout_length_limit: i32,
     pub execute_time_limit: i32,
+    pub compile_time_limit: i32,
     pub input_expire_after: i64,
     pub new_line_count_limit: i32,
     pub language_setting: HashMap<String, LanguageSetting>,
     pub blacklist_users: Vec<i64>,
+    pub sandbox: SandboxConfig,
+    // 评测模式下单次最多的测试数据组数
+    pub judge_max_cases: i32,
+    // 从群文件读取测试数据时的文件大小上限(字节)
+    pub testcase_file_size_limit: i64,
+    // REPL会话无操作多久后自动结束(毫秒)
+    pub repl_idle_timeout: i64,
+    // REPL会话的最长存活时间(毫秒)
+    pub repl_max_lifetime: i64,
+    // 输出的合并发送间隔(毫秒)
+    pub repl_batch_interval: i64,
+    pub repl_max_sessions_per_group: usize,
+    // 为各语言的编译与运行资源限制分别预热的容器池
+    pub pool: PoolConfig,
+    pub overflow_strategy: OverflowStrategy,
+    // 按群覆盖的处理方式
+    pub group_overflow_strategy: HashMap<i64, OverflowStrategy>,
+    // 合并转发消息中每个节点的最大字符数
+    pub forward_node_length: usize,
+    // 输出链接的有效时间(毫秒)
+    pub link_expire_after: i64,
+}
+impl DockerRunnerConfig {
+    pub fn overflow_strategy_of(&self, group_id: i64) -> OverflowStrategy {
+        self.group_overflow_strategy
+            .get(&group_id)
+            .cloned()
+            .unwrap_or(self.overflow_strategy)
+    }
+}
+fn default_compile_limits() -> ResourceLimits {
+    ResourceLimits {
+        memory_mb: 512,
+        cpu: 1.0,
+        ..Default::default()
+    }
 }
 impl Default for DockerRunnerConfig {
     fn default() -> Self {
         Self {
             docker_image: "python".to_string(),
             execute_time_limit: 2000,
+            compile_time_limit: 10000,
             input_expire_after: 1000 * 60 * 60,
             new_line_count_limit: 5,
             outout_length_limit: 200,
@@ -53,6 +114,10 @@ impl Default for DockerRunnerConfig {
                         executable_filename: "{name}.py".to_string(),
                         compile: "cp {source} {target}".to_string(),
                         run: "python3 {target}".to_string(),
+                        repl: "python3 -u -q -i -c \"import sys; sys.ps1 = sys.ps2 = ''\""
+                            .to_string(),
+                        compile_limits: default_compile_limits(),
+                        limits: ResourceLimits::default(),
                     },
                 ),
                 (
@@ -62,6 +127,9 @@ impl Default for DockerRunnerConfig {
                         executable_filename: "{name}.out".to_string(),
                         compile: "g++ -fdiagnostics-color=never {source} -o {target}".to_string(),
                         run: "./{target}".to_string(),
+                        repl: String::new(),
+                        compile_limits: default_compile_limits(),
+                        limits: ResourceLimits::default(),
                     },
                 ),
                 (
@@ -71,6 +139,9 @@ impl Default for DockerRunnerConfig {
                         executable_filename: "{name}.out".to_string(),
                         compile: "gcc -fdiagnostics-color=never {source} -o {target}".to_string(),
                         run: "./{target}".to_string(),
+                        repl: String::new(),
+                        compile_limits: default_compile_limits(),
+                        limits: ResourceLimits::default(),
                     },
                 ),
                 (
@@ -80,6 +151,9 @@ impl Default for DockerRunnerConfig {
                         executable_filename: "{name}.sh".to_string(),
                         compile: "cp {source} {target}".to_string(),
                         run: "bash {target}".to_string(),
+                        repl: "bash".to_string(),
+                        compile_limits: default_compile_limits(),
+                        limits: ResourceLimits::default(),
                     },
                 ),
                 (
@@ -89,6 +163,9 @@ impl Default for DockerRunnerConfig {
                         executable_filename: "{name}.out".to_string(),
                         compile: "rustc {source} -o {target}".to_string(),
                         run: "./{target}".to_string(),
+                        repl: String::new(),
+                        compile_limits: default_compile_limits(),
+                        limits: ResourceLimits::default(),
                     },
                 ),
                 (
@@ -98,10 +175,25 @@ impl Default for DockerRunnerConfig {
                         executable_filename: "{name}.out".to_string(),
                         compile: "ghc {source} -o {target}".to_string(),
                         run: "./{target}".to_string(),
+                        repl: String::new(),
+                        compile_limits: default_compile_limits(),
+                        limits: ResourceLimits::default(),
                     },
                 ),
             ]),
             blacklist_users: vec![],
+            sandbox: SandboxConfig::default(),
+            judge_max_cases: 20,
+            testcase_file_size_limit: 1 << 20,
+            repl_idle_timeout: 1000 * 60 * 3,
+            repl_max_lifetime: 1000 * 60 * 30,
+            repl_batch_interval: 800,
+            repl_max_sessions_per_group: 3,
+            pool: PoolConfig::default(),
+            overflow_strategy: OverflowStrategy::Truncate,
+            group_overflow_strategy: HashMap::new(),
+            forward_node_length: 1500,
+            link_expire_after: 1000 * 60 * 60,
         }
     }
 }
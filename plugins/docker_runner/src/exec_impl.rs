@@ -1,15 +1,87 @@
-use crate::DockerRunnerPlugin;
+use crate::{
+    config::LanguageSetting,
+    verdict::{trim_output, Verdict},
+    DockerRunnerPlugin,
+};
 use anyhow::anyhow;
-use bollard::{
-    container::{Config, LogOutput, LogsOptions},
-    models::{HostConfig, Mount, MountTypeEnum},
+use countdown_bot3::countdown_bot::{
+    client::ResultType,
+    command::SenderType,
+    sandbox::{SandboxRequest, SandboxResult},
 };
-use countdown_bot3::countdown_bot::{client::ResultType, command::SenderType};
-use futures_util::{sink, stream::StreamExt};
 use log::info;
-use std::time::Duration;
-const APP_NAME: &str = "app";
+use std::{path::Path, time::Duration};
+pub const APP_NAME: &str = "app";
 impl DockerRunnerPlugin {
+    // 编译成功时返回None，否则返回截断后的编译信息
+    pub async fn compile(
+        &self,
+        lang_config: &LanguageSetting,
+        working_dir: &Path,
+        name: &str,
+    ) -> ResultType<Option<String>> {
+        let config = self.config.as_ref().unwrap();
+        let command = lang_config.compile_arg(
+            &lang_config.source_file(name),
+            &lang_config.executable_file(name),
+        );
+        info!("Compile command: {}", command);
+        let result = self
+            .sandbox
+            .as_ref()
+            .unwrap()
+            .run(&SandboxRequest {
+                image: config.docker_image.clone(),
+                command,
+                working_dir: working_dir.to_path_buf(),
+                limits: lang_config.compile_limits.clone(),
+                timeout: Duration::from_millis(config.compile_time_limit as u64),
+            })
+            .await?;
+        if result.timed_out {
+            return Ok(Some(format!("编译超时 (>{}ms)", config.compile_time_limit)));
+        }
+        if result.exit_code.unwrap_or(0) != 0 || result.oom_killed {
+            let output = result.output_string();
+            return Ok(Some(trim_output(
+                output.trim(),
+                config.new_line_count_limit as usize,
+                config.outout_length_limit as usize,
+                result.output_truncated,
+            )));
+        }
+        Ok(None)
+    }
+    pub async fn run_program(
+        &self,
+        lang_config: &LanguageSetting,
+        working_dir: &Path,
+        name: &str,
+        args: &str,
+        stdin_file: &str,
+        stdout_only: bool,
+    ) -> ResultType<SandboxResult> {
+        let config = self.config.as_ref().unwrap();
+        let command = format!(
+            "{} {} < {}{}",
+            lang_config.run_arg(&lang_config.executable_file(name)),
+            args,
+            stdin_file,
+            if stdout_only { " 2>/dev/null" } else { "" }
+        );
+        info!("Run command: {}", command);
+        self.sandbox
+            .as_ref()
+            .unwrap()
+            .run(&SandboxRequest {
+                image: config.docker_image.clone(),
+                command,
+                working_dir: working_dir.to_path_buf(),
+                limits: lang_config.limits.clone(),
+                timeout: Duration::from_millis(config.execute_time_limit as u64),
+            })
+            .await
+    }
     pub async fn handle_exec(
         &mut self,
         sender: &SenderType,
@@ -31,137 +103,52 @@ impl DockerRunnerPlugin {
             return Err(anyhow!("你不被允许使用该指令!").into());
         }
         info!("Code = \n{}", code);
-        let docker_client = bollard::Docker::connect_with_socket_defaults()
-            .map_err(|e| anyhow!("初始化Docker时发生错误: {}", e))?;
         let working_dir = tempfile::tempdir()?;
-        let source_filename = lang_config.source_file(APP_NAME);
-        let exec_filename = lang_config.executable_file(APP_NAME);
-        tokio::fs::write(working_dir.path().join(&source_filename), code).await?;
+        tokio::fs::write(
+            working_dir.path().join(lang_config.source_file(APP_NAME)),
+            code,
+        )
+        .await?;
         tokio::fs::write(working_dir.path().join("f_stdin"), input_data).await?;
-        let command = format!(
-            "{} && {} < f_stdin",
-            lang_config.compile_arg(&source_filename, &exec_filename),
-            lang_config.run_arg(&exec_filename)
-        );
         info!("Working directory: {:?}", working_dir.path());
-        info!("Command line: {}", command);
-        let container = docker_client
-            .create_container::<String, String>(
-                None,
-                Config {
-                    image: Some(config.docker_image),
-                    cmd: Some(vec!["sh".to_string(), "-c".to_string(), command]),
-                    open_stdin: Some(true),
-                    // detach
-                    tty: Some(true),
-                    network_disabled: Some(true),
-                    working_dir: Some("/temp".to_string()),
-                    host_config: Some(HostConfig {
-                        mounts: Some(vec![Mount {
-                            target: Some("/temp".to_string()),
-                            source: Some(working_dir.path().to_str().unwrap().to_string()),
-                            read_only: Some(false),
-                            typ: Some(MountTypeEnum::BIND),
-                            ..Default::default()
-                        }]),
-                        memory: Some(50 * ((1 << 20) as i64)),
-                        memory_swap: Some(50 * ((1 << 20) as i64)),
-                        oom_kill_disable: Some(false),
-                        nano_cpus: Some((0.4 / 1e-9) as i64),
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                },
-            )
-            .await?;
-        let local_client = docker_client.clone();
-        let container_id = container.id.clone();
-        docker_client
-            .start_container::<&str>(&container.id, None)
-            .await
-            .map_err(|e| anyhow!("启动容器时发生错误: {}", e))?;
-        if let Err(_) = tokio::time::timeout(
-            Duration::from_millis(config.execute_time_limit as u64),
-            async move {
-                let client = local_client;
-                client
-                    .wait_container::<&str>(&container_id, None)
-                    .map(|x| Ok(x.unwrap()))
-                    .forward(sink::drain())
-                    .await
-                    .ok();
-            },
-        )
-        .await
+        let client = self.client.as_ref().unwrap();
+        if let Some(message) = self
+            .compile(lang_config, working_dir.path(), APP_NAME)
+            .await?
         {
-            docker_client
-                .kill_container::<&str>(&container.id, None)
-                .await
-                .ok();
-            docker_client.stop_container(&container.id, None).await.ok();
-            self.client
-                .as_ref()
-                .unwrap()
-                .quick_send_by_sender(sender, "执行超时!")
+            client
+                .quick_send_by_sender(sender, &Verdict::CompileError(message).describe())
                 .await?;
+            return Ok(());
         }
-        let output = docker_client
-            .logs(
-                &container.id,
-                Some(LogsOptions::<&str> {
-                    stderr: true,
-                    stdout: true,
-                    follow: true,
-                    ..Default::default()
-                }),
+        let result = self
+            .run_program(
+                lang_config,
+                working_dir.path(),
+                APP_NAME,
+                "",
+                "f_stdin",
+                false,
             )
-            .collect::<Vec<Result<LogOutput, bollard::errors::Error>>>()
-            .await;
-        let client = self.client.as_ref().unwrap();
+            .await?;
+        let verdict = Verdict::from_run_result(
+            &result,
+            &lang_config.limits,
+            config.execute_time_limit as u64,
+        );
+        let output = result.output_string();
         if output.is_empty() {
-            client.quick_send_by_sender(sender, "无输出!").await?;
-        } else {
-            let mut lines = vec![];
-            let mut line_overflow = false;
-            let mut length_overflow = false;
-            for line in output.iter() {
-                if let Ok(v) = line {
-                    match v {
-                        LogOutput::Console { message } => {
-                            lines.push(message);
-                            if lines.len() >= config.new_line_count_limit as usize {
-                                line_overflow = true;
-                                break;
-                            }
-                        }
-                        _ => continue,
-                    };
-                }
-            }
-            let mut output = String::new();
-            for line in lines {
-                output.push_str(&String::from_utf8(
-                    line.iter().map(|x| *x).collect::<Vec<u8>>(),
-                )?);
-                if output.len() > config.outout_length_limit as usize {
-                    length_overflow = true;
-                    break;
-                }
-            }
-            if length_overflow {
-                output = output
-                    .chars()
-                    .take(config.outout_length_limit as usize)
-                    .collect::<String>();
-                output.push_str("\n[超出长度部分已截断]");
-            }
-            if line_overflow {
-                output.push_str("\n[超出行数已截断]");
-            }
-
             client
-                .quick_send_by_sender_ex(sender, &output, true)
+                .quick_send_by_sender(sender, &format!("{}\n无输出!", verdict.describe()))
                 .await?;
+        } else {
+            self.send_output(
+                sender,
+                &verdict.describe(),
+                &output,
+                result.output_truncated,
+            )
+            .await?;
         }
         return Ok(());
     }
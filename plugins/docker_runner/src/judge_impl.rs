@@ -0,0 +1,333 @@
+use crate::{
+    exec_impl::APP_NAME,
+    testcase::{compare_output, parse_testcases, TestCase, CASE_SEPARATOR, IO_SEPARATOR},
+    verdict::Verdict,
+    CacheSourceTuple, DockerRunnerPlugin,
+};
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::ResultType, command::SenderType, event::message::GroupMessageEvent,
+    utils::fetch_luogu_pasteboard,
+};
+use log::info;
+use std::path::Path;
+
+const CHECKER_NAME: &str = "checker";
+
+#[derive(Debug, Clone)]
+pub struct SpecialJudge {
+    pub language: String,
+    pub code: String,
+}
+#[derive(Debug, Clone)]
+pub struct TestCaseEntry {
+    pub cases: Vec<TestCase>,
+    pub spj: Option<SpecialJudge>,
+    pub inserted_at: chrono::DateTime<chrono::Local>,
+}
+
+fn group_event(sender: &SenderType) -> ResultType<&GroupMessageEvent> {
+    match sender {
+        SenderType::Group(e) => Ok(e),
+        _ => Err(anyhow!("请在群内使用此指令!").into()),
+    }
+}
+
+impl DockerRunnerPlugin {
+    async fn download_group_file(&self, group_id: i64, name: &str) -> ResultType<String> {
+        let config = self.config.as_ref().unwrap();
+        let file = self
+            .client
+            .as_ref()
+            .unwrap()
+            .find_group_file(group_id, name)
+            .await?;
+        if file.file_size > config.testcase_file_size_limit {
+            return Err(anyhow!(
+                "文件过大: {} 字节，上限为 {} 字节",
+                file.file_size,
+                config.testcase_file_size_limit
+            )
+            .into());
+        }
+        let url = self
+            .client
+            .as_ref()
+            .unwrap()
+            .get_group_file_url(group_id, &file.file_id, file.busid)
+            .await?
+            .url;
+        info!("Downloading testcase file: {}", url);
+        let bytes = reqwest::get(&url)
+            .await
+            .map_err(|e| anyhow!("下载群文件时出错: {}", e))?
+            .bytes()
+            .await
+            .map_err(|e| anyhow!("下载群文件时出错: {}", e))?;
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+    async fn get_testcases(&self, source: &CacheSourceTuple) -> Option<TestCaseEntry> {
+        let config = self.config.as_ref().unwrap();
+        let mut guard = self.testcase_cache.lock().await;
+        if let Some(entry) = guard.get(source) {
+            let time_diff = chrono::Local::now() - entry.inserted_at;
+            if time_diff.num_milliseconds() <= config.input_expire_after {
+                return Some(entry.clone());
+            }
+            guard.remove(source);
+        }
+        None
+    }
+    async fn store_testcases(
+        &self,
+        source: CacheSourceTuple,
+        cases: Vec<TestCase>,
+    ) -> ResultType<String> {
+        let max_cases = self.config.as_ref().unwrap().judge_max_cases as usize;
+        if cases.len() > max_cases {
+            return Err(
+                anyhow!("测试数据过多: {} 组，上限为 {} 组", cases.len(), max_cases).into(),
+            );
+        }
+        let count = cases.len();
+        let mut guard = self.testcase_cache.lock().await;
+        let spj = guard.remove(&source).and_then(|v| v.spj);
+        guard.insert(
+            source,
+            TestCaseEntry {
+                cases,
+                spj,
+                inserted_at: chrono::Local::now(),
+            },
+        );
+        Ok(format!(
+            "已设置 {} 组测试数据，将在 {} 毫秒后失效。",
+            count,
+            self.config.as_ref().unwrap().input_expire_after
+        ))
+    }
+    pub async fn handle_testcase(
+        &mut self,
+        sender: &SenderType,
+        args: &[String],
+    ) -> ResultType<()> {
+        let evt = group_event(sender)?;
+        let source = CacheSourceTuple {
+            uid: evt.user_id as i64,
+            gid: evt.group_id,
+        };
+        let rest = args.get(1..).unwrap_or(&[]).join(" ");
+        let rest = html_escape::decode_html_entities(&rest).to_string();
+        let text = match args.first().map(|s| s.as_str()) {
+            Some("set") => {
+                self.store_testcases(source, parse_testcases(&rest)?)
+                    .await?
+            }
+            Some("paste") => {
+                let url = rest.trim();
+                if url.is_empty() {
+                    return Err(anyhow!("请输入洛谷剪贴板链接!").into());
+                }
+                let content = fetch_luogu_pasteboard(url).await?;
+                self.store_testcases(source, parse_testcases(&content)?)
+                    .await?
+            }
+            Some("file") => {
+                let name = rest.trim();
+                if name.is_empty() {
+                    return Err(anyhow!("请输入群文件名!").into());
+                }
+                let content = self.download_group_file(evt.group_id, name).await?;
+                self.store_testcases(source, parse_testcases(&content)?)
+                    .await?
+            }
+            Some("spj") => {
+                let mut split = rest.splitn(2, ' ');
+                let language = split.next().unwrap_or("").trim().to_string();
+                let code = split.next().unwrap_or("").to_string();
+                if !self
+                    .config
+                    .as_ref()
+                    .unwrap()
+                    .language_setting
+                    .contains_key(&language)
+                {
+                    return Err(anyhow!("非法语言ID: {}", language).into());
+                }
+                if code.trim().is_empty() {
+                    return Err(anyhow!("请输入SPJ代码!").into());
+                }
+                let mut guard = self.testcase_cache.lock().await;
+                let entry = guard
+                    .get_mut(&source)
+                    .ok_or(anyhow!("请先设置测试数据!"))?;
+                entry.spj = Some(SpecialJudge { language, code });
+                String::from("已设置SPJ。SPJ将以 输入文件 选手输出 标准答案 为参数运行，返回0表示答案正确。")
+            }
+            Some("clear") => {
+                self.testcase_cache.lock().await.remove(&source);
+                String::from("已清除测试数据")
+            }
+            _ => match self.get_testcases(&source).await {
+                Some(entry) => format!(
+                    "当前共有 {} 组测试数据{}",
+                    entry.cases.len(),
+                    if entry.spj.is_some() { "，已设置SPJ" } else { "" }
+                ),
+                None => format!(
+                    "当前没有测试数据。\n测试数据格式: 输入与期望输出之间以单独一行 {} 分隔，各组数据之间以单独一行 {} 分隔。",
+                    IO_SEPARATOR, CASE_SEPARATOR
+                ),
+            },
+        };
+        self.client
+            .as_ref()
+            .unwrap()
+            .quick_send_by_sender(sender, &text)
+            .await?;
+        Ok(())
+    }
+    // 使用SPJ检查输出，返回是否正确及SPJ的输出
+    async fn run_checker(
+        &self,
+        spj: &SpecialJudge,
+        checker_dir: &Path,
+        case: &TestCase,
+        output: &str,
+    ) -> ResultType<(bool, String)> {
+        let lang_config = &self.config.as_ref().unwrap().language_setting[&spj.language];
+        tokio::fs::write(checker_dir.join("input"), &case.input).await?;
+        tokio::fs::write(checker_dir.join("output"), output).await?;
+        tokio::fs::write(checker_dir.join("answer"), &case.expected).await?;
+        let result = self
+            .run_program(
+                lang_config,
+                checker_dir,
+                CHECKER_NAME,
+                "input output answer",
+                "/dev/null",
+                false,
+            )
+            .await?;
+        let message = result
+            .output_string()
+            .lines()
+            .next()
+            .unwrap_or("")
+            .to_string();
+        Ok((
+            !result.timed_out && result.exit_code.unwrap_or(0) == 0,
+            message,
+        ))
+    }
+    pub async fn handle_judge(
+        &mut self,
+        sender: &SenderType,
+        code: &str,
+        language: &str,
+    ) -> ResultType<()> {
+        let config = self.config.clone().unwrap();
+        let lang_config = config
+            .language_setting
+            .get(language)
+            .ok_or(anyhow!("非法语言ID: {}", language))?;
+        let evt = group_event(sender)?;
+        if config.blacklist_users.contains(&(evt.user_id as i64)) {
+            return Err(anyhow!("你不被允许使用该指令!").into());
+        }
+        let entry = self
+            .get_testcases(&CacheSourceTuple {
+                uid: evt.user_id as i64,
+                gid: evt.group_id,
+            })
+            .await
+            .ok_or(anyhow!("请先使用 testcase 指令设置测试数据!"))?;
+        let client = self.client.as_ref().unwrap();
+        let working_dir = tempfile::tempdir()?;
+        tokio::fs::write(
+            working_dir.path().join(lang_config.source_file(APP_NAME)),
+            code,
+        )
+        .await?;
+        if let Some(message) = self
+            .compile(lang_config, working_dir.path(), APP_NAME)
+            .await?
+        {
+            client
+                .quick_send_by_sender(sender, &Verdict::CompileError(message).describe())
+                .await?;
+            return Ok(());
+        }
+        let checker_dir = tempfile::tempdir()?;
+        if let Some(spj) = entry.spj.as_ref() {
+            let spj_config = &config.language_setting[&spj.language];
+            tokio::fs::write(
+                checker_dir
+                    .path()
+                    .join(spj_config.source_file(CHECKER_NAME)),
+                &spj.code,
+            )
+            .await?;
+            if let Some(message) = self
+                .compile(spj_config, checker_dir.path(), CHECKER_NAME)
+                .await?
+            {
+                client
+                    .quick_send_by_sender(sender, &format!("SPJ编译失败:\n{}", message))
+                    .await?;
+                return Ok(());
+            }
+        }
+        let mut lines = vec![];
+        let mut accepted = 0;
+        for (i, case) in entry.cases.iter().enumerate() {
+            tokio::fs::write(working_dir.path().join("f_stdin"), &case.input).await?;
+            // 沙箱不分配tty，丢弃标准错误后只比较标准输出
+            let result = self
+                .run_program(
+                    lang_config,
+                    working_dir.path(),
+                    APP_NAME,
+                    "",
+                    "f_stdin",
+                    true,
+                )
+                .await?;
+            let verdict = Verdict::from_run_result(
+                &result,
+                &lang_config.limits,
+                config.execute_time_limit as u64,
+            );
+            let line = match verdict {
+                Verdict::Ok { time_ms, .. } => {
+                    let output = result.output_string();
+                    let time = time_ms.map(|t| format!(" {}ms", t)).unwrap_or_default();
+                    let (ok, detail) = if let Some(spj) = entry.spj.as_ref() {
+                        self.run_checker(spj, checker_dir.path(), case, &output)
+                            .await?
+                    } else {
+                        match compare_output(&output, &case.expected) {
+                            None => (true, String::new()),
+                            Some(line) => (false, format!("第 {} 行不同", line)),
+                        }
+                    };
+                    if ok {
+                        accepted += 1;
+                        format!("#{} AC{}", i + 1, time)
+                    } else if result.output_truncated {
+                        format!("#{} WA{} | 输出超出长度限制", i + 1, time)
+                    } else {
+                        format!("#{} WA{} {}", i + 1, time, detail)
+                    }
+                }
+                other => format!("#{} {}", i + 1, other.short_name()),
+            };
+            lines.push(line.trim_end().to_string());
+        }
+        lines.push(format!("通过 {}/{}", accepted, entry.cases.len()));
+        client
+            .quick_send_by_sender(sender, &lines.join("\n"))
+            .await?;
+        Ok(())
+    }
+}
@@ -4,23 +4,36 @@ use chrono::Local;
 use config::DockerRunnerConfig;
 use countdown_bot3::{
     countdown_bot::{
+        artifact::ArtifactStore,
         bot,
-        client::CountdownBotClient,
+        client::{CountdownBotClient, ResultType},
         command::{Command, SenderType},
-        plugin::{BotPlugin, HookResult, PluginMeta},
+        event::{
+            manager::{EventListener, WrappedOOPEventContainer},
+            message::GroupMessageEvent,
+        },
+        plugin::{BotPlugin, BotPluginWrapped, HookResult, PluginMeta},
+        sandbox::{create_sandbox, ResourceLimits, SandboxWrapped},
         utils::load_config_or_save_default,
     },
     export_static_plugin,
 };
-use log::debug;
-use std::{collections::BTreeMap, sync::Arc};
+use judge_impl::TestCaseEntry;
+use log::{debug, error};
+use repl_impl::ReplSessions;
+use std::{any::TypeId, collections::BTreeMap, sync::Arc};
 use tokio::sync::Mutex;
 
 static PLUGIN_NAME: &str = "docker_runner";
 
-mod config;
+pub mod config;
 mod exec_impl;
+mod judge_impl;
 mod misc_impl;
+mod overflow_impl;
+mod repl_impl;
+pub mod testcase;
+mod verdict;
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct CacheSourceTuple {
     pub uid: i64,
@@ -34,6 +47,11 @@ struct DockerRunnerPlugin {
     client: Option<CountdownBotClient>,
     config: Option<DockerRunnerConfig>,
     input_cache: Arc<Mutex<BTreeMap<CacheSourceTuple, CacheEntry>>>,
+    testcase_cache: Arc<Mutex<BTreeMap<CacheSourceTuple, TestCaseEntry>>>,
+    sandbox: Option<SandboxWrapped>,
+    repl_sessions: ReplSessions,
+    command_prefix: Vec<String>,
+    artifacts: Option<ArtifactStore>,
 }
 
 impl Default for DockerRunnerPlugin {
@@ -42,6 +60,11 @@ impl Default for DockerRunnerPlugin {
             client: None,
             config: None,
             input_cache: Default::default(),
+            testcase_cache: Default::default(),
+            sandbox: None,
+            repl_sessions: Default::default(),
+            command_prefix: vec![],
+            artifacts: None,
         }
     }
 }
@@ -51,12 +74,39 @@ impl BotPlugin for DockerRunnerPlugin {
     fn on_enable(
         &mut self,
         bot: &mut bot::CountdownBot,
-        _handle: tokio::runtime::Handle,
+        handle: tokio::runtime::Handle,
     ) -> HookResult<()> {
         self.config = Some(load_config_or_save_default(
             &bot.ensure_plugin_data_dir(PLUGIN_NAME)?,
         )?);
         debug!("Config: {:#?}", self.config);
+        self.sandbox = Some(create_sandbox(&self.config.as_ref().unwrap().sandbox)?);
+        {
+            let config = self.config.clone().unwrap();
+            let sandbox = self.sandbox.clone().unwrap();
+            let mut limits_list: Vec<ResourceLimits> = vec![];
+            for setting in config.language_setting.values() {
+                for limits in [&setting.compile_limits, &setting.limits] {
+                    if !limits_list.contains(limits) {
+                        limits_list.push(limits.clone());
+                    }
+                }
+            }
+            handle.spawn(async move {
+                for limits in limits_list.iter() {
+                    if let Err(e) = sandbox
+                        .warmup(&config.docker_image, limits, &config.pool)
+                        .await
+                    {
+                        error!("Failed to warm up container pool: {}", e);
+                    }
+                }
+            });
+        }
+        bot.register_state_hook();
+        self.command_prefix = bot.get_command_prefix();
+        self.artifacts = Some(bot.get_artifact_store());
+        bot.register_event_handler(TypeId::of::<GroupMessageEvent>(), MyEventHandler {});
         bot.register_command(
             Command::new("exec")
                 .group(true)
@@ -72,6 +122,21 @@ impl BotPlugin for DockerRunnerPlugin {
                 .group(true)
                 .description("指定下一次执行程序时的标准输入 | input <数据>"),
         )?;
+        bot.register_command(
+            Command::new("testcase")
+                .group(true)
+                .single_alias("测试数据")
+                .description("设置评测用的测试数据 | testcase set <数据> | testcase paste <洛谷剪贴板链接> | testcase file <群文件名> | testcase spj <语言> <代码> | testcase clear"),
+        )?;
+        bot.register_command(Command::new("repl").group(true).description(
+            "启动交互式会话，之后发送的消息将作为输入 | repl <语言> | repl stop | repl",
+        ))?;
+        bot.register_command(
+            Command::new("judge")
+                .group(true)
+                .single_alias("评测")
+                .description("使用测试数据评测代码 | judge <语言> <代码>"),
+        )?;
 
         Ok(())
     }
@@ -83,6 +148,13 @@ impl BotPlugin for DockerRunnerPlugin {
         self.client = Some(client);
         Ok(())
     }
+    async fn on_disable(&mut self) -> HookResult<()> {
+        self.stop_all_repl().await;
+        if let Some(sandbox) = self.sandbox.as_ref() {
+            sandbox.shutdown().await;
+        }
+        Ok(())
+    }
     fn get_meta(&self) -> PluginMeta {
         PluginMeta {
             author: String::from("officeyutong"),
@@ -90,6 +162,17 @@ impl BotPlugin for DockerRunnerPlugin {
             version: env!("CARGO_PKG_VERSION").to_string(),
         }
     }
+    async fn on_state_hook(&mut self) -> HookResult<String> {
+        return Ok(self
+            .sandbox
+            .as_ref()
+            .unwrap()
+            .pool_stats()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join("\n"));
+    }
     async fn on_command(
         &mut self,
         command: String,
@@ -173,6 +256,23 @@ impl DockerRunnerPlugin {
                 self.handle_exec(sender, &code, &lang_id).await?;
                 return Ok(());
             }
+            "testcase" => {
+                self.handle_testcase(sender, &args).await?;
+                return Ok(());
+            }
+            "repl" => {
+                self.handle_repl(sender, &args).await?;
+                return Ok(());
+            }
+            "judge" => {
+                if args.len() < 2 {
+                    return Err(anyhow!("请输入语言与代码! | judge <语言> <代码>").into());
+                }
+                let code_joined = args[1..].join(" ");
+                let code = html_escape::decode_html_entities(&code_joined);
+                self.handle_judge(sender, &code, &args[0]).await?;
+                return Ok(());
+            }
             "input" => {
                 let items = args.join(" ");
                 self.handle_input(sender, &items).await?;
@@ -185,6 +285,23 @@ impl DockerRunnerPlugin {
 
 export_static_plugin!(PLUGIN_NAME, DockerRunnerPlugin::default());
 
+struct MyEventHandler;
+#[async_trait]
+impl EventListener for MyEventHandler {
+    async fn on_event(
+        &mut self,
+        event: WrappedOOPEventContainer,
+        plugin: BotPluginWrapped,
+    ) -> ResultType<()> {
+        let mut plugin_guard = plugin.write().await;
+        let casted = plugin_guard.downcast_mut::<DockerRunnerPlugin>().unwrap();
+        let event_guard = event.read().await.event.clone();
+        let gevt = event_guard.downcast_ref::<GroupMessageEvent>().unwrap();
+        casted.handle_repl_message(gevt).await?;
+        return Ok(());
+    }
+}
+
 fn make_assign_str(var: &str, val: &str) -> String {
     let b64enc = base64::encode(val.as_bytes());
     return format!(
@@ -0,0 +1,151 @@
+use crate::{config::OverflowStrategy, verdict::trim_output, DockerRunnerPlugin, PLUGIN_NAME};
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::ResultType,
+    command::SenderType,
+    message::segment::{MessageSegment, NodeData, TextData},
+};
+use log::error;
+use std::time::Duration;
+
+// 按行切分为不超过指定字符数的若干段
+fn split_chunks(text: &str, chunk_length: usize) -> Vec<String> {
+    let mut result = vec![];
+    let mut buf = String::new();
+    let mut count = 0;
+    for line in text.split_inclusive('\n') {
+        if count + line.chars().count() > chunk_length && !buf.is_empty() {
+            result.push(std::mem::take(&mut buf));
+            count = 0;
+        }
+        for ch in line.chars() {
+            if count >= chunk_length {
+                result.push(std::mem::take(&mut buf));
+                count = 0;
+            }
+            buf.push(ch);
+            count += 1;
+        }
+    }
+    if !buf.is_empty() {
+        result.push(buf);
+    }
+    result
+}
+
+impl DockerRunnerPlugin {
+    // 发送程序输出，超出限制时按群设置的方式处理
+    pub async fn send_output(
+        &self,
+        sender: &SenderType,
+        header: &str,
+        output: &str,
+        truncated: bool,
+    ) -> ResultType<()> {
+        let config = self.config.as_ref().unwrap();
+        let client = self.client.as_ref().unwrap();
+        let line_limit = config.new_line_count_limit as usize;
+        let length_limit = config.outout_length_limit as usize;
+        let preview = format!(
+            "{}\n{}",
+            header,
+            trim_output(output, line_limit, length_limit, truncated)
+        );
+        let overflow = truncated
+            || output.split_inclusive('\n').count() > line_limit
+            || output.chars().count() > length_limit;
+        let group_id = match sender {
+            SenderType::Group(evt) if overflow => evt.group_id,
+            _ => {
+                client.quick_send_by_sender(sender, &preview).await?;
+                return Ok(());
+            }
+        };
+        let strategy = config.overflow_strategy_of(group_id);
+        let result = match strategy {
+            OverflowStrategy::Truncate => Ok(preview.clone()),
+            OverflowStrategy::Forward => self
+                .send_forward(group_id, header, output)
+                .await
+                .map(|_| String::new()),
+            OverflowStrategy::File => self
+                .upload_output(group_id, output)
+                .await
+                .map(|name| format!("{}\n完整输出已上传为群文件: {}", preview, name)),
+            OverflowStrategy::Link => self.host_output(output).await.map(|url| {
+                format!(
+                    "{}\n完整输出 ({} 秒内有效): {}",
+                    preview,
+                    config.link_expire_after / 1000,
+                    url
+                )
+            }),
+        };
+        let text = match result {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    "Failed to handle overflowed output with {:?}: {}",
+                    strategy, e
+                );
+                preview
+            }
+        };
+        if !text.is_empty() {
+            client.quick_send_by_sender(sender, &text).await?;
+        }
+        Ok(())
+    }
+    async fn send_forward(&self, group_id: i64, header: &str, output: &str) -> ResultType<()> {
+        let config = self.config.as_ref().unwrap();
+        let client = self.client.as_ref().unwrap();
+        let login_info = client.get_login_info().await?;
+        let nodes = std::iter::once(header.to_string())
+            .chain(split_chunks(output, config.forward_node_length.max(1)))
+            .map(|text| {
+                MessageSegment::Node(NodeData {
+                    id: None,
+                    name: Some(String::from("docker_runner")),
+                    uin: Some(login_info.user_id as i64),
+                    content: Some(vec![Box::new(MessageSegment::Text(TextData { text }))]),
+                    seq: None,
+                })
+            })
+            .collect::<Vec<MessageSegment>>();
+        client.send_group_forward_msg(group_id, nodes).await?;
+        Ok(())
+    }
+    async fn upload_output(&self, group_id: i64, output: &str) -> ResultType<String> {
+        let working_dir = tempfile::tempdir()?;
+        let name = format!(
+            "output_{}.txt",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        );
+        let path = working_dir.path().join(&name);
+        tokio::fs::write(&path, output).await?;
+        self.client
+            .as_ref()
+            .unwrap()
+            .upload_group_file(
+                group_id,
+                path.to_str().ok_or(anyhow!("非法的文件路径"))?,
+                &name,
+            )
+            .await?;
+        Ok(name)
+    }
+    async fn host_output(&self, output: &str) -> ResultType<String> {
+        let config = self.config.as_ref().unwrap();
+        self.artifacts
+            .as_ref()
+            .unwrap()
+            .put(
+                PLUGIN_NAME,
+                output.as_bytes(),
+                "text/plain; charset=utf-8",
+                None,
+                Some(Duration::from_millis(config.link_expire_after as u64)),
+            )
+            .await
+    }
+}
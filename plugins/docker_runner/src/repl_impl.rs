@@ -0,0 +1,303 @@
+use crate::{verdict::trim_output, CacheSourceTuple, DockerRunnerPlugin};
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::{CountdownBotClient, ResultType},
+    command::SenderType,
+    event::message::GroupMessageEvent,
+    sandbox::{InteractiveSession, SandboxRequest},
+};
+use log::{error, info};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot, Mutex},
+    time::Instant,
+};
+
+pub struct ReplHandle {
+    // 用于区分同一用户先后启动的会话
+    pub id: u64,
+    pub language: String,
+    pub input: mpsc::UnboundedSender<String>,
+    // 关闭插件时通知会话结束，会话清理完成后通过传入的Sender回复
+    shutdown: oneshot::Sender<oneshot::Sender<()>>,
+}
+
+pub type ReplSessions = Arc<Mutex<BTreeMap<CacheSourceTuple, ReplHandle>>>;
+
+struct ReplContext {
+    client: CountdownBotClient,
+    sessions: ReplSessions,
+    source: CacheSourceTuple,
+    id: u64,
+    language: String,
+    idle_timeout: Duration,
+    batch_interval: Duration,
+    line_limit: usize,
+    length_limit: usize,
+}
+
+impl ReplContext {
+    async fn flush(&self, buf: &mut Vec<u8>) {
+        if buf.is_empty() {
+            return;
+        }
+        let text = String::from_utf8_lossy(buf).to_string();
+        buf.clear();
+        if text.trim().is_empty() {
+            return;
+        }
+        let text = trim_output(text.trim_end(), self.line_limit, self.length_limit, false);
+        if let Err(e) = self
+            .client
+            .send_group_msg(self.source.gid, &text, true)
+            .await
+        {
+            error!("Failed to send repl output: {}", e);
+        }
+    }
+    async fn run(
+        self,
+        mut session: InteractiveSession,
+        mut input: mpsc::UnboundedReceiver<String>,
+        mut shutdown: oneshot::Receiver<oneshot::Sender<()>>,
+    ) {
+        let mut buf = vec![];
+        let mut idle_deadline = Instant::now() + self.idle_timeout;
+        let mut batch_deadline = Instant::now();
+        let (reason, ack) = loop {
+            tokio::select! {
+                line = input.recv() => match line {
+                    Some(line) => {
+                        idle_deadline = Instant::now() + self.idle_timeout;
+                        if session.stdin.send(line.into_bytes()).is_err() {
+                            break ("进程已退出", None);
+                        }
+                    }
+                    None => break ("已结束", None),
+                },
+                ack = &mut shutdown => match ack {
+                    Ok(ack) => break ("已随插件关闭结束", Some(ack)),
+                    Err(_) => break ("已结束", None),
+                },
+                data = session.output.recv() => match data {
+                    Some(data) => {
+                        if buf.is_empty() {
+                            batch_deadline = Instant::now() + self.batch_interval;
+                        }
+                        buf.extend_from_slice(&data);
+                        if buf.len() > self.length_limit * 4 {
+                            self.flush(&mut buf).await;
+                        }
+                    }
+                    None => break ("进程已退出", None),
+                },
+                _ = tokio::time::sleep_until(batch_deadline), if !buf.is_empty() => {
+                    self.flush(&mut buf).await;
+                }
+                _ = tokio::time::sleep_until(idle_deadline) => break ("长时间无操作，已自动结束", None),
+            }
+        };
+        info!(
+            "Repl session of {} in group {} finished: {}",
+            self.source.uid, self.source.gid, reason
+        );
+        // 关闭插件时只结束进程，不再发送消息
+        if let Some(ack) = ack {
+            session.kill().await;
+            ack.send(()).ok();
+            return;
+        }
+        self.flush(&mut buf).await;
+        session.kill().await;
+        {
+            let mut guard = self.sessions.lock().await;
+            if guard.get(&self.source).map(|v| v.id) == Some(self.id) {
+                guard.remove(&self.source);
+            }
+        }
+        self.client
+            .send_group_msg(
+                self.source.gid,
+                &format!(
+                    "{} 的 {} REPL会话{}",
+                    self.source.uid, self.language, reason
+                ),
+                true,
+            )
+            .await
+            .ok();
+    }
+}
+
+impl DockerRunnerPlugin {
+    pub async fn handle_repl(&mut self, sender: &SenderType, args: &[String]) -> ResultType<()> {
+        let evt = match sender {
+            SenderType::Group(e) => e,
+            _ => return Err(anyhow!("请在群内使用此指令!").into()),
+        };
+        let config = self.config.clone().unwrap();
+        if config.blacklist_users.contains(&(evt.user_id as i64)) {
+            return Err(anyhow!("你不被允许使用该指令!").into());
+        }
+        let client = self.client.clone().unwrap();
+        let source = CacheSourceTuple {
+            uid: evt.user_id as i64,
+            gid: evt.group_id,
+        };
+        let args = args
+            .iter()
+            .filter(|s| !s.trim().is_empty())
+            .collect::<Vec<&String>>();
+        let language = match args.first() {
+            Some(v) => v.as_str(),
+            None => {
+                let guard = self.repl_sessions.lock().await;
+                let running = guard
+                    .iter()
+                    .filter(|(k, _)| k.gid == evt.group_id)
+                    .map(|(k, v)| format!("{}({})", k.uid, v.language))
+                    .collect::<Vec<String>>();
+                let mut languages = config
+                    .language_setting
+                    .iter()
+                    .filter(|(_, v)| !v.repl.is_empty())
+                    .map(|(k, _)| k.clone())
+                    .collect::<Vec<String>>();
+                languages.sort();
+                client
+                    .quick_send_by_sender(
+                        sender,
+                        &format!(
+                            "支持REPL的语言有: {}\n本群正在运行的会话: {}",
+                            languages.join(" "),
+                            if running.is_empty() {
+                                String::from("无")
+                            } else {
+                                running.join(" ")
+                            }
+                        ),
+                    )
+                    .await?;
+                return Ok(());
+            }
+        };
+        if language == "stop" {
+            let text = match self.repl_sessions.lock().await.remove(&source) {
+                Some(_) => "正在结束REPL会话..",
+                None => "你没有正在运行的REPL会话",
+            };
+            client.quick_send_by_sender(sender, text).await?;
+            return Ok(());
+        }
+        let lang_config = config
+            .language_setting
+            .get(language)
+            .ok_or(anyhow!("非法语言ID: {}", language))?;
+        if lang_config.repl.is_empty() {
+            return Err(anyhow!("语言 {} 不支持REPL!", language).into());
+        }
+        {
+            let guard = self.repl_sessions.lock().await;
+            if guard.contains_key(&source) {
+                return Err(
+                    anyhow!("你已经有一个正在运行的REPL会话，请先使用 repl stop 结束").into(),
+                );
+            }
+            let count = guard.keys().filter(|k| k.gid == evt.group_id).count();
+            if count >= config.repl_max_sessions_per_group {
+                return Err(anyhow!(
+                    "本群同时运行的REPL会话已达上限 {} 个!",
+                    config.repl_max_sessions_per_group
+                )
+                .into());
+            }
+        }
+        let working_dir = tempfile::tempdir()?;
+        let session = self
+            .sandbox
+            .as_ref()
+            .unwrap()
+            .spawn_interactive(&SandboxRequest {
+                image: config.docker_image.clone(),
+                command: lang_config.repl.clone(),
+                working_dir: working_dir.path().to_path_buf(),
+                limits: lang_config.limits.clone(),
+                timeout: Duration::from_millis(config.repl_max_lifetime as u64),
+            })
+            .await?;
+        let (input_tx, input_rx) = mpsc::unbounded_channel();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let id = rand::random::<u64>();
+        self.repl_sessions.lock().await.insert(
+            source.clone(),
+            ReplHandle {
+                id,
+                language: language.to_string(),
+                input: input_tx,
+                shutdown: shutdown_tx,
+            },
+        );
+        let context = ReplContext {
+            client: client.clone(),
+            sessions: self.repl_sessions.clone(),
+            source,
+            id,
+            language: language.to_string(),
+            idle_timeout: Duration::from_millis(config.repl_idle_timeout as u64),
+            batch_interval: Duration::from_millis(config.repl_batch_interval as u64),
+            line_limit: config.new_line_count_limit as usize,
+            length_limit: config.outout_length_limit as usize,
+        };
+        tokio::spawn(async move {
+            // 会话结束前保留工作目录
+            let _working_dir = working_dir;
+            context.run(session, input_rx, shutdown_rx).await;
+        });
+        client
+            .quick_send_by_sender(
+                sender,
+                &format!(
+                    "已启动 {} REPL，直接发送消息即可输入，使用 repl stop 结束，{} 秒无操作后将自动结束",
+                    language,
+                    config.repl_idle_timeout / 1000
+                ),
+            )
+            .await?;
+        Ok(())
+    }
+    // 结束所有REPL会话，并等待容器删除完成
+    pub async fn stop_all_repl(&self) {
+        let handles = std::mem::take(&mut *self.repl_sessions.lock().await);
+        let mut pending = vec![];
+        for (_, handle) in handles.into_iter() {
+            let (tx, rx) = oneshot::channel();
+            if handle.shutdown.send(tx).is_ok() {
+                // 在会话确认前保留输入端，避免会话因输入关闭而不回复
+                pending.push((handle.input, rx));
+            }
+        }
+        for (_input, rx) in pending {
+            rx.await.ok();
+        }
+    }
+    // 将有REPL会话的用户发送的非指令消息写入会话
+    pub async fn handle_repl_message(&mut self, evt: &GroupMessageEvent) -> ResultType<()> {
+        if self
+            .command_prefix
+            .iter()
+            .any(|p| evt.raw_message.starts_with(p.as_str()))
+        {
+            return Ok(());
+        }
+        let guard = self.repl_sessions.lock().await;
+        if let Some(handle) = guard.get(&CacheSourceTuple {
+            uid: evt.user_id as i64,
+            gid: evt.group_id,
+        }) {
+            let mut line = html_escape::decode_html_entities(&evt.raw_message).to_string();
+            line.push('\n');
+            handle.input.send(line).ok();
+        }
+        Ok(())
+    }
+}
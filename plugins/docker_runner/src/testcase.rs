@@ -0,0 +1,81 @@
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::client::ResultType;
+
+// 测试数据格式:
+// 每组数据的输入与期望输出之间以单独一行 --- 分隔，各组数据之间以单独一行 === 分隔
+pub const CASE_SEPARATOR: &str = "===";
+pub const IO_SEPARATOR: &str = "---";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub input: String,
+    pub expected: String,
+}
+
+fn join_lines(lines: &[&str]) -> String {
+    let mut buf = lines.join("\n");
+    if !buf.is_empty() {
+        buf.push('\n');
+    }
+    buf
+}
+
+pub fn parse_testcases(text: &str) -> ResultType<Vec<TestCase>> {
+    let text = text.replace("\r\n", "\n");
+    let mut result = vec![];
+    let mut block: Vec<&str> = vec![];
+    let mut blocks: Vec<Vec<&str>> = vec![];
+    for line in text.lines() {
+        if line.trim() == CASE_SEPARATOR {
+            blocks.push(std::mem::take(&mut block));
+        } else {
+            block.push(line);
+        }
+    }
+    blocks.push(block);
+    for (i, block) in blocks.iter().enumerate() {
+        if block.iter().all(|s| s.trim().is_empty()) {
+            continue;
+        }
+        let sep = block
+            .iter()
+            .position(|s| s.trim() == IO_SEPARATOR)
+            .ok_or(anyhow!(
+                "第 {} 组数据缺少输入与输出之间的分隔行 {}",
+                i + 1,
+                IO_SEPARATOR
+            ))?;
+        result.push(TestCase {
+            input: join_lines(&block[..sep]),
+            expected: join_lines(&block[sep + 1..]),
+        });
+    }
+    if result.is_empty() {
+        return Err(anyhow!("未找到任何测试数据!").into());
+    }
+    Ok(result)
+}
+
+// 按空白分隔的单词及其所在行号(从1开始)
+fn tokens(text: &str) -> Vec<(usize, &str)> {
+    text.lines()
+        .enumerate()
+        .flat_map(|(i, line)| line.split_whitespace().map(move |s| (i + 1, s)))
+        .collect()
+}
+
+// 逐个单词比较，忽略所有空白的差异
+// 不同时返回第一处不同的单词在选手输出中的行号，选手输出提前结束时返回标准答案中的行号
+pub fn compare_output(output: &str, expected: &str) -> Option<usize> {
+    let output = tokens(output);
+    let expected = tokens(expected);
+    for i in 0..output.len().max(expected.len()) {
+        match (output.get(i), expected.get(i)) {
+            (Some(a), Some(b)) if a.1 == b.1 => {}
+            (Some(a), _) => return Some(a.0),
+            (None, Some(b)) => return Some(b.0),
+            (None, None) => unreachable!(),
+        }
+    }
+    None
+}
@@ -0,0 +1,137 @@
+use countdown_bot3::countdown_bot::sandbox::{ResourceLimits, SandboxResult};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    CompileError(String),
+    // 时间限制(毫秒)
+    TimeLimitExceeded(u64),
+    // 内存限制(MB)
+    MemoryLimitExceeded(i64),
+    RuntimeError {
+        exit_code: Option<i64>,
+        signal: Option<i32>,
+    },
+    Ok {
+        time_ms: Option<u64>,
+        memory_bytes: Option<u64>,
+    },
+}
+
+fn signal_name(signal: i32) -> &'static str {
+    match signal {
+        4 => "SIGILL",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        15 => "SIGTERM",
+        24 => "SIGXCPU",
+        25 => "SIGXFSZ",
+        _ => "",
+    }
+}
+
+impl Verdict {
+    pub fn from_run_result(
+        result: &SandboxResult,
+        limits: &ResourceLimits,
+        time_limit: u64,
+    ) -> Self {
+        if result.timed_out {
+            return Verdict::TimeLimitExceeded(time_limit);
+        }
+        if result.oom_killed {
+            return Verdict::MemoryLimitExceeded(limits.memory_mb);
+        }
+        let exit_code = result.exit_code.unwrap_or(0);
+        if exit_code != 0 || result.signal.is_some() {
+            // 通过shell执行时，被信号终止的进程退出码为128+信号
+            let signal = result
+                .signal
+                .or(if exit_code > 128 && exit_code <= 128 + 64 {
+                    Some((exit_code - 128) as i32)
+                } else {
+                    None
+                });
+            return Verdict::RuntimeError {
+                exit_code: result.exit_code,
+                signal,
+            };
+        }
+        Verdict::Ok {
+            time_ms: result.time_ms,
+            memory_bytes: result.memory_peak_bytes,
+        }
+    }
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Verdict::CompileError(_) => "CE",
+            Verdict::TimeLimitExceeded(_) => "TLE",
+            Verdict::MemoryLimitExceeded(_) => "MLE",
+            Verdict::RuntimeError { .. } => "RE",
+            Verdict::Ok { .. } => "OK",
+        }
+    }
+    pub fn describe(&self) -> String {
+        match self {
+            Verdict::CompileError(message) => format!("Compile Error\n{}", message),
+            Verdict::TimeLimitExceeded(limit) => format!("Time Limit Exceeded (>{}ms)", limit),
+            Verdict::MemoryLimitExceeded(limit) => {
+                format!("Memory Limit Exceeded (>{}MB)", limit)
+            }
+            Verdict::RuntimeError { exit_code, signal } => {
+                let mut details = vec![];
+                if let Some(code) = exit_code {
+                    details.push(format!("exit code {}", code));
+                }
+                if let Some(sig) = signal {
+                    details.push(
+                        format!("signal {} {}", sig, signal_name(*sig))
+                            .trim_end()
+                            .to_string(),
+                    );
+                }
+                if details.is_empty() {
+                    String::from("Runtime Error")
+                } else {
+                    format!("Runtime Error ({})", details.join(", "))
+                }
+            }
+            Verdict::Ok {
+                time_ms,
+                memory_bytes,
+            } => {
+                let mut buf = String::from("OK");
+                if let Some(t) = time_ms {
+                    buf.push_str(&format!(" | 用时 {}ms", t));
+                }
+                if let Some(m) = memory_bytes {
+                    buf.push_str(&format!(" | 内存 {:.2}MB", *m as f64 / (1 << 20) as f64));
+                }
+                buf
+            }
+        }
+    }
+}
+
+// 按行数与字符数截断输出
+pub fn trim_output(text: &str, line_limit: usize, length_limit: usize, truncated: bool) -> String {
+    let all_lines = text.split_inclusive('\n').collect::<Vec<&str>>();
+    let line_limit = line_limit.max(1);
+    let line_overflow = all_lines.len() > line_limit;
+    let mut output = all_lines[..all_lines.len().min(line_limit)].concat();
+    let mut length_overflow = truncated;
+    if output.chars().count() > length_limit {
+        length_overflow = true;
+        output = output.chars().take(length_limit).collect::<String>();
+    }
+    if length_overflow {
+        output.push_str("\n[超出长度部分已截断]");
+    }
+    if line_overflow {
+        output.push_str("\n[超出行数已截断]");
+    }
+    output
+}
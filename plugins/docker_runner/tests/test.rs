@@ -0,0 +1,35 @@
+use countdown_bot3::countdown_bot::sandbox::ResourceLimits;
+use docker_runner::{
+    config::LanguageSetting,
+    testcase::{compare_output, parse_testcases},
+};
+
+#[test]
+fn test_parse_testcases() {
+    let cases = parse_testcases("1 2\n---\n3\n===\n5 6\n---\n11\n").unwrap();
+    assert_eq!(cases.len(), 2);
+    assert_eq!(cases[0].input, "1 2\n");
+    assert_eq!(cases[0].expected, "3\n");
+    assert_eq!(cases[1].input, "5 6\n");
+    assert!(parse_testcases("1 2\n3").is_err());
+}
+
+#[test]
+fn test_compare_output() {
+    assert_eq!(compare_output("3  \n4\n\n", "3\n4"), None);
+    assert_eq!(compare_output("3\n5\n", "3\n4\n"), Some(2));
+    assert_eq!(compare_output("3\n", "3\n4\n"), Some(2));
+    assert_eq!(compare_output("1 2\n", "1\n  2\n"), None);
+    assert_eq!(compare_output("1\n2 3\n", "1\n2 4\n"), Some(2));
+}
+
+#[test]
+fn test_language_setting_defaults() {
+    let setting: LanguageSetting = serde_json::from_str(
+        r#"{"source_filename":"{name}.java","executable_filename":"Main","compile":"javac {source}","run":"java Main"}"#,
+    )
+    .unwrap();
+    assert!(setting.repl.is_empty());
+    assert_eq!(setting.limits, ResourceLimits::default());
+    assert_eq!(setting.compile_limits.memory_mb, 512);
+}
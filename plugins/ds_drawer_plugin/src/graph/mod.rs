@@ -0,0 +1,273 @@
+use std::collections::{BinaryHeap, VecDeque};
+
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::client::ResultType;
+use dot_writer::{Attributes, Color, DotWriter};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub weight: Option<i64>,
+}
+
+// 顶点编号从0开始，绘制时按输入的编号方式显示
+#[derive(Debug, Clone)]
+pub struct Graph {
+    pub vertex_count: usize,
+    pub edges: Vec<GraphEdge>,
+    pub directed: bool,
+    // 输入中的顶点编号是否从1开始
+    pub one_based: bool,
+}
+
+fn parse_number<T: std::str::FromStr>(token: &str) -> ResultType<T> {
+    token
+        .parse::<T>()
+        .map_err(|_| anyhow!("非法数字: {}", token).into())
+}
+
+impl Graph {
+    /*
+    解析 n m 以及m行 u v [w] 格式的图
+    weighted为None时根据数字个数判断是否带权
+    tree为true时允许省略m，此时边数为n-1
+    顶点编号中出现0时视为从0开始编号，否则视为从1开始
+    */
+    pub fn parse(
+        text: &str,
+        directed: bool,
+        weighted: Option<bool>,
+        tree: bool,
+    ) -> ResultType<Graph> {
+        let tokens = text.split_whitespace().collect::<Vec<&str>>();
+        if tokens.is_empty() {
+            return Err(anyhow!("请输入图的数据!").into());
+        }
+        let vertex_count = parse_number::<usize>(tokens[0])?;
+        if vertex_count == 0 {
+            return Err(anyhow!("顶点数必须为正数!").into());
+        }
+        // 顶点数与边数来自用户输入，乘法需要检查溢出
+        let tree_edges = vertex_count - 1;
+        let is_tree_data = |width: usize| tree_edges.checked_mul(width) == Some(tokens.len() - 1);
+        let (edge_count, rest) = if tree && (is_tree_data(2) || is_tree_data(3)) {
+            (tree_edges, &tokens[1..])
+        } else {
+            let count = parse_number::<usize>(tokens.get(1).ok_or(anyhow!("缺少边数!"))?)?;
+            (count, &tokens[2..])
+        };
+        let weighted = match weighted {
+            Some(v) => v,
+            None => edge_count.checked_mul(3) == Some(rest.len()) && edge_count > 0,
+        };
+        let width = if weighted { 3 } else { 2 };
+        let expected_len = edge_count
+            .checked_mul(width)
+            .ok_or(anyhow!("边数过大: {}", edge_count))?;
+        if rest.len() != expected_len {
+            return Err(anyhow!(
+                "数据个数不正确: {}条{}边需要{}个数，实际为{}个",
+                edge_count,
+                if weighted { "带权" } else { "" },
+                expected_len,
+                rest.len()
+            )
+            .into());
+        }
+        let mut edges = vec![];
+        for chunk in rest.chunks(width) {
+            edges.push(GraphEdge {
+                from: parse_number(chunk[0])?,
+                to: parse_number(chunk[1])?,
+                weight: if weighted {
+                    Some(parse_number(chunk[2])?)
+                } else {
+                    None
+                },
+            });
+        }
+        let one_based = !edges.iter().any(|v| v.from == 0 || v.to == 0);
+        let offset = if one_based { 1 } else { 0 };
+        for edge in edges.iter_mut() {
+            for vtx in [&mut edge.from, &mut edge.to] {
+                if *vtx < offset || *vtx - offset >= vertex_count {
+                    return Err(anyhow!("顶点编号 {} 超出范围", vtx).into());
+                }
+                *vtx -= offset;
+            }
+        }
+        Ok(Graph {
+            vertex_count,
+            edges,
+            directed,
+            one_based,
+        })
+    }
+    pub fn display_id(&self, vtx: usize) -> usize {
+        if self.one_based {
+            vtx + 1
+        } else {
+            vtx
+        }
+    }
+    // 将显示的顶点编号转换为内部编号
+    pub fn internal_id(&self, vtx: usize) -> ResultType<usize> {
+        let offset = if self.one_based { 1 } else { 0 };
+        if vtx < offset || vtx - offset >= self.vertex_count {
+            return Err(anyhow!("顶点编号 {} 超出范围", vtx).into());
+        }
+        Ok(vtx - offset)
+    }
+    // (相邻顶点, 边编号)
+    fn adjacency(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut result = vec![vec![]; self.vertex_count];
+        for (id, edge) in self.edges.iter().enumerate() {
+            result[edge.from].push((edge.to, id));
+            if !self.directed {
+                result[edge.to].push((edge.from, id));
+            }
+        }
+        result
+    }
+    // 以root为根时每个顶点的父边，图不是以root为根的树时返回错误
+    pub fn tree_parents(&self, root: usize) -> ResultType<Vec<Option<usize>>> {
+        if self.edges.len() != self.vertex_count - 1 {
+            return Err(anyhow!("这不是一棵树: 边数应为{}", self.vertex_count - 1).into());
+        }
+        let adjacency = self.adjacency();
+        let mut parents = vec![None; self.vertex_count];
+        let mut visited = vec![false; self.vertex_count];
+        let mut queue = VecDeque::from([root]);
+        visited[root] = true;
+        while let Some(vtx) = queue.pop_front() {
+            for (next, edge) in adjacency[vtx].iter() {
+                if !visited[*next] {
+                    visited[*next] = true;
+                    parents[*next] = Some(*edge);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        if visited.iter().any(|v| !v) {
+            return Err(anyhow!("这不是一棵树: 从根出发无法到达所有顶点").into());
+        }
+        Ok(parents)
+    }
+    // Dijkstra，无权图的边权视为1，返回(路径长度, 路径上的边编号)
+    pub fn shortest_path(
+        &self,
+        source: usize,
+        target: usize,
+    ) -> ResultType<Option<(i64, Vec<usize>)>> {
+        if self.edges.iter().any(|v| v.weight.unwrap_or(1) < 0) {
+            return Err(anyhow!("最短路不支持负权边!").into());
+        }
+        let adjacency = self.adjacency();
+        let mut dist = vec![i64::MAX; self.vertex_count];
+        let mut prev_edge = vec![None; self.vertex_count];
+        let mut heap = BinaryHeap::new();
+        dist[source] = 0;
+        heap.push(std::cmp::Reverse((0i64, source)));
+        while let Some(std::cmp::Reverse((d, vtx))) = heap.pop() {
+            if d > dist[vtx] {
+                continue;
+            }
+            for (next, edge) in adjacency[vtx].iter() {
+                let nd = d + self.edges[*edge].weight.unwrap_or(1);
+                if nd < dist[*next] {
+                    dist[*next] = nd;
+                    prev_edge[*next] = Some((*edge, vtx));
+                    heap.push(std::cmp::Reverse((nd, *next)));
+                }
+            }
+        }
+        if dist[target] == i64::MAX {
+            return Ok(None);
+        }
+        let mut path = vec![];
+        let mut curr = target;
+        while let Some((edge, prev)) = prev_edge[curr] {
+            path.push(edge);
+            curr = prev;
+        }
+        path.reverse();
+        Ok(Some((dist[target], path)))
+    }
+    // Kruskal，返回(权值和, 边编号, 是否连通)，不连通时为最小生成森林
+    pub fn minimum_spanning_tree(&self) -> ResultType<(i64, Vec<usize>, bool)> {
+        if self.directed {
+            return Err(anyhow!("有向图不支持最小生成树!").into());
+        }
+        let mut order = (0..self.edges.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|v| self.edges[*v].weight.unwrap_or(1));
+        let mut parent = (0..self.vertex_count).collect::<Vec<usize>>();
+        fn find(parent: &mut Vec<usize>, x: usize) -> usize {
+            if parent[x] != x {
+                parent[x] = find(parent, parent[x]);
+            }
+            parent[x]
+        }
+        let mut total = 0;
+        let mut chosen = vec![];
+        for id in order.into_iter() {
+            let edge = &self.edges[id];
+            let (a, b) = (find(&mut parent, edge.from), find(&mut parent, edge.to));
+            if a != b {
+                parent[a] = b;
+                total += edge.weight.unwrap_or(1);
+                chosen.push(id);
+            }
+        }
+        let connected = chosen.len() + 1 == self.vertex_count;
+        Ok((total, chosen, connected))
+    }
+    /*
+    highlighted中的边与其端点以红色粗线绘制
+    tree_parents不为None时按树的父子方向绘制，使dot自动按层排列
+    */
+    pub fn generate_graph(
+        &self,
+        highlighted: &[usize],
+        tree_parents: Option<&[Option<usize>]>,
+    ) -> Vec<u8> {
+        let mut out_buf = Vec::<u8>::new();
+        {
+            let mut writer = DotWriter::from(&mut out_buf);
+            let mut digraph = writer.digraph();
+            let mut highlighted_vertices = vec![false; self.vertex_count];
+            for id in highlighted.iter() {
+                highlighted_vertices[self.edges[*id].from] = true;
+                highlighted_vertices[self.edges[*id].to] = true;
+            }
+            for vtx in 0..self.vertex_count {
+                let mut node = digraph.node_named(vtx.to_string());
+                node.set_label(&self.display_id(vtx).to_string());
+                node.set("shape", "circle", false);
+                if highlighted_vertices[vtx] {
+                    node.set_color(Color::Red);
+                }
+            }
+            for (id, edge) in self.edges.iter().enumerate() {
+                let (from, to) = match tree_parents {
+                    Some(parents) if parents[edge.from] == Some(id) => (edge.to, edge.from),
+                    _ => (edge.from, edge.to),
+                };
+                let mut edge_list = digraph.edge(from.to_string(), to.to_string());
+                let attributes = edge_list.attributes();
+                if let Some(w) = edge.weight {
+                    attributes.set_label(&w.to_string());
+                }
+                if !self.directed {
+                    attributes.set("dir", "none", false);
+                }
+                if highlighted.contains(&id) {
+                    attributes
+                        .set_color(Color::Red)
+                        .set("penwidth", "2.5", false);
+                }
+            }
+        }
+        return out_buf;
+    }
+}
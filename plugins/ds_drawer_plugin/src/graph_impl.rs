@@ -0,0 +1,146 @@
+use clap::{App, Arg};
+use countdown_bot3::countdown_bot::{
+    client::ResultType, command::SenderType, utils::fetch_luogu_pasteboard,
+};
+use log::info;
+
+use crate::{graph::Graph, DSDrawerPlugin};
+use anyhow::anyhow;
+
+impl DSDrawerPlugin {
+    pub async fn draw_graph(&self, args: Vec<String>, sender: &SenderType) -> ResultType<()> {
+        let config = self.config.as_ref().unwrap();
+        let parse_ret = App::new("graph")
+            .about("绘制图，输入格式为 n m 以及m行 u v [w]，顶点从1开始编号(出现0时从0开始)")
+            .arg(
+                Arg::new("from-paste")
+                    .short('p')
+                    .long("from-paste")
+                    .help("从洛谷剪贴板读取图")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::new("directed")
+                    .short('d')
+                    .long("directed")
+                    .help("有向图"),
+            )
+            .arg(
+                Arg::new("weighted")
+                    .short('w')
+                    .long("weighted")
+                    .help("带权图(默认根据数据个数判断)"),
+            )
+            .arg(
+                Arg::new("root")
+                    .short('r')
+                    .long("root")
+                    .help("按树的形式绘制，并指定根(此时可省略边数m)")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::new("path")
+                    .long("path")
+                    .help("标出从S到T的最短路")
+                    .number_of_values(2)
+                    .value_names(&["S", "T"]),
+            )
+            .arg(Arg::new("mst").long("mst").help("标出最小生成树"))
+            .arg(
+                Arg::new("DATA")
+                    .multiple_values(true)
+                    .takes_value(true)
+                    .help("图的数据"),
+            )
+            .setting(clap::AppSettings::NoBinaryName)
+            .setting(clap::AppSettings::DisableVersionFlag)
+            .setting(clap::AppSettings::AllowNegativeNumbers)
+            .color(clap::ColorChoice::Never)
+            .try_get_matches_from(
+                args.join(" ")
+                    .split_whitespace()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>(),
+            )?;
+        let text = if let Some(url) = parse_ret.value_of("from-paste") {
+            fetch_luogu_pasteboard(url).await?
+        } else {
+            parse_ret
+                .values_of("DATA")
+                .map(|v| v.collect::<Vec<&str>>().join(" "))
+                .unwrap_or_default()
+        };
+        let weighted = if parse_ret.is_present("weighted") {
+            Some(true)
+        } else {
+            None
+        };
+        let graph = Graph::parse(
+            &text,
+            parse_ret.is_present("directed"),
+            weighted,
+            parse_ret.is_present("root"),
+        )?;
+        if graph.vertex_count > config.max_graph_vertices as usize {
+            return Err(anyhow!(
+                "顶点数 {} 超出了上限 {}",
+                graph.vertex_count,
+                config.max_graph_vertices
+            )
+            .into());
+        }
+        if graph.edges.len() > config.max_graph_edges as usize {
+            return Err(anyhow!(
+                "边数 {} 超出了上限 {}",
+                graph.edges.len(),
+                config.max_graph_edges
+            )
+            .into());
+        }
+        info!(
+            "Drawing graph with {} vertices and {} edges",
+            graph.vertex_count,
+            graph.edges.len()
+        );
+        let parse_vertex = |s: &str| -> ResultType<usize> {
+            graph.internal_id(
+                s.parse::<usize>()
+                    .map_err(|_| anyhow!("非法的顶点编号: {}", s))?,
+            )
+        };
+        let tree_parents = match parse_ret.value_of("root") {
+            Some(root) => Some(graph.tree_parents(parse_vertex(root)?)?),
+            None => None,
+        };
+        let mut highlighted = vec![];
+        let mut captions = vec![];
+        if let Some(mut values) = parse_ret.values_of("path") {
+            let (source, target) = (values.next().unwrap(), values.next().unwrap());
+            match graph.shortest_path(parse_vertex(source)?, parse_vertex(target)?)? {
+                Some((length, path)) => {
+                    captions.push(format!("{} 到 {} 的最短路长度: {}", source, target, length));
+                    highlighted.extend(path);
+                }
+                None => captions.push(format!("{} 无法到达 {}", source, target)),
+            }
+        }
+        if parse_ret.is_present("mst") {
+            let (total, edges, connected) = graph.minimum_spanning_tree()?;
+            captions.push(if connected {
+                format!("最小生成树的权值和: {}", total)
+            } else {
+                format!("图不连通，最小生成森林的权值和: {}", total)
+            });
+            highlighted.extend(edges);
+        }
+        if !captions.is_empty() {
+            self.client
+                .clone()
+                .unwrap()
+                .quick_send_by_sender(sender, &captions.join("\n"))
+                .await?;
+        }
+        let dot_data = graph.generate_graph(&highlighted, tree_parents.as_deref());
+        self.render_dot(dot_data, sender).await
+    }
+}
@@ -3,31 +3,237 @@ use std::time::Duration;
 use countdown_bot3::countdown_bot::{client::ResultType, command::SenderType};
 use log::{error, info};
 
-use crate::{sam::SAMPool, DSDrawerPlugin};
+use crate::{
+    kmp::KMP, layout, pam::PAM, sam::SAMPool, suffix_tree::SuffixTree, trie::Trie, DSDrawerPlugin,
+    LayoutEngine,
+};
 use anyhow::anyhow;
 impl DSDrawerPlugin {
-    pub async fn generate_sam(&self, text: &str, sender: &SenderType) -> ResultType<()> {
-        info!("Generating SAM: {}", text);
+    // 生成对应指令的数据结构的dot代码，以及需要额外发送的文本
+    fn build_graph(command: &str, text: &str) -> ResultType<(Vec<u8>, Option<String>)> {
+        Ok(match command {
+            "sam" => {
+                let mut pool = SAMPool::default();
+                for (id, s) in text.split("|").enumerate() {
+                    pool.join_string(s, id as i32);
+                }
+                pool.collect();
+                (pool.generate_graph(), None)
+            }
+            "trie" | "acam" => {
+                let mut trie = Trie::default();
+                for (id, s) in text.split("|").enumerate() {
+                    trie.insert(s, id + 1);
+                }
+                if command == "acam" {
+                    trie.build_fail();
+                }
+                (trie.generate_graph(command == "acam"), None)
+            }
+            "suffixtree" => (SuffixTree::build(text).generate_graph(), None),
+            "pam" => (PAM::build(text).generate_graph(), None),
+            "kmp" => {
+                let kmp = KMP::build(text);
+                let caption = format!(
+                    "fail数组(长度为i的前缀的最长border): {}",
+                    kmp.fail
+                        .iter()
+                        .skip(1)
+                        .map(|v| v.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                );
+                (kmp.generate_graph(), Some(caption))
+            }
+            _ => return Err(anyhow!("未知指令: {}", command).into()),
+        })
+    }
+    // 在后缀自动机上执行文字查询
+    fn query_sam(subcommand: &str, param: Option<&str>, text: &str) -> ResultType<String> {
+        let strings = text.split("|").collect::<Vec<&str>>();
+        let mut pool = SAMPool::default();
+        for (id, s) in strings.iter().enumerate() {
+            pool.join_string(s, id as i32);
+        }
+        pool.collect();
+        let require_param = || param.ok_or(anyhow!("缺少参数!"));
+        Ok(match subcommand {
+            "distinct" => format!("本质不同的子串个数: {}", pool.count_distinct_substrings()),
+            "lcs" => {
+                let result = pool.longest_common_substring(strings.len() as i32);
+                if result.is_empty() {
+                    String::from("不存在公共子串")
+                } else {
+                    format!("最长公共子串: {} (长度 {})", result, result.chars().count())
+                }
+            }
+            "count" => {
+                let pattern = require_param()?;
+                let counts = pool.count_occurrences(pattern);
+                let count_of = |id: usize| counts.get(&(id as i32)).copied().unwrap_or(0);
+                if strings.len() == 1 {
+                    format!("{} 出现了 {} 次", pattern, count_of(0))
+                } else {
+                    (0..strings.len())
+                        .map(|id| format!("{} 在字符串{}中出现了 {} 次", pattern, id, count_of(id)))
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                }
+            }
+            "kth" => {
+                let k = require_param()?
+                    .parse::<u64>()
+                    .map_err(|_| anyhow!("非法的k: {}", require_param().unwrap()))?;
+                match pool.kth_substring(k) {
+                    Some(v) => format!("字典序第{}小的子串: {}", k, v),
+                    None => format!(
+                        "k超出范围，本质不同的子串只有 {} 个",
+                        pool.count_distinct_substrings()
+                    ),
+                }
+            }
+            _ => return Err(anyhow!("未知的查询: {}", subcommand).into()),
+        })
+    }
+    // samq <distinct|lcs|count|kth> [模式串或k] <字符串>
+    pub async fn sam_query(&self, args: Vec<String>, sender: &SenderType) -> ResultType<()> {
+        let config = self.config.as_ref().unwrap();
+        let subcommand = args
+            .first()
+            .filter(|v| !v.is_empty())
+            .ok_or(anyhow!("请输入查询类型: distinct, lcs, count 或 kth"))?
+            .clone();
+        let (param, text) = match subcommand.as_str() {
+            "count" | "kth" => (args.get(1).cloned(), args.iter().skip(2)),
+            _ => (None, args.iter().skip(1)),
+        };
+        let text = text.cloned().collect::<Vec<String>>().join(" ");
+        if text.is_empty() {
+            return Err(anyhow!("请输入字符串!").into());
+        }
+        let string_count = text.split('|').count();
+        if string_count > config.max_query_strings as usize {
+            return Err(anyhow!(
+                "字符串个数 {} 超出了上限 {}",
+                string_count,
+                config.max_query_strings
+            )
+            .into());
+        }
+        let length = text.chars().filter(|c| *c != '|').count();
+        if length > config.max_query_length as usize {
+            return Err(anyhow!(
+                "字符串总长度 {} 超出了上限 {}",
+                length,
+                config.max_query_length
+            )
+            .into());
+        }
+        info!("SAM query {}: {}", subcommand, text);
+        let result = tokio::task::spawn_blocking(move || {
+            Self::query_sam(&subcommand, param.as_deref(), &text).map_err(|e| e.to_string())
+        })
+        .await?
+        .map_err(|e| anyhow!("{}", e))?;
+        self.client
+            .clone()
+            .unwrap()
+            .quick_send_by_sender(sender, &result)
+            .await?;
+        Ok(())
+    }
+    pub async fn generate_structure(
+        &self,
+        command: &str,
+        text: &str,
+        sender: &SenderType,
+    ) -> ResultType<()> {
+        info!("Generating {}: {}", command, text);
         let config = self.config.as_ref().unwrap();
+        // 多个字符串时限制总长度，不包括分隔符
+        let length = text.chars().filter(|c| *c != '|').count();
+        if length > config.max_string_length as usize {
+            return Err(anyhow!(
+                "字符串总长度 {} 超出了上限 {}",
+                length,
+                config.max_string_length
+            )
+            .into());
+        }
+        let command_cloned = command.to_string();
         let text_cloned = text.to_string();
-        let dot_data = tokio::task::spawn_blocking(move || {
-            let mut pool = SAMPool::default();
-            for (id, s) in text_cloned.split("|").enumerate() {
-                pool.join_string(s, id as i32);
-            }
-            pool.collect();
-            pool.generate_graph()
+        let (dot_data, caption) = tokio::task::spawn_blocking(move || {
+            Self::build_graph(&command_cloned, &text_cloned).map_err(|e| e.to_string())
         })
-        .await?;
+        .await?
+        .map_err(|e| anyhow!("{}", e))?;
+        if let Some(v) = caption {
+            self.client
+                .clone()
+                .unwrap()
+                .quick_send_by_sender(sender, &v)
+                .await?;
+        }
+        self.render_dot(dot_data, sender).await
+    }
+    pub(crate) async fn render_dot(
+        &self,
+        dot_data: Vec<u8>,
+        sender: &SenderType,
+    ) -> ResultType<()> {
+        let config = self.config.as_ref().unwrap();
+        let rendered = match config.layout_engine {
+            LayoutEngine::Builtin => None,
+            LayoutEngine::Graphviz => Some(
+                self.render_with_graphviz(&dot_data)
+                    .await?
+                    .ok_or(anyhow!("找不到dot: {}", config.dot_executable))?,
+            ),
+            LayoutEngine::Auto => self.render_with_graphviz(&dot_data).await?,
+        };
+        let img_data = match rendered {
+            Some(v) => v,
+            None => {
+                info!("Rendering with builtin layout");
+                tokio::task::spawn_blocking(move || {
+                    layout::render_png(&dot_data).map_err(|e| e.to_string())
+                })
+                .await?
+                .map_err(|e| anyhow!("{}", e))?
+            }
+        };
+        let b64enc = base64::encode(img_data);
+        self.client
+            .clone()
+            .unwrap()
+            .quick_send_by_sender_ex(
+                sender,
+                format!("[CQ:image,file=base64://{}]", b64enc).as_str(),
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+    // 使用Graphviz绘制，找不到dot时返回None
+    async fn render_with_graphviz(&self, dot_data: &[u8]) -> ResultType<Option<Vec<u8>>> {
+        let config = self.config.as_ref().unwrap();
         let work_dir = tempfile::tempdir()?;
         let dot_file = work_dir.path().join("out.dot");
         let png_file = work_dir.path().join("out.png");
         tokio::fs::write(dot_file.clone(), dot_data).await?;
-        let mut process = tokio::process::Command::new(config.dot_executable.clone())
+        let mut process = match tokio::process::Command::new(config.dot_executable.clone())
             .arg("-Tpng")
             .args(&["-o", png_file.to_str().unwrap()])
             .arg(dot_file.to_str().unwrap())
-            .spawn()?;
+            .spawn()
+        {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!("{} not found", config.dot_executable);
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
         info!("Rendering image to {:?}, using {:?}", png_file, dot_file);
         match tokio::time::timeout(
             Duration::from_secs(config.dot_timeout as u64),
@@ -45,18 +251,7 @@ impl DSDrawerPlugin {
                     error!("{}", exit_status);
                     return Err(anyhow!("执行dot失败!\n{}", exit_status).into());
                 } else {
-                    let img_data = tokio::fs::read(png_file).await?;
-                    let b64enc = base64::encode(img_data);
-                    self.client
-                        .clone()
-                        .unwrap()
-                        .quick_send_by_sender_ex(
-                            sender,
-                            format!("[CQ:image,file=base64://{}]", b64enc).as_str(),
-                            false,
-                        )
-                        .await?;
-                    return Ok(());
+                    return Ok(Some(tokio::fs::read(png_file).await?));
                 }
             }
         };
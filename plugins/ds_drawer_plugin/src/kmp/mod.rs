@@ -0,0 +1,54 @@
+use dot_writer::{Attributes, Color, DotWriter};
+
+pub struct KMP {
+    pub chars: Vec<char>,
+    // fail[i]为长度为i的前缀的最长公共前后缀(border)长度，fail[0]为0
+    pub fail: Vec<usize>,
+}
+
+impl KMP {
+    pub fn build(text: &str) -> KMP {
+        let chars = text.chars().collect::<Vec<char>>();
+        let mut fail = vec![0; chars.len() + 1];
+        for i in 1..chars.len() {
+            let mut j = fail[i];
+            while j > 0 && chars[i] != chars[j] {
+                j = fail[j];
+            }
+            if chars[i] == chars[j] {
+                j += 1;
+            }
+            fail[i + 1] = j;
+        }
+        KMP { chars, fail }
+    }
+    // 节点i表示已匹配长度为i的前缀，红色虚线为失配指针
+    pub fn generate_graph(&self) -> Vec<u8> {
+        let mut out_buf = Vec::<u8>::new();
+        {
+            let mut writer = DotWriter::from(&mut out_buf);
+            let mut digraph = writer.digraph();
+            digraph.graph_attributes().set("rankdir", "LR", false);
+            for (i, fail) in self.fail.iter().enumerate() {
+                digraph
+                    .node_named(i.to_string())
+                    .set_label(&format!("{}\nfail={}", i, fail));
+                if i < self.chars.len() {
+                    digraph
+                        .edge(i.to_string(), (i + 1).to_string())
+                        .attributes()
+                        .set_label(&String::from(self.chars[i]));
+                }
+                if i > 0 {
+                    digraph
+                        .edge(i.to_string(), fail.to_string())
+                        .attributes()
+                        .set_color(Color::Red)
+                        .set("style", "dashed", false)
+                        .set("constraint", "false", false);
+                }
+            }
+        }
+        return out_buf;
+    }
+}
@@ -0,0 +1,437 @@
+use std::{collections::HashMap, fmt::Write};
+
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::text_render::svg_to_png;
+
+use self::{
+    parser::{parse_dot, Attributes, DotGraph},
+    sugiyama::{layout, Layout, LayoutEdge},
+};
+
+pub mod parser;
+pub mod sugiyama;
+
+const FONT_SIZE: f64 = 14.0;
+const LINE_HEIGHT: f64 = 18.0;
+const EDGE_FONT_SIZE: f64 = 12.0;
+const ARROW_LENGTH: f64 = 10.0;
+// 两点之间的平行边相互错开的距离
+const PARALLEL_OFFSET: f64 = 18.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Shape {
+    Ellipse,
+    Circle,
+    DoubleCircle,
+    Box,
+    Point,
+}
+
+struct NodeGeometry {
+    shape: Shape,
+    lines: Vec<String>,
+    // 半宽与半高
+    rx: f64,
+    ry: f64,
+}
+
+// 粗略估计文本宽度，非ASCII字符按全角计算
+fn text_width(text: &str, font_size: f64) -> f64 {
+    text.chars()
+        .map(|c| if c.is_ascii() { 0.6 } else { 1.0 })
+        .sum::<f64>()
+        * font_size
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn node_geometry(name: &str, attributes: &Attributes) -> NodeGeometry {
+    let label = attributes.get("label").map(|v| v.as_str()).unwrap_or(name);
+    let lines = if label.is_empty() {
+        vec![]
+    } else {
+        label.split('\n').map(|v| v.to_string()).collect::<Vec<_>>()
+    };
+    let text_w = lines
+        .iter()
+        .map(|v| text_width(v, FONT_SIZE))
+        .fold(0.0, f64::max);
+    let text_h = lines.len() as f64 * LINE_HEIGHT;
+    let shape = match attributes.get("shape").map(|v| v.as_str()) {
+        Some("circle") => Shape::Circle,
+        Some("doublecircle") => Shape::DoubleCircle,
+        Some("box") | Some("rect") | Some("rectangle") | Some("square") | Some("record") => {
+            Shape::Box
+        }
+        Some("point") => Shape::Point,
+        _ => Shape::Ellipse,
+    };
+    let (rx, ry) = match shape {
+        Shape::Ellipse => (
+            ((text_w + 12.0) * 0.71).max(27.0),
+            ((text_h + 8.0) * 0.71).max(18.0),
+        ),
+        Shape::Circle | Shape::DoubleCircle => {
+            let r = ((text_w + 12.0).max(text_h + 8.0) / 2.0).max(15.0)
+                + if shape == Shape::DoubleCircle {
+                    4.0
+                } else {
+                    0.0
+                };
+            (r, r)
+        }
+        Shape::Box => ((text_w + 16.0) / 2.0, ((text_h + 10.0) / 2.0).max(14.0)),
+        Shape::Point => (3.0, 3.0),
+    };
+    NodeGeometry {
+        shape,
+        lines,
+        rx,
+        ry,
+    }
+}
+
+// 从节点中心沿指向target的方向与节点边界的交点
+fn clip_to_boundary(center: (f64, f64), node: &NodeGeometry, target: (f64, f64)) -> (f64, f64) {
+    let (dx, dy) = (target.0 - center.0, target.1 - center.1);
+    if dx.abs() < 1e-9 && dy.abs() < 1e-9 {
+        return center;
+    }
+    let t = if node.shape == Shape::Box {
+        (node.rx / dx.abs()).min(node.ry / dy.abs())
+    } else {
+        1.0 / ((dx / node.rx).powi(2) + (dy / node.ry).powi(2)).sqrt()
+    };
+    (center.0 + dx * t, center.1 + dy * t)
+}
+
+// 尖端位于tip，方向为from指向tip
+fn arrow_head(
+    svg: &mut String,
+    from: (f64, f64),
+    tip: (f64, f64),
+    color: &str,
+) -> std::fmt::Result {
+    let (dx, dy) = (tip.0 - from.0, tip.1 - from.1);
+    let len = (dx * dx + dy * dy).sqrt().max(1e-9);
+    let (ux, uy) = (dx / len, dy / len);
+    let back = (tip.0 - ux * ARROW_LENGTH, tip.1 - uy * ARROW_LENGTH);
+    let half = ARROW_LENGTH * 0.4;
+    write!(
+        svg,
+        r#"<polygon points="{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}" fill="{}" stroke="{}"/>"#,
+        tip.0,
+        tip.1,
+        back.0 - uy * half,
+        back.1 + ux * half,
+        back.0 + uy * half,
+        back.1 - ux * half,
+        color,
+        color
+    )
+}
+
+fn text_lines(
+    svg: &mut String,
+    lines: &[String],
+    center: (f64, f64),
+    font_size: f64,
+    color: &str,
+) -> std::fmt::Result {
+    let top = center.1 - (lines.len() as f64 - 1.0) * LINE_HEIGHT / 2.0;
+    for (index, line) in lines.iter().enumerate() {
+        write!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" font-size="{:.0}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+            center.0,
+            top + index as f64 * LINE_HEIGHT,
+            font_size,
+            color,
+            escape_xml(line)
+        )?;
+    }
+    Ok(())
+}
+
+fn stroke_attributes(attributes: &Attributes, default_width: f64) -> String {
+    let color = attributes
+        .get("color")
+        .map(|v| v.as_str())
+        .unwrap_or("black");
+    let style = attributes.get("style").map(|v| v.as_str()).unwrap_or("");
+    let mut width = attributes
+        .get("penwidth")
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(default_width);
+    if style.contains("bold") {
+        width = width.max(2.0);
+    }
+    let dash = if style.contains("dashed") {
+        r#" stroke-dasharray="5,3""#
+    } else if style.contains("dotted") {
+        r#" stroke-dasharray="1,3""#
+    } else {
+        ""
+    };
+    format!(
+        r#"stroke="{}" stroke-width="{:.1}"{}"#,
+        escape_xml(color),
+        width,
+        dash
+    )
+}
+
+fn write_svg(graph: &DotGraph, nodes: &[NodeGeometry], layout: &Layout) -> anyhow::Result<String> {
+    let mut svg = String::new();
+    write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" font-family="sans-serif">"#,
+        layout.width.ceil(),
+        layout.height.ceil()
+    )?;
+    write!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+    let left_to_right = graph.attributes.get("rankdir").map(|v| v.as_str()) == Some("LR");
+    // 同一对节点之间的边的数量，用于错开平行边
+    let mut parallel = HashMap::<(usize, usize), (usize, usize)>::new();
+    for edge in graph.edges.iter() {
+        let key = (edge.from.min(edge.to), edge.from.max(edge.to));
+        parallel.entry(key).or_insert((0, 0)).1 += 1;
+    }
+    for (edge, route) in graph.edges.iter().zip(layout.routes.iter()) {
+        let key = (edge.from.min(edge.to), edge.from.max(edge.to));
+        let slot = parallel.get_mut(&key).unwrap();
+        let offset = (slot.0 as f64 - (slot.1 as f64 - 1.0) / 2.0) * PARALLEL_OFFSET;
+        slot.0 += 1;
+        let color = edge
+            .attributes
+            .get("color")
+            .map(|v| escape_xml(v))
+            .unwrap_or(String::from("black"));
+        let dir = edge
+            .attributes
+            .get("dir")
+            .map(|v| v.as_str())
+            .unwrap_or(if graph.directed { "forward" } else { "none" });
+        let (from_center, to_center) = (layout.positions[edge.from], layout.positions[edge.to]);
+        let (from_node, to_node) = (&nodes[edge.from], &nodes[edge.to]);
+        // 路径的起点、终点、两端的切线方向上的点以及标签位置
+        let (path, start, start_dir, end, end_dir, label_at);
+        if edge.from == edge.to {
+            let (cx, cy) = from_center;
+            let size = 24.0 + offset.abs();
+            let (a, b, c1, c2) = if left_to_right {
+                (
+                    (cx - from_node.rx * 0.5, cy - from_node.ry * 0.8),
+                    (cx + from_node.rx * 0.5, cy - from_node.ry * 0.8),
+                    (cx - from_node.rx, cy - from_node.ry - size * 1.5),
+                    (cx + from_node.rx, cy - from_node.ry - size * 1.5),
+                )
+            } else {
+                (
+                    (cx + from_node.rx * 0.8, cy - from_node.ry * 0.5),
+                    (cx + from_node.rx * 0.8, cy + from_node.ry * 0.5),
+                    (cx + from_node.rx + size * 1.5, cy - from_node.ry),
+                    (cx + from_node.rx + size * 1.5, cy + from_node.ry),
+                )
+            };
+            path = format!(
+                "M{:.1},{:.1} C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}",
+                a.0, a.1, c1.0, c1.1, c2.0, c2.1, b.0, b.1
+            );
+            start = a;
+            start_dir = c1;
+            end = b;
+            end_dir = c2;
+            label_at = ((c1.0 + c2.0) / 2.0, (c1.1 + c2.1) / 2.0);
+        } else if route.layered && (!route.bends.is_empty() || offset == 0.0) {
+            let mut points = vec![];
+            points.push(clip_to_boundary(
+                from_center,
+                from_node,
+                *route.bends.first().unwrap_or(&to_center),
+            ));
+            points.extend(route.bends.iter().copied());
+            points.push(clip_to_boundary(
+                to_center,
+                to_node,
+                *route.bends.last().unwrap_or(&from_center),
+            ));
+            // 经过折点时用二次贝塞尔曲线平滑
+            let mut d = format!("M{:.1},{:.1}", points[0].0, points[0].1);
+            for i in 1..points.len() - 1 {
+                let mid = (
+                    (points[i].0 + points[i + 1].0) / 2.0,
+                    (points[i].1 + points[i + 1].1) / 2.0,
+                );
+                write!(
+                    d,
+                    " Q{:.1},{:.1} {:.1},{:.1}",
+                    points[i].0, points[i].1, mid.0, mid.1
+                )?;
+            }
+            let last = points[points.len() - 1];
+            write!(d, " L{:.1},{:.1}", last.0, last.1)?;
+            path = d;
+            start = points[0];
+            start_dir = points[1];
+            end = last;
+            end_dir = points[points.len() - 2];
+            let mid = points.len() / 2;
+            label_at = if points.len() % 2 == 1 {
+                points[mid]
+            } else {
+                (
+                    (points[mid - 1].0 + points[mid].0) / 2.0,
+                    (points[mid - 1].1 + points[mid].1) / 2.0,
+                )
+            };
+        } else {
+            // 不参与分层的边与平行边绘制为弧线，向前进方向的左侧弯曲
+            let (dx, dy) = (to_center.0 - from_center.0, to_center.1 - from_center.1);
+            let len = (dx * dx + dy * dy).sqrt().max(1e-9);
+            let bend = if route.layered {
+                offset
+            } else {
+                len * 0.25 + 15.0 + offset
+            };
+            let control = (
+                (from_center.0 + to_center.0) / 2.0 + dy / len * bend,
+                (from_center.1 + to_center.1) / 2.0 - dx / len * bend,
+            );
+            start = clip_to_boundary(from_center, from_node, control);
+            end = clip_to_boundary(to_center, to_node, control);
+            path = format!(
+                "M{:.1},{:.1} Q{:.1},{:.1} {:.1},{:.1}",
+                start.0, start.1, control.0, control.1, end.0, end.1
+            );
+            start_dir = control;
+            end_dir = control;
+            label_at = (
+                0.25 * start.0 + 0.5 * control.0 + 0.25 * end.0,
+                0.25 * start.1 + 0.5 * control.1 + 0.25 * end.1,
+            );
+        }
+        write!(
+            svg,
+            r#"<path d="{}" fill="none" {}/>"#,
+            path,
+            stroke_attributes(&edge.attributes, 1.0)
+        )?;
+        if dir == "forward" || dir == "both" {
+            arrow_head(&mut svg, end_dir, end, &color)?;
+        }
+        if dir == "back" || dir == "both" {
+            arrow_head(&mut svg, start_dir, start, &color)?;
+        }
+        if let Some(label) = edge.attributes.get("label").filter(|v| !v.is_empty()) {
+            let lines = label.split('\n').map(|v| v.to_string()).collect::<Vec<_>>();
+            let width = lines
+                .iter()
+                .map(|v| text_width(v, EDGE_FONT_SIZE))
+                .fold(0.0, f64::max);
+            let font_color = edge
+                .attributes
+                .get("fontcolor")
+                .map(|v| escape_xml(v))
+                .unwrap_or(color.clone());
+            text_lines(
+                &mut svg,
+                &lines,
+                (label_at.0 + width / 2.0 + 4.0, label_at.1),
+                EDGE_FONT_SIZE,
+                &font_color,
+            )?;
+        }
+    }
+    for (index, (node, dot_node)) in nodes.iter().zip(graph.nodes.iter()).enumerate() {
+        let (cx, cy) = layout.positions[index];
+        let attributes = &dot_node.attributes;
+        let style = attributes.get("style").map(|v| v.as_str()).unwrap_or("");
+        let fill = if node.shape == Shape::Point {
+            attributes
+                .get("color")
+                .map(|v| escape_xml(v))
+                .unwrap_or(String::from("black"))
+        } else if style.contains("filled") {
+            attributes
+                .get("fillcolor")
+                .or(attributes.get("color"))
+                .map(|v| escape_xml(v))
+                .unwrap_or(String::from("lightgrey"))
+        } else {
+            String::from("white")
+        };
+        let stroke = stroke_attributes(attributes, 1.0);
+        match node.shape {
+            Shape::Box => write!(
+                svg,
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" {}/>"#,
+                cx - node.rx,
+                cy - node.ry,
+                node.rx * 2.0,
+                node.ry * 2.0,
+                fill,
+                stroke
+            )?,
+            _ => write!(
+                svg,
+                r#"<ellipse cx="{:.1}" cy="{:.1}" rx="{:.1}" ry="{:.1}" fill="{}" {}/>"#,
+                cx, cy, node.rx, node.ry, fill, stroke
+            )?,
+        }
+        if node.shape == Shape::DoubleCircle {
+            write!(
+                svg,
+                r#"<ellipse cx="{:.1}" cy="{:.1}" rx="{:.1}" ry="{:.1}" fill="none" {}/>"#,
+                cx,
+                cy,
+                node.rx - 4.0,
+                node.ry - 4.0,
+                stroke
+            )?;
+        }
+        let font_color = attributes
+            .get("fontcolor")
+            .map(|v| escape_xml(v))
+            .unwrap_or(String::from("black"));
+        text_lines(&mut svg, &node.lines, (cx, cy), FONT_SIZE, &font_color)?;
+    }
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+// 不依赖Graphviz，使用内置的分层布局将dot代码绘制为SVG
+pub fn render_svg(dot: &str) -> anyhow::Result<String> {
+    let graph = parse_dot(dot)?;
+    let nodes = graph
+        .nodes
+        .iter()
+        .map(|v| node_geometry(&v.name, &v.attributes))
+        .collect::<Vec<_>>();
+    let edges = graph
+        .edges
+        .iter()
+        .map(|v| LayoutEdge {
+            from: v.from,
+            to: v.to,
+            constraint: v.attributes.get("constraint").map(|v| v.as_str()) != Some("false"),
+        })
+        .collect::<Vec<_>>();
+    let sizes = nodes
+        .iter()
+        .map(|v| (v.rx * 2.0, v.ry * 2.0))
+        .collect::<Vec<_>>();
+    let left_to_right = graph.attributes.get("rankdir").map(|v| v.as_str()) == Some("LR");
+    let layout = layout(&sizes, &edges, left_to_right);
+    write_svg(&graph, &nodes, &layout)
+}
+
+pub fn render_png(dot: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let svg = render_svg(std::str::from_utf8(dot).map_err(|e| anyhow!("非法的dot代码: {}", e))?)?;
+    svg_to_png(&svg, "")
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+pub type Attributes = HashMap<String, String>;
+
+#[derive(Debug, Clone, Default)]
+pub struct DotNode {
+    pub name: String,
+    pub attributes: Attributes,
+}
+
+#[derive(Debug, Clone)]
+pub struct DotEdge {
+    pub from: usize,
+    pub to: usize,
+    pub attributes: Attributes,
+}
+
+// dot语言的一个子集，足以表示dot_writer生成的内容
+#[derive(Debug, Clone, Default)]
+pub struct DotGraph {
+    pub directed: bool,
+    pub attributes: Attributes,
+    pub nodes: Vec<DotNode>,
+    pub edges: Vec<DotEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(text: &str) -> anyhow::Result<Vec<Token>> {
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut result = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' {
+            let mut buf = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(anyhow!("dot代码中的字符串未闭合")),
+                    Some('"') => break,
+                    Some('\\') => {
+                        i += 1;
+                        match chars.get(i) {
+                            Some('n') | Some('l') | Some('r') => buf.push('\n'),
+                            Some(v) => buf.push(*v),
+                            None => return Err(anyhow!("dot代码中的字符串未闭合")),
+                        }
+                    }
+                    Some(v) => buf.push(*v),
+                }
+                i += 1;
+            }
+            i += 1;
+            result.push(Token::Ident(buf));
+        } else if c == '-' && matches!(chars.get(i + 1), Some('>') | Some('-')) {
+            result.push(Token::Symbol(if chars[i + 1] == '>' { "->" } else { "--" }));
+            i += 2;
+        } else if let Some(sym) = ["{", "}", "[", "]", "=", ";", ","]
+            .iter()
+            .find(|v| v.starts_with(c))
+        {
+            result.push(Token::Symbol(*sym));
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' {
+            let start = i;
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+            {
+                i += 1;
+            }
+            result.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            return Err(anyhow!("dot代码中有无法识别的字符: {}", c));
+        }
+    }
+    Ok(result)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+    fn next(&mut self) -> anyhow::Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(anyhow!("dot代码意外结束"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+    fn is_symbol(&self, sym: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(v)) if *v == sym)
+    }
+    fn expect_symbol(&mut self, sym: &str) -> anyhow::Result<()> {
+        match self.next()? {
+            Token::Symbol(v) if v == sym => Ok(()),
+            other => Err(anyhow!("dot代码中应为 {}，实际为 {:?}", sym, other)),
+        }
+    }
+    fn ident(&mut self) -> anyhow::Result<String> {
+        match self.next()? {
+            Token::Ident(v) => Ok(v),
+            other => Err(anyhow!("dot代码中应为标识符，实际为 {:?}", other)),
+        }
+    }
+    // [a=b, c=d; e=f]，可以连续出现多组
+    fn attribute_list(&mut self, attributes: &mut Attributes) -> anyhow::Result<()> {
+        while self.is_symbol("[") {
+            self.pos += 1;
+            while !self.is_symbol("]") {
+                let key = self.ident()?;
+                self.expect_symbol("=")?;
+                let value = self.ident()?;
+                attributes.insert(key, value);
+                if self.is_symbol(",") || self.is_symbol(";") {
+                    self.pos += 1;
+                }
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_dot(text: &str) -> anyhow::Result<DotGraph> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+    let mut graph = DotGraph::default();
+    let mut header = parser.ident()?;
+    if header == "strict" {
+        header = parser.ident()?;
+    }
+    graph.directed = match header.as_str() {
+        "digraph" => true,
+        "graph" => false,
+        _ => return Err(anyhow!("无法识别的图类型: {}", header)),
+    };
+    if !parser.is_symbol("{") {
+        parser.ident()?;
+    }
+    parser.expect_symbol("{")?;
+    let mut node_defaults = Attributes::new();
+    let mut edge_defaults = Attributes::new();
+    let mut node_ids = HashMap::<String, usize>::new();
+    let mut node_id = |graph: &mut DotGraph, name: &str, defaults: &Attributes| -> usize {
+        *node_ids.entry(name.to_string()).or_insert_with(|| {
+            graph.nodes.push(DotNode {
+                name: name.to_string(),
+                attributes: defaults.clone(),
+            });
+            graph.nodes.len() - 1
+        })
+    };
+    while !parser.is_symbol("}") {
+        if parser.is_symbol(";") {
+            parser.pos += 1;
+            continue;
+        }
+        let first = parser.ident()?;
+        match first.as_str() {
+            "graph" => parser.attribute_list(&mut graph.attributes)?,
+            "node" => parser.attribute_list(&mut node_defaults)?,
+            "edge" => parser.attribute_list(&mut edge_defaults)?,
+            "subgraph" => return Err(anyhow!("内置布局不支持子图")),
+            _ if parser.is_symbol("=") => {
+                parser.pos += 1;
+                let value = parser.ident()?;
+                graph.attributes.insert(first, value);
+            }
+            _ => {
+                let mut chain = vec![node_id(&mut graph, &first, &node_defaults)];
+                while parser.is_symbol("->") || parser.is_symbol("--") {
+                    parser.pos += 1;
+                    let name = parser.ident()?;
+                    chain.push(node_id(&mut graph, &name, &node_defaults));
+                }
+                let mut attributes = Attributes::new();
+                parser.attribute_list(&mut attributes)?;
+                if chain.len() == 1 {
+                    graph.nodes[chain[0]].attributes.extend(attributes);
+                } else {
+                    for pair in chain.windows(2) {
+                        let mut edge_attributes = edge_defaults.clone();
+                        edge_attributes.extend(attributes.clone());
+                        graph.edges.push(DotEdge {
+                            from: pair[0],
+                            to: pair[1],
+                            attributes: edge_attributes,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(graph)
+}
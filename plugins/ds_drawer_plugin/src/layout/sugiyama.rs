@@ -0,0 +1,373 @@
+use std::collections::VecDeque;
+
+const NODE_SEP: f64 = 24.0;
+const RANK_SEP: f64 = 48.0;
+const MARGIN: f64 = 16.0;
+// 长边上的虚拟节点的宽度
+const DUMMY_WIDTH: f64 = 8.0;
+const ORDER_ITERATIONS: usize = 12;
+const POSITION_ITERATIONS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct LayoutEdge {
+    pub from: usize,
+    pub to: usize,
+    // 为false时不参与分层，对应dot的constraint=false
+    pub constraint: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct EdgeRoute {
+    // 从起点到终点依次经过的折点，不包括两端
+    pub bends: Vec<(f64, f64)>,
+    // 是否为跨越不同层的边，其余的边(同层、自环、不参与分层)需要另行绘制
+    pub layered: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layout {
+    // 各节点的中心坐标
+    pub positions: Vec<(f64, f64)>,
+    pub routes: Vec<EdgeRoute>,
+    pub width: f64,
+    pub height: f64,
+}
+
+// 深度优先搜索，将返祖边反向以消除环，返回每条边是否被反向
+fn find_reversed_edges(n: usize, edges: &[LayoutEdge]) -> Vec<bool> {
+    let mut out_edges = vec![vec![]; n];
+    for (id, edge) in edges.iter().enumerate() {
+        if edge.constraint && edge.from != edge.to {
+            out_edges[edge.from].push(id);
+        }
+    }
+    let mut reversed = vec![false; edges.len()];
+    // 0: 未访问, 1: 在栈中, 2: 已完成
+    let mut state = vec![0u8; n];
+    for root in 0..n {
+        if state[root] != 0 {
+            continue;
+        }
+        let mut stack = vec![(root, 0usize)];
+        state[root] = 1;
+        while let Some((vtx, index)) = stack.last_mut() {
+            let vtx = *vtx;
+            if let Some(edge) = out_edges[vtx].get(*index).copied() {
+                *index += 1;
+                let next = edges[edge].to;
+                match state[next] {
+                    0 => {
+                        state[next] = 1;
+                        stack.push((next, 0));
+                    }
+                    1 => reversed[edge] = true,
+                    _ => {}
+                }
+            } else {
+                state[vtx] = 2;
+                stack.pop();
+            }
+        }
+    }
+    reversed
+}
+
+// 最长路分层，并将没有入边的节点尽量下移到其后继的上一层
+fn assign_ranks(n: usize, oriented: &[(usize, usize)]) -> Vec<usize> {
+    let mut in_degree = vec![0; n];
+    let mut out_edges = vec![vec![]; n];
+    let mut in_edges = vec![vec![]; n];
+    for (a, b) in oriented.iter() {
+        in_degree[*b] += 1;
+        out_edges[*a].push(*b);
+        in_edges[*b].push(*a);
+    }
+    let mut queue = (0..n)
+        .filter(|v| in_degree[*v] == 0)
+        .collect::<VecDeque<_>>();
+    let mut order = vec![];
+    let mut rank = vec![0; n];
+    while let Some(vtx) = queue.pop_front() {
+        order.push(vtx);
+        for next in out_edges[vtx].iter() {
+            rank[*next] = rank[*next].max(rank[vtx] + 1);
+            in_degree[*next] -= 1;
+            if in_degree[*next] == 0 {
+                queue.push_back(*next);
+            }
+        }
+    }
+    for vtx in order.into_iter().rev() {
+        if in_edges[vtx].is_empty() && !out_edges[vtx].is_empty() {
+            rank[vtx] = out_edges[vtx].iter().map(|v| rank[*v]).min().unwrap() - 1;
+        }
+    }
+    rank
+}
+
+fn count_crossings(layers: &[Vec<usize>], position: &[usize], down: &[Vec<usize>]) -> usize {
+    let mut result = 0;
+    for layer in layers.iter() {
+        let segments = layer
+            .iter()
+            .flat_map(|u| down[*u].iter().map(move |v| (position[*u], position[*v])))
+            .collect::<Vec<_>>();
+        for i in 0..segments.len() {
+            for j in i + 1..segments.len() {
+                let (a, b) = (segments[i], segments[j]);
+                if (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1) {
+                    result += 1;
+                }
+            }
+        }
+    }
+    result
+}
+
+// 按重心排序一层中的节点，没有相邻节点的节点保持原位置
+fn sort_by_barycenter(layer: &mut Vec<usize>, position: &mut [usize], neighbors: &[Vec<usize>]) {
+    let mut keyed = layer
+        .iter()
+        .map(|v| {
+            let key = if neighbors[*v].is_empty() {
+                position[*v] as f64
+            } else {
+                neighbors[*v]
+                    .iter()
+                    .map(|u| position[*u] as f64)
+                    .sum::<f64>()
+                    / neighbors[*v].len() as f64
+            };
+            (key, *v)
+        })
+        .collect::<Vec<_>>();
+    keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    *layer = keyed.into_iter().map(|v| v.1).collect();
+    for (index, vtx) in layer.iter().enumerate() {
+        position[*vtx] = index;
+    }
+}
+
+/*
+在不重叠且保持顺序的前提下，使一层中的节点尽量靠近期望的横坐标
+分别从左向右、从右向左放置后取平均，再从左向右消除重叠
+*/
+fn place_layer(layer: &[usize], desired: &[f64], widths: &[f64], x: &mut [f64]) {
+    if layer.is_empty() {
+        return;
+    }
+    let gap = |a: usize, b: usize| (widths[a] + widths[b]) / 2.0 + NODE_SEP;
+    let k = layer.len();
+    let mut left = vec![0.0; k];
+    let mut right = vec![0.0; k];
+    for i in 0..k {
+        left[i] = if i == 0 {
+            desired[0]
+        } else {
+            desired[i].max(left[i - 1] + gap(layer[i - 1], layer[i]))
+        };
+    }
+    for i in (0..k).rev() {
+        right[i] = if i == k - 1 {
+            desired[k - 1]
+        } else {
+            desired[i].min(right[i + 1] - gap(layer[i], layer[i + 1]))
+        };
+    }
+    for i in 0..k {
+        let mut value = (left[i] + right[i]) / 2.0;
+        if i > 0 {
+            value = value.max(x[layer[i - 1]] + gap(layer[i - 1], layer[i]));
+        }
+        x[layer[i]] = value;
+    }
+}
+
+/*
+分层(Sugiyama)布局:
+消除环 -> 最长路分层 -> 为长边插入虚拟节点 -> 按重心减少交叉 -> 确定坐标
+sizes为各节点的宽和高，left_to_right为true时层从左向右排列
+*/
+pub fn layout(sizes: &[(f64, f64)], edges: &[LayoutEdge], left_to_right: bool) -> Layout {
+    let n = sizes.len();
+    let (mut widths, mut heights): (Vec<f64>, Vec<f64>) = sizes
+        .iter()
+        .map(|(w, h)| if left_to_right { (*h, *w) } else { (*w, *h) })
+        .unzip();
+    let reversed = find_reversed_edges(n, edges);
+    let oriented = edges
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.constraint && e.from != e.to)
+        .map(|(id, e)| {
+            if reversed[id] {
+                (e.to, e.from)
+            } else {
+                (e.from, e.to)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut rank = assign_ranks(n, &oriented);
+    // 虚拟图，编号n之后的节点为虚拟节点
+    let mut down = vec![vec![]; n];
+    let mut up = vec![vec![]; n];
+    let mut chains = vec![None; edges.len()];
+    for (id, edge) in edges.iter().enumerate() {
+        let (a, b) = if reversed[id] {
+            (edge.to, edge.from)
+        } else {
+            (edge.from, edge.to)
+        };
+        if !edge.constraint || a == b || rank[b] <= rank[a] {
+            continue;
+        }
+        let mut chain = vec![a];
+        for r in rank[a] + 1..rank[b] {
+            let dummy = widths.len();
+            widths.push(DUMMY_WIDTH);
+            heights.push(0.0);
+            rank.push(r);
+            down.push(vec![]);
+            up.push(vec![]);
+            chain.push(dummy);
+        }
+        chain.push(b);
+        for pair in chain.windows(2) {
+            down[pair[0]].push(pair[1]);
+            up[pair[1]].push(pair[0]);
+        }
+        chains[id] = Some(chain);
+    }
+    let total = widths.len();
+    let layer_count = rank.iter().max().map(|v| v + 1).unwrap_or(0);
+    let mut layers = vec![vec![]; layer_count];
+    for vtx in 0..total {
+        layers[rank[vtx]].push(vtx);
+    }
+    let mut position = vec![0; total];
+    for layer in layers.iter() {
+        for (index, vtx) in layer.iter().enumerate() {
+            position[*vtx] = index;
+        }
+    }
+    let mut best = (count_crossings(&layers, &position, &down), layers.clone());
+    for iteration in 0..ORDER_ITERATIONS {
+        if best.0 == 0 {
+            break;
+        }
+        if iteration % 2 == 0 {
+            for r in 1..layer_count {
+                sort_by_barycenter(&mut layers[r], &mut position, &up);
+            }
+        } else {
+            for r in (0..layer_count.saturating_sub(1)).rev() {
+                sort_by_barycenter(&mut layers[r], &mut position, &down);
+            }
+        }
+        let crossings = count_crossings(&layers, &position, &down);
+        if crossings < best.0 {
+            best = (crossings, layers.clone());
+        }
+    }
+    let layers = best.1;
+
+    let mut y = vec![0.0; total];
+    let mut top = MARGIN;
+    for layer in layers.iter() {
+        let layer_height = layer.iter().map(|v| heights[*v]).fold(0.0, f64::max);
+        for vtx in layer.iter() {
+            y[*vtx] = top + layer_height / 2.0;
+        }
+        top += layer_height + RANK_SEP;
+    }
+    let mut x = vec![0.0; total];
+    for layer in layers.iter() {
+        let mut cursor = 0.0;
+        for vtx in layer.iter() {
+            x[*vtx] = cursor + widths[*vtx] / 2.0;
+            cursor += widths[*vtx] + NODE_SEP;
+        }
+        for vtx in layer.iter() {
+            x[*vtx] -= cursor / 2.0;
+        }
+    }
+    for iteration in 0..POSITION_ITERATIONS {
+        let (order, neighbors): (Vec<usize>, &Vec<Vec<usize>>) = if iteration % 2 == 0 {
+            ((1..layer_count).collect(), &up)
+        } else {
+            ((0..layer_count.saturating_sub(1)).rev().collect(), &down)
+        };
+        for r in order.into_iter() {
+            let desired = layers[r]
+                .iter()
+                .map(|v| {
+                    if neighbors[*v].is_empty() {
+                        x[*v]
+                    } else {
+                        neighbors[*v].iter().map(|u| x[*u]).sum::<f64>()
+                            / neighbors[*v].len() as f64
+                    }
+                })
+                .collect::<Vec<_>>();
+            place_layer(&layers[r], &desired, &widths, &mut x);
+        }
+    }
+    let min_x = (0..total)
+        .map(|v| x[v] - widths[v] / 2.0)
+        .fold(f64::INFINITY, f64::min);
+    let shift = if min_x.is_finite() {
+        MARGIN - min_x
+    } else {
+        0.0
+    };
+    for value in x.iter_mut() {
+        *value += shift;
+    }
+    let width = (0..total)
+        .map(|v| x[v] + widths[v] / 2.0)
+        .fold(0.0, f64::max)
+        + MARGIN;
+    let height = (top - RANK_SEP + MARGIN).max(MARGIN * 2.0);
+
+    let point = |v: usize| {
+        if left_to_right {
+            (y[v], x[v])
+        } else {
+            (x[v], y[v])
+        }
+    };
+    let routes = chains
+        .into_iter()
+        .enumerate()
+        .map(|(id, chain)| match chain {
+            Some(chain) => {
+                let mut bends = chain[1..chain.len() - 1]
+                    .iter()
+                    .map(|v| point(*v))
+                    .collect::<Vec<_>>();
+                if reversed[id] {
+                    bends.reverse();
+                }
+                EdgeRoute {
+                    bends,
+                    layered: true,
+                }
+            }
+            None => EdgeRoute {
+                bends: vec![],
+                layered: false,
+            },
+        })
+        .collect();
+    let (width, height) = if left_to_right {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    Layout {
+        positions: (0..n).map(point).collect(),
+        routes,
+        width,
+        height,
+    }
+}
@@ -11,14 +11,35 @@ use countdown_bot3::{
     export_static_plugin,
 };
 use serde::{Deserialize, Serialize};
+pub mod graph;
+mod graph_impl;
 mod r#impl;
+pub mod kmp;
+pub mod layout;
+pub mod pam;
 pub mod sam;
+pub mod suffix_tree;
+pub mod trie;
 static PLUGIN_NAME: &str = "ds_drawer_plugin";
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutEngine {
+    // 优先使用Graphviz，找不到dot时使用内置布局
+    Auto,
+    Graphviz,
+    // 内置的分层布局，不依赖外部程序
+    Builtin,
+}
 #[derive(Deserialize, Serialize)]
 pub struct DSDrawerConfig {
     pub max_string_length: u32,
     pub dot_executable: String,
     pub dot_timeout: i32,
+    pub layout_engine: LayoutEngine,
+    pub max_graph_vertices: u32,
+    pub max_graph_edges: u32,
+    pub max_query_length: u32,
+    pub max_query_strings: u32,
 }
 impl Default for DSDrawerConfig {
     fn default() -> Self {
@@ -26,6 +47,11 @@ impl Default for DSDrawerConfig {
             max_string_length: 20,
             dot_executable: String::from("dot"),
             dot_timeout: 30,
+            layout_engine: LayoutEngine::Auto,
+            max_graph_vertices: 100,
+            max_graph_edges: 300,
+            max_query_length: 2000,
+            max_query_strings: 10,
         }
     }
 }
@@ -61,6 +87,32 @@ impl BotPlugin for DSDrawerPlugin {
                 .guild(true)
                 .description("绘制后缀自动机 | sam <字符串(使用|分割不同的字符串)>"),
         )?;
+        for (name, description) in [
+            ("suffixtree", "绘制后缀树 | suffixtree <字符串>"),
+            (
+                "acam",
+                "绘制AC自动机(红色虚线为失配指针) | acam <模式串(使用|分割不同的模式串)>",
+            ),
+            ("pam", "绘制回文自动机 | pam <字符串>"),
+            ("kmp", "绘制KMP失配指针 | kmp <字符串>"),
+            ("trie", "绘制字典树 | trie <字符串(使用|分割不同的字符串)>"),
+            (
+                "samq",
+                "后缀自动机查询 | samq distinct <字符串> | samq lcs <字符串1|字符串2...> | samq count <模式串> <字符串> | samq kth <k> <字符串>",
+            ),
+            (
+                "graph",
+                "绘制图，可标出最短路或最小生成树 | graph [-d] [-w] [-r 根] [--path S T] [--mst] <n m u1 v1 [w1] ...> (使用 graph -h 查看帮助)",
+            ),
+        ] {
+            bot.register_command(
+                Command::new(name)
+                    .group(true)
+                    .private(true)
+                    .guild(true)
+                    .description(description),
+            )?;
+        }
         Ok(())
     }
     fn on_before_start(
@@ -74,21 +126,29 @@ impl BotPlugin for DSDrawerPlugin {
     fn get_meta(&self) -> PluginMeta {
         PluginMeta {
             author: String::from("officeyutong"),
-            description: String::from("SAM绘制器"),
+            description: String::from("数据结构与图绘制器"),
             version: env!("CARGO_PKG_VERSION").to_string(),
         }
     }
     async fn on_command(
         &mut self,
-        _command: String,
+        command: String,
         args: Vec<String>,
         sender: &SenderType,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        if command == "graph" {
+            self.draw_graph(args, sender).await?;
+            return Ok(());
+        }
+        if command == "samq" {
+            self.sam_query(args, sender).await?;
+            return Ok(());
+        }
         if args.is_empty() {
             return Err(anyhow!("请输入字符串!").into());
         }
         let s = args.join(" ");
-        self.generate_sam(&s, sender).await?;
+        self.generate_structure(&command, &s, sender).await?;
         Ok(())
     }
 }
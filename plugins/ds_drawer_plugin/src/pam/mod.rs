@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use dot_writer::{Attributes, Color, DotWriter};
+
+#[derive(Debug, Default, Clone)]
+pub struct PAMNode {
+    pub len: i32,
+    pub fail: usize,
+    pub chds: BTreeMap<char, usize>,
+    // 此回文串作为最长回文后缀出现的次数，collect后为总出现次数
+    pub count: i32,
+    pub text: String,
+}
+
+// 回文自动机，0号节点为奇根(长度-1)，1号节点为偶根(长度0)
+pub struct PAM {
+    pub nodes: Vec<PAMNode>,
+    chars: Vec<char>,
+    last: usize,
+}
+
+impl Default for PAM {
+    fn default() -> Self {
+        Self {
+            nodes: vec![
+                PAMNode {
+                    len: -1,
+                    ..Default::default()
+                },
+                PAMNode::default(),
+            ],
+            chars: vec![],
+            last: 1,
+        }
+    }
+}
+
+impl PAM {
+    pub fn build(text: &str) -> PAM {
+        let mut pam = PAM::default();
+        for chr in text.chars() {
+            pam.append(chr);
+        }
+        pam.collect();
+        pam
+    }
+    // 沿失配指针找到可以在两侧扩展chr的最长回文后缀
+    fn get_fail(&self, mut vtx: usize) -> usize {
+        let pos = self.chars.len() as i32 - 1;
+        loop {
+            let mirror = pos - 1 - self.nodes[vtx].len;
+            if mirror >= 0 && self.chars[mirror as usize] == self.chars[pos as usize] {
+                return vtx;
+            }
+            vtx = self.nodes[vtx].fail;
+        }
+    }
+    pub fn append(&mut self, chr: char) {
+        self.chars.push(chr);
+        let curr = self.get_fail(self.last);
+        if !self.nodes[curr].chds.contains_key(&chr) {
+            let len = self.nodes[curr].len + 2;
+            let fail = if len == 1 {
+                1
+            } else {
+                self.nodes[self.get_fail(self.nodes[curr].fail)].chds[&chr]
+            };
+            let end = self.chars.len();
+            self.nodes.push(PAMNode {
+                len,
+                fail,
+                chds: BTreeMap::new(),
+                count: 0,
+                text: self.chars[end - len as usize..end].iter().collect(),
+            });
+            let id = self.nodes.len() - 1;
+            self.nodes[curr].chds.insert(chr, id);
+        }
+        self.last = self.nodes[curr].chds[&chr];
+        self.nodes[self.last].count += 1;
+    }
+    // 将出现次数累加到失配指针指向的节点，节点编号越大长度不会更小
+    fn collect(&mut self) {
+        for id in (2..self.nodes.len()).rev() {
+            let fail = self.nodes[id].fail;
+            self.nodes[fail].count += self.nodes[id].count;
+        }
+    }
+    pub fn generate_graph(&self) -> Vec<u8> {
+        let mut out_buf = Vec::<u8>::new();
+        {
+            let mut writer = DotWriter::from(&mut out_buf);
+            let mut digraph = writer.digraph();
+            for (id, node) in self.nodes.iter().enumerate() {
+                {
+                    let label = if id < 2 {
+                        format!("{}\nlen={}", id, node.len)
+                    } else {
+                        format!(
+                            "{}\n{}\nlen={}\ncnt={}",
+                            id, node.text, node.len, node.count
+                        )
+                    };
+                    digraph.node_named(id.to_string()).set_label(&label);
+                }
+                for (chr, chd) in node.chds.iter() {
+                    digraph
+                        .edge(id.to_string(), chd.to_string())
+                        .attributes()
+                        .set_label(&String::from(*chr));
+                }
+                if id >= 1 {
+                    digraph
+                        .edge(id.to_string(), node.fail.to_string())
+                        .attributes()
+                        .set_color(Color::Red)
+                        .set("style", "dashed", false);
+                }
+            }
+        }
+        return out_buf;
+    }
+}
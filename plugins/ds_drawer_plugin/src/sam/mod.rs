@@ -2,6 +2,8 @@ use std::{collections::HashMap, fmt::Display};
 
 use dot_writer::Attributes;
 
+mod query;
+
 pub struct SAMPool {
     pub nodes: Vec<Box<SAMNode>>,
     pub root: *mut SAMNode,
@@ -93,17 +95,6 @@ impl Default for SAMPool {
     }
 }
 impl SAMPool {
-    pub fn dfs(&self, vtx: *mut SAMNode) {
-        unsafe {
-            for chd in (*vtx).chd_on_tree.iter() {
-                self.dfs(*chd);
-                for (k, v) in (*(*chd)).right_size.iter() {
-                    let raw_val = (*vtx).right_size.get(k).map(|v| *v).unwrap_or(0);
-                    (*vtx).right_size.insert(*k, raw_val + *v);
-                }
-            }
-        }
-    }
     pub fn collect(&mut self) {
         for node in self.nodes.iter() {
             if !node.link.is_null() {
@@ -119,7 +110,24 @@ impl SAMPool {
                 }
             }
         }
-        self.dfs(self.root);
+        // link指向的节点max_len更小，按max_len从大到小累加即可，避免在长串上递归
+        let mut order = self
+            .nodes
+            .iter()
+            .map(|v| v.self_ptr)
+            .collect::<Vec<NodePtr>>();
+        order.sort_by_key(|v| unsafe { std::cmp::Reverse((**v).max_len) });
+        for vtx in order.into_iter() {
+            unsafe {
+                let link = (*vtx).link;
+                if link.is_null() {
+                    continue;
+                }
+                for (k, v) in (*vtx).right_size.iter() {
+                    *(*link).right_size.entry(*k).or_insert(0) += *v;
+                }
+            }
+        }
     }
     pub fn join_string(&mut self, text: &str, str_id: i32) {
         self.last = self.root;
@@ -0,0 +1,114 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{NodePtr, SAMPool};
+
+impl SAMPool {
+    // 从根可达的节点，按max_len从小到大排列
+    fn reachable_nodes(&self) -> Vec<NodePtr> {
+        let mut visited = HashMap::<i32, NodePtr>::new();
+        let mut queue = VecDeque::from([self.root]);
+        unsafe {
+            visited.insert((*self.root).vtx_id, self.root);
+            while let Some(vtx) = queue.pop_front() {
+                for chd in (*vtx).chds.values() {
+                    if !visited.contains_key(&(**chd).vtx_id) {
+                        visited.insert((**chd).vtx_id, *chd);
+                        queue.push_back(*chd);
+                    }
+                }
+            }
+        }
+        let mut result = visited.into_values().collect::<Vec<NodePtr>>();
+        result.sort_by_key(|v| unsafe { ((**v).max_len, (**v).vtx_id) });
+        result
+    }
+    // 从每个节点出发的非空路径数，根节点的值即为本质不同的子串数
+    fn path_counts(&self) -> HashMap<i32, u64> {
+        let mut result = HashMap::new();
+        for vtx in self.reachable_nodes().into_iter().rev() {
+            unsafe {
+                let count = (*vtx).chds.values().fold(0u64, |s, v| {
+                    s.saturating_add(1 + result.get(&(**v).vtx_id).copied().unwrap_or(0))
+                });
+                result.insert((*vtx).vtx_id, count);
+            }
+        }
+        result
+    }
+    pub fn count_distinct_substrings(&self) -> u64 {
+        unsafe { self.path_counts()[&(*self.root).vtx_id] }
+    }
+    /*
+    所有字符串(编号为0到string_count-1)的最长公共子串，不存在时返回空串
+    需要先调用collect计算right_size
+    */
+    pub fn longest_common_substring(&self, string_count: i32) -> String {
+        let nodes = self.reachable_nodes();
+        // 每个节点对应的最长串由哪个节点经过哪个字符转移而来
+        let mut longest_from = HashMap::<i32, (NodePtr, char)>::new();
+        let mut best: Option<NodePtr> = None;
+        unsafe {
+            for vtx in nodes.iter() {
+                for (chr, chd) in (**vtx).chds.iter() {
+                    if (**chd).max_len == (**vtx).max_len + 1 {
+                        longest_from.entry((**chd).vtx_id).or_insert((*vtx, *chr));
+                    }
+                }
+                let common = (0..string_count)
+                    .all(|id| (**vtx).right_size.get(&id).copied().unwrap_or(0) > 0);
+                if common && best.map(|v| (*v).max_len < (**vtx).max_len).unwrap_or(true) {
+                    best = Some(*vtx);
+                }
+            }
+            let mut chars = vec![];
+            let mut curr = best.unwrap_or(self.root);
+            while let Some((prev, chr)) = longest_from.get(&(*curr).vtx_id) {
+                chars.push(*chr);
+                curr = *prev;
+            }
+            chars.into_iter().rev().collect()
+        }
+    }
+    /*
+    pattern在各字符串中的出现次数，字符串ID -> 次数
+    需要先调用collect计算right_size
+    */
+    pub fn count_occurrences(&self, pattern: &str) -> HashMap<i32, i32> {
+        let mut curr = self.root;
+        unsafe {
+            for chr in pattern.chars() {
+                match (*curr).chds.get(&chr) {
+                    Some(v) => curr = *v,
+                    None => return HashMap::new(),
+                }
+            }
+            (*curr).right_size.clone()
+        }
+    }
+    // 字典序第k小的子串(相同的子串只计一次，k从1开始)
+    pub fn kth_substring(&self, mut k: u64) -> Option<String> {
+        let counts = self.path_counts();
+        let mut curr = self.root;
+        let mut result = String::new();
+        unsafe {
+            if k == 0 || k > counts[&(*curr).vtx_id] {
+                return None;
+            }
+            while k > 0 {
+                let mut chds = (*curr).chds.iter().collect::<Vec<_>>();
+                chds.sort_by_key(|v| *v.0);
+                for (chr, chd) in chds.into_iter() {
+                    let size = 1 + counts[&(**chd).vtx_id];
+                    if k <= size {
+                        result.push(*chr);
+                        k -= 1;
+                        curr = *chd;
+                        break;
+                    }
+                    k -= size;
+                }
+            }
+        }
+        Some(result)
+    }
+}
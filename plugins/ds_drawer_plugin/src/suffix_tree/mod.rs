@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use dot_writer::{Attributes, DotWriter};
+
+// 后缀树的结束符
+pub const TERMINATOR: char = '$';
+
+#[derive(Debug, Default, Clone)]
+pub struct SuffixTreeNode {
+    pub chds: BTreeMap<char, usize>,
+    // 从父节点到此节点的边上的字符串
+    pub label: String,
+    // 叶子对应的后缀的起始位置，从1开始
+    pub suffix: Option<usize>,
+}
+
+pub struct SuffixTree {
+    pub nodes: Vec<SuffixTreeNode>,
+}
+
+impl SuffixTree {
+    /*
+    朴素构建：先将所有后缀插入字典树，再压缩只有一个子节点的链
+    复杂度为O(n^2)，仅适用于较短的字符串
+    */
+    pub fn build(text: &str) -> SuffixTree {
+        let chars = text
+            .chars()
+            .chain(std::iter::once(TERMINATOR))
+            .collect::<Vec<char>>();
+        let mut trie = vec![SuffixTreeNode::default()];
+        for start in 0..chars.len() {
+            let mut curr = 0;
+            for chr in chars[start..].iter() {
+                curr = match trie[curr].chds.get(chr) {
+                    Some(v) => *v,
+                    None => {
+                        trie.push(SuffixTreeNode {
+                            label: chr.to_string(),
+                            ..Default::default()
+                        });
+                        let id = trie.len() - 1;
+                        trie[curr].chds.insert(*chr, id);
+                        id
+                    }
+                };
+            }
+            trie[curr].suffix = Some(start + 1);
+        }
+        let mut result = SuffixTree {
+            nodes: vec![SuffixTreeNode::default()],
+        };
+        result.compress(&trie, 0, 0);
+        result
+    }
+    fn compress(&mut self, trie: &[SuffixTreeNode], trie_vtx: usize, vtx: usize) {
+        for (chr, chd) in trie[trie_vtx].chds.iter() {
+            let mut label = trie[*chd].label.clone();
+            let mut curr = *chd;
+            while trie[curr].chds.len() == 1 && trie[curr].suffix.is_none() {
+                curr = *trie[curr].chds.values().next().unwrap();
+                label.push_str(&trie[curr].label);
+            }
+            self.nodes.push(SuffixTreeNode {
+                chds: BTreeMap::new(),
+                label,
+                suffix: trie[curr].suffix,
+            });
+            let id = self.nodes.len() - 1;
+            self.nodes[vtx].chds.insert(*chr, id);
+            self.compress(trie, curr, id);
+        }
+    }
+    pub fn generate_graph(&self) -> Vec<u8> {
+        let mut out_buf = Vec::<u8>::new();
+        {
+            let mut writer = DotWriter::from(&mut out_buf);
+            let mut digraph = writer.digraph();
+            for (id, node) in self.nodes.iter().enumerate() {
+                {
+                    let mut curr_node = digraph.node_named(id.to_string());
+                    match node.suffix {
+                        Some(v) => {
+                            curr_node.set_label(&v.to_string());
+                            curr_node.set("shape", "box", false);
+                        }
+                        None => {
+                            curr_node.set_label("");
+                            curr_node.set("shape", "circle", false);
+                        }
+                    };
+                }
+                for chd in node.chds.values() {
+                    digraph
+                        .edge(id.to_string(), chd.to_string())
+                        .attributes()
+                        .set_label(&self.nodes[*chd].label);
+                }
+            }
+        }
+        return out_buf;
+    }
+}
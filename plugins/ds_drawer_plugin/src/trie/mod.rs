@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use dot_writer::{Attributes, Color, DotWriter};
+
+#[derive(Debug, Default, Clone)]
+pub struct TrieNode {
+    pub chds: BTreeMap<char, usize>,
+    // 失配指针，仅在build_fail后有效
+    pub fail: usize,
+    // 在此节点结束的模式串编号
+    pub ends: Vec<usize>,
+}
+
+// 字典树，计算失配指针后即为AC自动机，0号节点为根
+pub struct Trie {
+    pub nodes: Vec<TrieNode>,
+}
+
+impl Default for Trie {
+    fn default() -> Self {
+        Self {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+}
+
+impl Trie {
+    pub fn insert(&mut self, text: &str, str_id: usize) {
+        let mut curr = 0;
+        for chr in text.chars() {
+            curr = match self.nodes[curr].chds.get(&chr) {
+                Some(v) => *v,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let id = self.nodes.len() - 1;
+                    self.nodes[curr].chds.insert(chr, id);
+                    id
+                }
+            };
+        }
+        self.nodes[curr].ends.push(str_id);
+    }
+    // 按BFS序计算失配指针
+    pub fn build_fail(&mut self) {
+        let mut queue = VecDeque::new();
+        for v in self.nodes[0].chds.values() {
+            queue.push_back(*v);
+        }
+        while let Some(vtx) = queue.pop_front() {
+            let chds = self.nodes[vtx].chds.clone();
+            for (chr, chd) in chds.into_iter() {
+                let mut curr = self.nodes[vtx].fail;
+                self.nodes[chd].fail = loop {
+                    if let Some(v) = self.nodes[curr].chds.get(&chr) {
+                        break *v;
+                    }
+                    if curr == 0 {
+                        break 0;
+                    }
+                    curr = self.nodes[curr].fail;
+                };
+                queue.push_back(chd);
+            }
+        }
+    }
+    /*
+    show_fail为true时以红色虚线绘制失配指针
+    指向根的失配指针不绘制，以免图像过于杂乱
+    */
+    pub fn generate_graph(&self, show_fail: bool) -> Vec<u8> {
+        let mut out_buf = Vec::<u8>::new();
+        {
+            let mut writer = DotWriter::from(&mut out_buf);
+            let mut digraph = writer.digraph();
+            for (id, node) in self.nodes.iter().enumerate() {
+                {
+                    let mut label = id.to_string();
+                    if !node.ends.is_empty() {
+                        label.push_str(&format!(
+                            "\n#{}",
+                            node.ends
+                                .iter()
+                                .map(|v| v.to_string())
+                                .collect::<Vec<String>>()
+                                .join(",#")
+                        ));
+                    }
+                    let mut curr_node = digraph.node_named(id.to_string());
+                    curr_node.set_label(&label);
+                    if !node.ends.is_empty() {
+                        curr_node.set("shape", "doublecircle", false);
+                    }
+                }
+                for (chr, chd) in node.chds.iter() {
+                    digraph
+                        .edge(id.to_string(), chd.to_string())
+                        .attributes()
+                        .set_label(&String::from(*chr));
+                }
+                if show_fail && node.fail != 0 {
+                    digraph
+                        .edge(id.to_string(), node.fail.to_string())
+                        .attributes()
+                        .set_color(Color::Red)
+                        .set("style", "dashed", false);
+                }
+            }
+        }
+        return out_buf;
+    }
+}
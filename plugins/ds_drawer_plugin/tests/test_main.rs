@@ -12,3 +12,101 @@ fn test1() {
     //     println!("{}", b);
     // }
 }
+
+#[test]
+fn kmp_fail() {
+    use ds_drawer_plugin::kmp::KMP;
+    assert_eq!(KMP::build("abababca").fail, vec![0, 0, 0, 1, 2, 3, 4, 0, 1]);
+}
+
+#[test]
+fn ac_automaton_fail() {
+    use ds_drawer_plugin::trie::Trie;
+    let mut trie = Trie::default();
+    for (id, s) in ["he", "she", "his", "hers"].iter().enumerate() {
+        trie.insert(s, id + 1);
+    }
+    trie.build_fail();
+    // "sh" 的失配指针指向 "h"，"she" 指向 "he"
+    let walk = |s: &str| s.chars().fold(0, |v, c| trie.nodes[v].chds[&c]);
+    assert_eq!(trie.nodes[walk("sh")].fail, walk("h"));
+    assert_eq!(trie.nodes[walk("she")].fail, walk("he"));
+    assert_eq!(trie.nodes[walk("hers")].fail, walk("s"));
+}
+
+#[test]
+fn pam_and_suffix_tree() {
+    use ds_drawer_plugin::{pam::PAM, suffix_tree::SuffixTree};
+    // 两个根以及 a, b, aba, bab, ababa
+    let pam = PAM::build("ababa");
+    assert_eq!(pam.nodes.len(), 7);
+    assert_eq!(pam.nodes.iter().find(|v| v.text == "aba").unwrap().count, 2);
+    let tree = SuffixTree::build("banana");
+    let leaves = tree.nodes.iter().filter(|v| v.suffix.is_some()).count();
+    assert_eq!(leaves, 7);
+}
+
+#[test]
+fn graph_algorithms() {
+    use ds_drawer_plugin::graph::Graph;
+    let graph = Graph::parse("4 5\n1 2 1\n2 3 2\n1 3 5\n3 4 1\n1 4 9", false, None, false).unwrap();
+    assert!(graph.one_based);
+    let (length, path) = graph.shortest_path(0, 3).unwrap().unwrap();
+    assert_eq!(length, 4);
+    assert_eq!(path, vec![0, 1, 3]);
+    let (total, _, connected) = graph.minimum_spanning_tree().unwrap();
+    assert_eq!((total, connected), (4, true));
+    // 以树的形式输入时可以省略边数
+    let tree = Graph::parse("3 0 1 0 2", false, None, true).unwrap();
+    assert_eq!(tree.edges.len(), 2);
+    assert!(tree.tree_parents(0).is_ok());
+    assert!(Graph::parse("3 1 1 4", false, None, false).is_err());
+    // 边数过大时不应溢出
+    assert!(Graph::parse("3 18446744073709551615 1 2", false, None, false).is_err());
+    assert!(Graph::parse("18446744073709551615 1 2", false, None, true).is_err());
+}
+
+#[test]
+fn builtin_layout() {
+    use ds_drawer_plugin::{
+        kmp::KMP,
+        layout::{parser::parse_dot, render_svg},
+    };
+    let dot = String::from_utf8(KMP::build("abab").generate_graph()).unwrap();
+    let graph = parse_dot(&dot).unwrap();
+    assert_eq!(graph.nodes.len(), 5);
+    assert_eq!(
+        graph.attributes.get("rankdir").map(|v| v.as_str()),
+        Some("LR")
+    );
+    assert!(render_svg(&dot).unwrap().starts_with("<svg"));
+    // 有环的图也能完成布局
+    let svg = render_svg("digraph { a -> b; b -> c [label=\"x\"]; c -> a; a -> a }").unwrap();
+    assert!(svg.contains(">x</text>"));
+}
+
+#[test]
+fn sam_queries() {
+    use ds_drawer_plugin::sam::SAMPool;
+    let mut pool = SAMPool::default();
+    pool.join_string("abab", 0);
+    pool.collect();
+    // a b ab ba aba bab abab
+    assert_eq!(pool.count_distinct_substrings(), 7);
+    assert_eq!(pool.kth_substring(1).unwrap(), "a");
+    assert_eq!(pool.kth_substring(4).unwrap(), "abab");
+    assert_eq!(pool.kth_substring(8), None);
+    assert_eq!(pool.count_occurrences("ab")[&0], 2);
+    let mut pool = SAMPool::default();
+    for (id, s) in ["xabcy", "zabcw", "abd"].iter().enumerate() {
+        pool.join_string(s, id as i32);
+    }
+    pool.collect();
+    assert_eq!(pool.longest_common_substring(3), "ab");
+    assert_eq!(pool.longest_common_substring(2), "abc");
+    // 长串上collect不应递归过深
+    let mut pool = SAMPool::default();
+    pool.join_string(&"a".repeat(100000), 0);
+    pool.collect();
+    assert_eq!(pool.count_occurrences("a")[&0], 100000);
+}
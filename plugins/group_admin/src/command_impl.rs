@@ -0,0 +1,295 @@
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::ResultType, command::SenderType, event::message::GroupMessageEvent,
+};
+
+use crate::{
+    duration::{format_duration, parse_duration},
+    permission_impl::get_reply_id,
+    GroupAdminPlugin, PendingAction,
+};
+
+impl GroupAdminPlugin {
+    async fn send(&self, evt: &GroupMessageEvent, text: &str) -> ResultType<()> {
+        self.client
+            .as_ref()
+            .unwrap()
+            .quick_send_by_sender(&SenderType::Group(evt.clone()), text)
+            .await?;
+        Ok(())
+    }
+    // 需要确认时保存操作并提示，否则直接执行
+    async fn confirm_or_execute(
+        &mut self,
+        evt: &GroupMessageEvent,
+        action: PendingAction,
+        description: &str,
+    ) -> ResultType<()> {
+        let config = self.config.as_ref().unwrap();
+        if !config.require_confirm {
+            return self.execute_action(evt, action).await;
+        }
+        let timeout = config.confirm_timeout;
+        self.pending.insert(
+            (evt.group_id, evt.user_id),
+            (action, chrono::Local::now().timestamp() + timeout),
+        );
+        self.send(
+            evt,
+            &format!(
+                "即将{}，请在 {} 秒内使用 confirm 指令确认，或使用 cancel 指令取消",
+                description, timeout
+            ),
+        )
+        .await
+    }
+    async fn execute_action(
+        &self,
+        evt: &GroupMessageEvent,
+        action: PendingAction,
+    ) -> ResultType<()> {
+        let client = self.client.as_ref().unwrap();
+        match action {
+            PendingAction::Kick {
+                user_id,
+                reject_add,
+            } => {
+                client
+                    .set_group_kick(evt.group_id as u64, user_id as u64, reject_add)
+                    .await?;
+                self.send(evt, &format!("已将 {} 移出本群", user_id))
+                    .await?;
+            }
+            PendingAction::WholeBan { enable } => {
+                client
+                    .set_group_whole_ban(evt.group_id as u64, enable)
+                    .await?;
+                self.send(
+                    evt,
+                    if enable {
+                        "已开启全员禁言"
+                    } else {
+                        "已关闭全员禁言"
+                    },
+                )
+                .await?;
+            }
+        };
+        Ok(())
+    }
+    pub async fn command_kick(
+        &mut self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<()> {
+        self.require_admin(evt).await?;
+        let (target, rest) = self.require_target(evt, args).await?;
+        self.check_target(evt, target).await?;
+        let reject_add = rest.iter().any(|s| s == "--block");
+        self.confirm_or_execute(
+            evt,
+            PendingAction::Kick {
+                user_id: target,
+                reject_add,
+            },
+            &format!(
+                "将 {} 移出本群{}",
+                target,
+                if reject_add {
+                    "并拒绝其再次加群"
+                } else {
+                    ""
+                }
+            ),
+        )
+        .await
+    }
+    pub async fn command_ban(
+        &mut self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<()> {
+        self.require_admin(evt).await?;
+        let (target, rest) = self.require_target(evt, args).await?;
+        let duration_str = rest
+            .first()
+            .ok_or(anyhow!("请输入禁言时长，如 30m, 1h30m, 2d, 1天"))?;
+        let duration =
+            parse_duration(duration_str).ok_or(anyhow!("非法的禁言时长: {}", duration_str))?;
+        if duration == 0 {
+            return Err(anyhow!("禁言时长必须大于0，解除禁言请使用 unban 指令").into());
+        }
+        let max_duration = self.config.as_ref().unwrap().max_ban_duration;
+        if duration > max_duration {
+            return Err(anyhow!("禁言时长不能超过 {}", format_duration(max_duration)).into());
+        }
+        self.check_target(evt, target).await?;
+        self.client
+            .as_ref()
+            .unwrap()
+            .set_group_ban(evt.group_id as u64, target as u64, duration)
+            .await?;
+        self.send(
+            evt,
+            &format!("已将 {} 禁言 {}", target, format_duration(duration)),
+        )
+        .await
+    }
+    pub async fn command_unban(
+        &mut self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<()> {
+        self.require_admin(evt).await?;
+        let (target, _) = self.require_target(evt, args).await?;
+        self.check_target(evt, target).await?;
+        self.client
+            .as_ref()
+            .unwrap()
+            .set_group_ban(evt.group_id as u64, target as u64, 0)
+            .await?;
+        self.send(evt, &format!("已解除 {} 的禁言", target)).await
+    }
+    pub async fn command_muteall(
+        &mut self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<()> {
+        self.require_admin(evt).await?;
+        if self.bot_rank(evt.group_id).await? < 1 {
+            return Err(anyhow!("机器人不是本群管理员!").into());
+        }
+        match args.first().map(|s| s.as_str()) {
+            Some("on") | Some("开") | Some("开启") => {
+                self.confirm_or_execute(
+                    evt,
+                    PendingAction::WholeBan { enable: true },
+                    "开启全员禁言",
+                )
+                .await
+            }
+            Some("off") | Some("关") | Some("关闭") => {
+                self.execute_action(evt, PendingAction::WholeBan { enable: false })
+                    .await
+            }
+            _ => Err(anyhow!("请使用 muteall on 或 muteall off").into()),
+        }
+    }
+    pub async fn command_setcard(
+        &mut self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<()> {
+        let (target, rest) = self.resolve_target(evt, args).await?;
+        let target = target.unwrap_or(evt.user_id);
+        let allow_self_card = self.config.as_ref().unwrap().allow_self_card;
+        if !(target == evt.user_id && allow_self_card) {
+            self.require_admin(evt).await?;
+            if target != evt.user_id {
+                self.check_target(evt, target).await?;
+            }
+        }
+        let card = rest.join(" ");
+        self.client
+            .as_ref()
+            .unwrap()
+            .set_group_card(evt.group_id as u64, target as u64, &card)
+            .await?;
+        self.send(
+            evt,
+            &if card.is_empty() {
+                format!("已清除 {} 的群名片", target)
+            } else {
+                format!("已将 {} 的群名片设置为: {}", target, card)
+            },
+        )
+        .await
+    }
+    pub async fn command_settitle(
+        &mut self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<()> {
+        self.require_admin(evt).await?;
+        if self.bot_rank(evt.group_id).await? < 2 {
+            return Err(anyhow!("机器人需要为群主才能设置专属头衔!").into());
+        }
+        let (target, rest) = self.require_target(evt, args).await?;
+        if target != evt.user_id {
+            self.check_target(evt, target).await?;
+        }
+        let title = rest.join(" ");
+        self.client
+            .as_ref()
+            .unwrap()
+            .set_group_special_title(evt.group_id as u64, target as u64, &title, -1)
+            .await?;
+        self.send(
+            evt,
+            &if title.is_empty() {
+                format!("已清除 {} 的专属头衔", target)
+            } else {
+                format!("已将 {} 的专属头衔设置为: {}", target, title)
+            },
+        )
+        .await
+    }
+    pub async fn command_essence(
+        &mut self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<()> {
+        let client = self.client.as_ref().unwrap();
+        match args.first().map(|s| s.as_str()) {
+            Some("list") | None => {
+                let list = client.get_essence_msg_list(evt.group_id).await?;
+                if list.is_empty() {
+                    return self.send(evt, "本群暂无精华消息").await;
+                }
+                let mut buf = String::from("本群精华消息:\n");
+                for (i, item) in list.iter().enumerate() {
+                    let time = chrono::NaiveDateTime::from_timestamp(item.sender_time, 0);
+                    buf.push_str(&format!(
+                        "{}. {}({}) 于 {} 发送，由 {} 设置 | 消息ID: {}\n",
+                        i + 1,
+                        item.sender_nick,
+                        item.sender_id,
+                        time.format("%Y-%m-%d %H:%M"),
+                        item.operator_nick,
+                        item.message_id
+                    ));
+                }
+                self.send(evt, buf.trim_end()).await
+            }
+            Some(op @ ("add" | "del")) => {
+                self.require_admin(evt).await?;
+                let message_id = get_reply_id(evt)
+                    .or_else(|| args.get(1).and_then(|s| s.parse::<i64>().ok()))
+                    .ok_or(anyhow!("请回复要操作的消息，或指定消息ID!"))?;
+                if op == "add" {
+                    client.set_essence_msg(message_id).await?;
+                    self.send(evt, "已设为精华消息").await
+                } else {
+                    client.delete_essence_msg(message_id).await?;
+                    self.send(evt, "已移除精华消息").await
+                }
+            }
+            Some(other) => Err(anyhow!("未知操作: {}，可用操作: list, add, del", other).into()),
+        }
+    }
+    pub async fn command_confirm(&mut self, evt: &GroupMessageEvent) -> ResultType<()> {
+        let now = chrono::Local::now().timestamp();
+        self.pending.retain(|_, (_, expire)| *expire >= now);
+        let (action, _) = self
+            .pending
+            .remove(&(evt.group_id, evt.user_id))
+            .ok_or(anyhow!("你没有待确认的操作，或操作已超时!"))?;
+        self.execute_action(evt, action).await
+    }
+    pub async fn command_cancel(&mut self, evt: &GroupMessageEvent) -> ResultType<()> {
+        match self.pending.remove(&(evt.group_id, evt.user_id)) {
+            Some(_) => self.send(evt, "已取消").await,
+            None => self.send(evt, "你没有待确认的操作").await,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GroupAdminConfig {
+    // 不受群身份限制的用户
+    pub super_users: Vec<i64>,
+    // 踢人、全员禁言前是否需要确认
+    pub require_confirm: bool,
+    // 确认的有效时间(秒)
+    pub confirm_timeout: i64,
+    // 最长禁言时间(秒)，QQ的上限为30天
+    pub max_ban_duration: u64,
+    // 允许普通成员修改自己的群名片
+    pub allow_self_card: bool,
+    pub disable_groups: Vec<i64>,
+}
+
+impl Default for GroupAdminConfig {
+    fn default() -> Self {
+        Self {
+            super_users: vec![],
+            require_confirm: true,
+            confirm_timeout: 60,
+            max_ban_duration: 30 * 24 * 60 * 60 - 1,
+            allow_self_card: true,
+            disable_groups: vec![],
+        }
+    }
+}
@@ -0,0 +1,56 @@
+// 解析形如 30 / 10m / 1h30m / 2d / 1天2小时 / 30分钟 的时长，返回秒数
+// 不带单位的数字按分钟计算
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(v) = text.parse::<u64>() {
+        return v.checked_mul(60);
+    }
+    let mut total: u64 = 0;
+    let mut chars = text.chars().peekable();
+    while chars.peek().is_some() {
+        let mut number = String::new();
+        while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            number.push(*c);
+            chars.next();
+        }
+        let mut unit = String::new();
+        while let Some(c) = chars.peek().filter(|c| !c.is_ascii_digit()) {
+            unit.push(*c);
+            chars.next();
+        }
+        let value = number.parse::<u64>().ok()?;
+        let scale = match unit.trim() {
+            "d" | "day" | "days" | "天" => 24 * 60 * 60,
+            "h" | "hour" | "hours" | "小时" | "时" => 60 * 60,
+            "m" | "min" | "mins" | "分钟" | "分" => 60,
+            "s" | "sec" | "secs" | "秒" => 1,
+            _ => return None,
+        };
+        total = total.checked_add(value.checked_mul(scale)?)?;
+    }
+    return Some(total);
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    let units = [
+        (24 * 60 * 60, "天"),
+        (60 * 60, "小时"),
+        (60, "分钟"),
+        (1, "秒"),
+    ];
+    let mut rest = seconds;
+    let mut out = String::new();
+    for (scale, name) in units.iter() {
+        if rest >= *scale {
+            out.push_str(&format!("{}{}", rest / scale, name));
+            rest %= scale;
+        }
+    }
+    if out.is_empty() {
+        out.push_str("0秒");
+    }
+    return out;
+}
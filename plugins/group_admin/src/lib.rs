@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use config::GroupAdminConfig;
+use countdown_bot3::{
+    countdown_bot::{
+        bot,
+        client::CountdownBotClient,
+        command::{Command, SenderType},
+        plugin::{BotPlugin, HookResult, PluginMeta},
+        utils::load_config_or_save_default,
+    },
+    export_static_plugin,
+};
+use log::debug;
+static PLUGIN_NAME: &str = "group_admin";
+
+mod command_impl;
+mod config;
+pub mod duration;
+mod permission_impl;
+
+// 需要确认后才执行的操作
+#[derive(Debug, Clone)]
+enum PendingAction {
+    Kick { user_id: i64, reject_add: bool },
+    WholeBan { enable: bool },
+}
+#[derive(Default)]
+struct GroupAdminPlugin {
+    client: Option<CountdownBotClient>,
+    config: Option<GroupAdminConfig>,
+    // (群号, 发起者QQ) -> (操作, 过期时间)
+    pending: HashMap<(i64, i64), (PendingAction, i64)>,
+}
+
+#[async_trait]
+impl BotPlugin for GroupAdminPlugin {
+    fn on_enable(
+        &mut self,
+        bot: &mut bot::CountdownBot,
+        _handle: tokio::runtime::Handle,
+    ) -> HookResult<()> {
+        self.config = Some(load_config_or_save_default(
+            &bot.ensure_plugin_data_dir(PLUGIN_NAME)?,
+        )?);
+        debug!("Config: {:#?}", self.config);
+        bot.register_command(
+            Command::new("kick")
+                .group(true)
+                .single_alias("踢出")
+                .description(
+                    "将成员移出本群 | kick <QQ号或@> [--block] | 也可回复目标用户的消息使用",
+                ),
+        )?;
+        bot.register_command(
+            Command::new("ban")
+                .group(true)
+                .single_alias("禁言")
+                .description("禁言成员 | ban <QQ号或@> <时长,如 30m, 1h30m, 2d, 1天> | 也可回复目标用户的消息使用"),
+        )?;
+        bot.register_command(
+            Command::new("unban")
+                .group(true)
+                .single_alias("解除禁言")
+                .description("解除成员禁言 | unban <QQ号或@>"),
+        )?;
+        bot.register_command(
+            Command::new("muteall")
+                .group(true)
+                .single_alias("全员禁言")
+                .description("全员禁言 | muteall <on/off>"),
+        )?;
+        bot.register_command(
+            Command::new("setcard")
+                .group(true)
+                .single_alias("群名片")
+                .description("设置群名片 | setcard <QQ号或@> [名片,留空则清除]"),
+        )?;
+        bot.register_command(
+            Command::new("settitle")
+                .group(true)
+                .single_alias("头衔")
+                .description(
+                    "设置专属头衔(需要机器人为群主) | settitle <QQ号或@> [头衔,留空则清除]",
+                ),
+        )?;
+        bot.register_command(
+            Command::new("essence")
+                .group(true)
+                .single_alias("精华")
+                .description(
+                    "精华消息 | essence list | 回复某条消息并使用 essence add/del 设置或移除精华",
+                ),
+        )?;
+        bot.register_command(
+            Command::new("confirm")
+                .group(true)
+                .single_alias("确认")
+                .description("确认执行群管理操作"),
+        )?;
+        bot.register_command(
+            Command::new("cancel")
+                .group(true)
+                .single_alias("取消")
+                .description("取消待确认的群管理操作"),
+        )?;
+        Ok(())
+    }
+    fn on_before_start(
+        &mut self,
+        _bot: &mut bot::CountdownBot,
+        client: CountdownBotClient,
+    ) -> HookResult<()> {
+        self.client = Some(client);
+        Ok(())
+    }
+    fn get_meta(&self) -> PluginMeta {
+        PluginMeta {
+            author: String::from("officeyutong"),
+            description: String::from("群管理"),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+    async fn on_command(
+        &mut self,
+        command: String,
+        args: Vec<String>,
+        sender: &SenderType,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let evt = match sender {
+            SenderType::Group(evt) => evt,
+            _ => return Err(anyhow!("请在群内使用此指令!").into()),
+        };
+        if self
+            .config
+            .as_ref()
+            .unwrap()
+            .disable_groups
+            .contains(&evt.group_id)
+        {
+            return Err(anyhow!("本群已禁用群管理功能!").into());
+        }
+        let args = args
+            .iter()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        match command.as_str() {
+            "kick" => self.command_kick(evt, &args).await?,
+            "ban" => self.command_ban(evt, &args).await?,
+            "unban" => self.command_unban(evt, &args).await?,
+            "muteall" => self.command_muteall(evt, &args).await?,
+            "setcard" => self.command_setcard(evt, &args).await?,
+            "settitle" => self.command_settitle(evt, &args).await?,
+            "essence" => self.command_essence(evt, &args).await?,
+            "confirm" => self.command_confirm(evt).await?,
+            "cancel" => self.command_cancel(evt).await?,
+            _ => todo!(),
+        };
+        Ok(())
+    }
+}
+
+export_static_plugin!(PLUGIN_NAME, GroupAdminPlugin::default());
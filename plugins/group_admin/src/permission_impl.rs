@@ -0,0 +1,126 @@
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::ResultType,
+    event::message::{GroupMessageEvent, GroupSenderRole},
+    message::cq_code::parse_cq_code,
+};
+
+use crate::GroupAdminPlugin;
+
+fn role_rank(role: &GroupSenderRole) -> i32 {
+    match role {
+        GroupSenderRole::Owner => 2,
+        GroupSenderRole::Admin => 1,
+        GroupSenderRole::Member => 0,
+    }
+}
+// 解析 QQ号 或 [CQ:at,qq=xxx]
+pub fn parse_target(arg: &str) -> Option<i64> {
+    if let Some(rest) = arg.strip_prefix("[CQ:at,qq=") {
+        return rest
+            .split(|c| c == ',' || c == ']')
+            .next()?
+            .parse::<i64>()
+            .ok();
+    }
+    arg.parse::<i64>().ok().filter(|v| *v > 10000)
+}
+// 被回复的消息ID，回复消息中的指令需要在机器人配置中开启command_in_reply
+pub fn get_reply_id(evt: &GroupMessageEvent) -> Option<i64> {
+    parse_cq_code(&evt.raw_message)
+        .into_iter()
+        .find(|seg| seg["type"] == "reply")
+        .and_then(|seg| seg["data"]["id"].as_str()?.parse::<i64>().ok())
+}
+
+impl GroupAdminPlugin {
+    pub fn is_super_user(&self, user_id: i64) -> bool {
+        self.config.as_ref().unwrap().super_users.contains(&user_id)
+    }
+    // 超级用户视为高于群主
+    async fn sender_rank(&self, evt: &GroupMessageEvent) -> ResultType<i32> {
+        if self.is_super_user(evt.user_id) {
+            return Ok(3);
+        }
+        let role = self
+            .client
+            .as_ref()
+            .unwrap()
+            .get_sender_role(evt)
+            .await
+            .map_err(|e| anyhow!("无法获取你的群身份: {}", e))?
+            .ok_or(anyhow!("无法获取你的群身份!"))?;
+        Ok(role_rank(&role))
+    }
+    pub async fn require_admin(&self, evt: &GroupMessageEvent) -> ResultType<()> {
+        if self.sender_rank(evt).await? < 1 {
+            return Err(anyhow!("只有群主或管理员可以使用此指令!").into());
+        }
+        Ok(())
+    }
+    async fn member_rank(&self, group_id: i64, user_id: i64) -> ResultType<i32> {
+        let role = self
+            .client
+            .as_ref()
+            .unwrap()
+            .get_member_role(group_id, user_id, true)
+            .await
+            .map_err(|e| anyhow!("无法获取成员 {} 的信息: {}", user_id, e))?
+            .ok_or(anyhow!("无法获取成员 {} 的群身份!", user_id))?;
+        Ok(role_rank(&role))
+    }
+    pub async fn bot_rank(&self, group_id: i64) -> ResultType<i32> {
+        let login_info = self.client.as_ref().unwrap().get_login_info().await?;
+        self.member_rank(group_id, login_info.user_id as i64).await
+    }
+    // 发起者与机器人的身份都必须高于目标
+    pub async fn check_target(&self, evt: &GroupMessageEvent, target: i64) -> ResultType<()> {
+        if target == evt.user_id {
+            return Err(anyhow!("不能对自己执行此操作!").into());
+        }
+        let target_rank = self.member_rank(evt.group_id, target).await?;
+        if self.sender_rank(evt).await? <= target_rank {
+            return Err(anyhow!("你的群身份不高于目标用户，无法执行此操作!").into());
+        }
+        if self.bot_rank(evt.group_id).await? <= target_rank {
+            return Err(anyhow!("机器人的群身份不高于目标用户，无法执行此操作!").into());
+        }
+        Ok(())
+    }
+    // 回复消息时以被回复者为目标，否则从第一个参数读取
+    // 返回目标与剩余参数
+    pub async fn resolve_target(
+        &self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<(Option<i64>, Vec<String>)> {
+        if let Some(reply_id) = get_reply_id(evt) {
+            let msg = self.client.as_ref().unwrap().get_msg(reply_id).await?;
+            let user_id = msg.sender["user_id"]
+                .as_i64()
+                .ok_or(anyhow!("无法获取被回复消息的发送者!"))?;
+            let rest = args
+                .iter()
+                .skip_while(|s| parse_target(s) == Some(user_id))
+                .cloned()
+                .collect();
+            return Ok((Some(user_id), rest));
+        }
+        match args.first().and_then(|s| parse_target(s)) {
+            Some(v) => Ok((Some(v), args[1..].to_vec())),
+            None => Ok((None, args.to_vec())),
+        }
+    }
+    pub async fn require_target(
+        &self,
+        evt: &GroupMessageEvent,
+        args: &[String],
+    ) -> ResultType<(i64, Vec<String>)> {
+        match self.resolve_target(evt, args).await? {
+            (Some(v), rest) => Ok((v, rest)),
+            (None, _) => {
+                Err(anyhow!("请指定目标用户(QQ号或@)，或回复目标用户的消息使用此指令!").into())
+            }
+        }
+    }
+}
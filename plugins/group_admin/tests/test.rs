@@ -0,0 +1,13 @@
+use group_admin::duration::{format_duration, parse_duration};
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("30"), Some(30 * 60));
+    assert_eq!(parse_duration("1h30m"), Some(90 * 60));
+    assert_eq!(parse_duration("2d"), Some(2 * 24 * 60 * 60));
+    assert_eq!(parse_duration("1天2小时"), Some(26 * 60 * 60));
+    assert_eq!(parse_duration("45秒"), Some(45));
+    assert_eq!(parse_duration("abc"), None);
+    assert_eq!(parse_duration("10x"), None);
+    assert_eq!(format_duration(90 * 60 + 5), "1小时30分钟5秒");
+}
@@ -1,8 +1,47 @@
 use countdown_bot3::countdown_bot::client::ResultType;
 use log::info;
 
-use crate::{exec_impl::ExecuteResult, MathPlugin};
+use crate::{
+    config::LatexRenderer, exec_impl::ExecuteResult, render::render_latex_png, MathPlugin,
+};
 use anyhow::anyhow;
+
+const MAX_MATRIX_SIZE: usize = 6;
+
+fn parse_number(args: &[String], index: usize, name: &str) -> ResultType<f64> {
+    let value = args
+        .get(index)
+        .ok_or(anyhow!("请提供{}!", name))?
+        .parse::<f64>()
+        .map_err(|_| anyhow!("请提供合法的{}!", name))?;
+    if !value.is_finite() {
+        return Err(anyhow!("请提供合法的{}!", name).into());
+    }
+    Ok(value)
+}
+
+// 矩阵格式: 行之间以;分隔，元素之间以,分隔，如 1,2;3,4
+fn parse_matrix(text: &str) -> ResultType<Vec<Vec<String>>> {
+    let rows = text
+        .split(';')
+        .map(|row| {
+            row.split(',')
+                .map(|v| v.trim().to_string())
+                .collect::<Vec<String>>()
+        })
+        .collect::<Vec<Vec<String>>>();
+    if rows.iter().flatten().any(|v| v.is_empty()) {
+        return Err(anyhow!("矩阵中存在空元素!").into());
+    }
+    if rows.len() > MAX_MATRIX_SIZE {
+        return Err(anyhow!("矩阵的大小不能超过 {}x{}", MAX_MATRIX_SIZE, MAX_MATRIX_SIZE).into());
+    }
+    if rows.iter().any(|row| row.len() != rows.len()) {
+        return Err(anyhow!("请提供方阵!").into());
+    }
+    Ok(rows)
+}
+
 impl MathPlugin {
     pub async fn execute(
         &self,
@@ -12,13 +51,37 @@ impl MathPlugin {
         let config = self.config.as_ref().unwrap();
         let template = include_str!("template.py");
         info!("Executing {}", code);
-        let replaced_template = template.replace("{CODE}", code).replace(
-            "{PACKAGES}",
-            &serde_json::to_string(&config.latex_packages)?,
-        );
-        return self
+        let local_render = config.latex_renderer == LatexRenderer::Local;
+        let replaced_template = template
+            .replace("{CODE}", code)
+            .replace(
+                "{PACKAGES}",
+                &serde_json::to_string(&config.latex_packages)?,
+            )
+            .replace(
+                "{LOCAL_RENDER}",
+                if local_render { "True" } else { "False" },
+            );
+        let mut result = self
             .handle_exec(&replaced_template, custom_timeout_message)
-            .await;
+            .await?;
+        if local_render && result.image.is_empty() && !result.latex.is_empty() {
+            result.image = base64::encode(self.render_local(&result.latex).await?);
+        }
+        return Ok(result);
+    }
+    // 渲染耗时较长，放到阻塞线程中执行
+    async fn render_local(&self, formula: &str) -> ResultType<Vec<u8>> {
+        let config = self.config.as_ref().unwrap();
+        let formula = formula.to_string();
+        let font_size = config.local_render_font_size;
+        let font_family = config.local_render_font_family.clone();
+        let image = tokio::task::spawn_blocking(move || {
+            render_latex_png(&formula, font_size, &font_family).map_err(|e| e.to_string())
+        })
+        .await?
+        .map_err(|e| anyhow!(e))?;
+        Ok(image)
     }
     pub async fn command_solve(&self, args: Vec<String>) -> ResultType<ExecuteResult> {
         let x = args.get(0).ok_or(anyhow!("请提供未知数!"))?;
@@ -41,8 +104,139 @@ impl MathPlugin {
     }
     pub async fn command_integrate(&self, args: Vec<String>) -> ResultType<ExecuteResult> {
         let x = args.get(0).ok_or(anyhow!("请提供要积分的函数"))?;
+        if args.len() == 1 {
+            return self
+                .execute(&format!("output=integrate('{}')", x), Some("积分运行超时!"))
+                .await;
+        }
+        let lower = args.get(1).ok_or(anyhow!("请提供积分下限!"))?;
+        let upper = args.get(2).ok_or(anyhow!("请提供积分上限!"))?;
+        return self
+            .execute(
+                &format!(
+                    "output=definite_integrate({},{},{})",
+                    serde_json::to_string(x)?,
+                    serde_json::to_string(lower)?,
+                    serde_json::to_string(upper)?
+                ),
+                Some("积分运行超时!"),
+            )
+            .await;
+    }
+    pub async fn command_limit(&self, args: Vec<String>) -> ResultType<ExecuteResult> {
+        let x0 = args.get(0).ok_or(anyhow!("请提供趋近点!"))?;
+        let func = args.get(1).ok_or(anyhow!("请提供函数!"))?;
+        let direction = match args.get(2).map(|s| s.as_str()) {
+            None => "+-",
+            Some(v @ ("+" | "-")) => v,
+            Some(v) => return Err(anyhow!("非法的方向: {}，请使用 + 或 -", v).into()),
+        };
         return self
-            .execute(&format!("output=integrate('{}')", x), Some("积分运行超时!"))
+            .execute(
+                &format!(
+                    "output=limit({},{},{})",
+                    serde_json::to_string(x0)?,
+                    serde_json::to_string(func)?,
+                    serde_json::to_string(direction)?
+                ),
+                Some("求极限运行超时!"),
+            )
+            .await;
+    }
+    pub async fn command_simplify(
+        &self,
+        args: Vec<String>,
+        expand: bool,
+    ) -> ResultType<ExecuteResult> {
+        let expr = args.join(" ");
+        if expr.trim().is_empty() {
+            return Err(anyhow!("请提供式子!").into());
+        }
+        return self
+            .execute(
+                &format!(
+                    "output={}({})",
+                    if expand { "expand" } else { "simplify" },
+                    serde_json::to_string(&expr)?
+                ),
+                Some("化简运行超时!"),
+            )
+            .await;
+    }
+    pub async fn command_matrix(&self, args: Vec<String>) -> ResultType<ExecuteResult> {
+        let operation = args
+            .get(0)
+            .ok_or(anyhow!("请提供操作: det, inv, eigen"))?
+            .as_str();
+        if !["det", "inv", "eigen"].contains(&operation) {
+            return Err(anyhow!("非法的操作: {}，可用操作: det, inv, eigen", operation).into());
+        }
+        if args.len() < 2 {
+            return Err(anyhow!("请提供矩阵，如 1,2;3,4").into());
+        }
+        let rows = parse_matrix(&args[1..].join(""))?;
+        return self
+            .execute(
+                &format!(
+                    "output=matrix({},{})",
+                    serde_json::to_string(operation)?,
+                    serde_json::to_string(&rows)?
+                ),
+                Some("矩阵运算运行超时!"),
+            )
+            .await;
+    }
+    pub async fn command_dsolve(&self, args: Vec<String>) -> ResultType<ExecuteResult> {
+        let equation = args.join(" ");
+        if equation.trim().is_empty() {
+            return Err(anyhow!("请提供微分方程!").into());
+        }
+        if equation.matches('=').count() > 1 {
+            return Err(anyhow!("方程中只能有一个等号!").into());
+        }
+        if !equation.contains("f(x)") {
+            return Err(anyhow!("请使用 f(x) 表示未知函数，如 f(x).diff(x,2)+f(x)=0").into());
+        }
+        return self
+            .execute(
+                &format!("output=dsolve({})", serde_json::to_string(&equation)?),
+                Some("解微分方程运行超时!"),
+            )
+            .await;
+    }
+    pub async fn command_plot3d(&self, args: Vec<String>) -> ResultType<ExecuteResult> {
+        let config = self.config.as_ref().unwrap();
+        let x_begin = parse_number(&args, 0, "x起始点")?;
+        let x_end = parse_number(&args, 1, "x终点")?;
+        let y_begin = parse_number(&args, 2, "y起始点")?;
+        let y_end = parse_number(&args, 3, "y终点")?;
+        if x_begin >= x_end || y_begin >= y_end {
+            return Err(anyhow!("起始点必须小于终点!").into());
+        }
+        let range_limit = config.matplot_range_length as f64;
+        if x_end - x_begin > range_limit || y_end - y_begin > range_limit {
+            return Err(anyhow!("绘图范围的长度不能超过 {}", range_limit).into());
+        }
+        if args.len() < 5 {
+            return Err(anyhow!("请提供足够的参数!").into());
+        }
+        let joined = args[4..].join(" ");
+        let funcs = joined.split(",").collect::<Vec<&str>>();
+        if funcs.len() > config.function_count_limit as usize {
+            return Err(anyhow!("函数数量不能超过 {}", config.function_count_limit).into());
+        }
+        return self
+            .execute(
+                &format!(
+                    "output=plot3d({},{},{},{},{})",
+                    x_begin,
+                    x_end,
+                    y_begin,
+                    y_end,
+                    serde_json::to_string(&funcs)?
+                ),
+                Some("绘图运行运行超时!"),
+            )
             .await;
     }
     pub async fn command_diff(&self, args: Vec<String>) -> ResultType<ExecuteResult> {
@@ -113,6 +307,14 @@ impl MathPlugin {
     }
     pub async fn command_latex(&self, args: Vec<String>) -> ResultType<ExecuteResult> {
         let str = args.join(" ");
+        if self.config.as_ref().unwrap().latex_renderer == LatexRenderer::Local {
+            return Ok(ExecuteResult {
+                image: base64::encode(self.render_local(&str).await?),
+                latex: str,
+                python_expr: String::new(),
+                error: String::new(),
+            });
+        }
         let template = format!("import base64\noutput={{'latex':'','python_expr':'','image':render_latex(base64.decodebytes('{}'.encode()).decode())}}",base64::encode(str.as_bytes()));
         return self.execute(&template, Some("LaTeX渲染运行超时!")).await;
     }
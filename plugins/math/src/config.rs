@@ -1,5 +1,15 @@
+use countdown_bot3::countdown_bot::sandbox::{pool::PoolConfig, ResourceLimits, SandboxConfig};
 use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LatexRenderer {
+    // 在沙箱中调用TeX渲染，支持完整的LaTeX
+    Sandbox,
+    // 使用内置的渲染器，速度快但仅支持常用公式
+    Local,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MathPluginConfig {
     pub docker_image: String,
@@ -7,6 +17,13 @@ pub struct MathPluginConfig {
     pub function_count_limit: i32,
     pub matplot_range_length: i32,
     pub latex_packages: Vec<String>,
+    pub sandbox: SandboxConfig,
+    pub limits: ResourceLimits,
+    // 预热的容器池，预热命令只会让sympy等库的文件进入页面缓存，不会保留常驻的解释器
+    pub pool: PoolConfig,
+    pub latex_renderer: LatexRenderer,
+    pub local_render_font_size: u32,
+    pub local_render_font_family: String,
 }
 
 impl Default for MathPluginConfig {
@@ -27,6 +44,18 @@ impl Default for MathPluginConfig {
             .iter()
             .map(|x| String::from(*x))
             .collect(),
+            sandbox: SandboxConfig::default(),
+            limits: ResourceLimits {
+                memory_mb: 128,
+                ..Default::default()
+            },
+            pool: PoolConfig {
+                warmup_command: "python -c 'import sympy, numpy, matplotlib'".to_string(),
+                ..Default::default()
+            },
+            latex_renderer: LatexRenderer::Sandbox,
+            local_render_font_size: 32,
+            local_render_font_family: "serif".to_string(),
         }
     }
 }
@@ -6,22 +6,25 @@ use countdown_bot3::{
         client::CountdownBotClient,
         command::{Command, SenderType},
         plugin::{BotPlugin, HookResult, PluginMeta},
+        sandbox::{create_sandbox, SandboxWrapped},
         utils::load_config_or_save_default,
     },
     export_static_plugin,
 };
 use html_escape::decode_html_entities;
-use log::debug;
+use log::{debug, error};
 
 static PLUGIN_NAME: &str = "math";
 
 mod command_impl;
 mod config;
 mod exec_impl;
+pub mod render;
 
 struct MathPlugin {
     client: Option<CountdownBotClient>,
     config: Option<MathPluginConfig>,
+    sandbox: Option<SandboxWrapped>,
 }
 
 impl Default for MathPlugin {
@@ -29,6 +32,7 @@ impl Default for MathPlugin {
         Self {
             client: None,
             config: None,
+            sandbox: None,
         }
     }
 }
@@ -38,12 +42,26 @@ impl BotPlugin for MathPlugin {
     fn on_enable(
         &mut self,
         bot: &mut bot::CountdownBot,
-        _handle: tokio::runtime::Handle,
+        handle: tokio::runtime::Handle,
     ) -> HookResult<()> {
         self.config = Some(load_config_or_save_default(
             &bot.ensure_plugin_data_dir(PLUGIN_NAME)?,
         )?);
         debug!("Config: {:#?}", self.config);
+        self.sandbox = Some(create_sandbox(&self.config.as_ref().unwrap().sandbox)?);
+        {
+            let config = self.config.clone().unwrap();
+            let sandbox = self.sandbox.clone().unwrap();
+            handle.spawn(async move {
+                if let Err(e) = sandbox
+                    .warmup(&config.docker_image, &config.limits, &config.pool)
+                    .await
+                {
+                    error!("Failed to warm up container pool: {}", e);
+                }
+            });
+        }
+        bot.register_state_hook();
         bot.register_command(
             Command::new("solve")
                 .group(true)
@@ -54,7 +72,37 @@ impl BotPlugin for MathPlugin {
             Command::new("integrate")
                 .group(true)
                 .guild(true)
-                .description("不定积分 | integrate <函数>"),
+                .description("积分 | integrate <函数> [下限 上限]"),
+        )?;
+        bot.register_command(
+            Command::new("limit")
+                .group(true)
+                .guild(true)
+                .description("求极限 | limit <趋近点> <函数> [+/-]"),
+        )?;
+        bot.register_command(
+            Command::new("simplify")
+                .group(true)
+                .guild(true)
+                .description("化简 | simplify <式子>"),
+        )?;
+        bot.register_command(
+            Command::new("expand")
+                .group(true)
+                .guild(true)
+                .description("展开 | expand <式子>"),
+        )?;
+        bot.register_command(
+            Command::new("matrix")
+                .group(true)
+                .guild(true)
+                .description("矩阵运算 | matrix <det/inv/eigen> <矩阵,如 1,2;3,4>"),
+        )?;
+        bot.register_command(
+            Command::new("dsolve")
+                .group(true)
+                .guild(true)
+                .description("解常微分方程 | dsolve <方程,以f(x)表示未知函数,如 f(x).diff(x,2)+f(x)=0>"),
         )?;
         bot.register_command(
             Command::new("diff")
@@ -85,6 +133,12 @@ impl BotPlugin for MathPlugin {
                 .group(true).guild(true)
                 .description("绘制参数方程函数图像 | plotpe <参数起始点(参数符号为t)> <参数重点> <x方程1:y方程1[,x方程2:y方程2[,...]]>"),
         )?;
+        bot.register_command(
+            Command::new("plot3d")
+                .group(true)
+                .guild(true)
+                .description("绘制三维曲面 | plot3d <x起始点> <x终点> <y起始点> <y终点> <函数1[,函数2[,...]]>"),
+        )?;
         bot.register_command(
             Command::new("factor")
                 .group(true)
@@ -101,6 +155,12 @@ impl BotPlugin for MathPlugin {
         self.client = Some(client);
         Ok(())
     }
+    async fn on_disable(&mut self) -> HookResult<()> {
+        if let Some(sandbox) = self.sandbox.as_ref() {
+            sandbox.shutdown().await;
+        }
+        Ok(())
+    }
     fn get_meta(&self) -> PluginMeta {
         PluginMeta {
             author: String::from("officeyutong"),
@@ -108,6 +168,17 @@ impl BotPlugin for MathPlugin {
             version: env!("CARGO_PKG_VERSION").to_string(),
         }
     }
+    async fn on_state_hook(&mut self) -> HookResult<String> {
+        return Ok(self
+            .sandbox
+            .as_ref()
+            .unwrap()
+            .pool_stats()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join("\n"));
+    }
     async fn on_command(
         &mut self,
         command: String,
@@ -136,6 +207,15 @@ impl MathPlugin {
             "integrate" => self.command_integrate(args).await?,
             "diff" => self.command_diff(args).await?,
             "series" => self.command_series(args).await?,
+            "limit" => self.command_limit(args).await?,
+            "simplify" => self.command_simplify(args, false).await?,
+            "expand" => self.command_simplify(args, true).await?,
+            "matrix" => self.command_matrix(args).await?,
+            "dsolve" => self.command_dsolve(args).await?,
+            "plot3d" => {
+                image_only = true;
+                self.command_plot3d(args).await?
+            }
             "plot" => {
                 image_only = true;
                 self.command_plot(args).await?
@@ -0,0 +1,430 @@
+use super::parser::Node;
+
+// 以下尺寸均以em为单位，y轴向下，基线为0
+const ASCENT: f64 = 0.75;
+const DESCENT: f64 = 0.25;
+// 数学轴高度，分数线与大型运算符以此对齐
+const AXIS: f64 = 0.25;
+const RULE: f64 = 0.05;
+
+#[derive(Debug, Clone)]
+pub enum Item {
+    Text {
+        x: f64,
+        y: f64,
+        size: f64,
+        italic: bool,
+        content: String,
+    },
+    Line {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        thickness: f64,
+    },
+    Polyline {
+        points: Vec<(f64, f64)>,
+        thickness: f64,
+    },
+}
+
+impl Item {
+    fn translate(self, dx: f64, dy: f64) -> Item {
+        match self {
+            Item::Text {
+                x,
+                y,
+                size,
+                italic,
+                content,
+            } => Item::Text {
+                x: x + dx,
+                y: y + dy,
+                size,
+                italic,
+                content,
+            },
+            Item::Line {
+                x1,
+                y1,
+                x2,
+                y2,
+                thickness,
+            } => Item::Line {
+                x1: x1 + dx,
+                y1: y1 + dy,
+                x2: x2 + dx,
+                y2: y2 + dy,
+                thickness,
+            },
+            Item::Polyline { points, thickness } => Item::Polyline {
+                points: points.into_iter().map(|(x, y)| (x + dx, y + dy)).collect(),
+                thickness,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LayoutBox {
+    pub width: f64,
+    pub ascent: f64,
+    pub descent: f64,
+    pub items: Vec<Item>,
+}
+
+impl LayoutBox {
+    fn append(&mut self, other: LayoutBox, dx: f64, dy: f64) {
+        self.ascent = self.ascent.max(other.ascent - dy);
+        self.descent = self.descent.max(other.descent + dy);
+        self.items
+            .extend(other.items.into_iter().map(|v| v.translate(dx, dy)));
+    }
+    fn height(&self) -> f64 {
+        self.ascent + self.descent
+    }
+}
+
+// 没有字体度量信息，只能粗略估计字符宽度
+fn char_width(c: char) -> f64 {
+    match c {
+        'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '!' | '|' | '\'' | '′' => 0.3,
+        'f' | 't' | 'r' | '(' | ')' | '[' | ']' | '{' | '}' => 0.38,
+        'm' | 'w' | 'M' | 'W' => 0.8,
+        'A'..='Z' => 0.68,
+        '0'..='9' | 'a'..='z' | ' ' => 0.52,
+        c if c.is_ascii() => 0.6,
+        '∑' | '∏' | '∫' | '∬' | '∮' | '⋃' | '⋂' => 0.8,
+        '\u{0370}'..='\u{03FF}' | '\u{2000}'..='\u{2BFF}' => 0.62,
+        _ => 1.0,
+    }
+}
+
+fn text_box(content: &str, size: f64, italic: bool) -> LayoutBox {
+    let width = content.chars().map(char_width).sum::<f64>() * size;
+    LayoutBox {
+        width,
+        ascent: ASCENT * size,
+        descent: DESCENT * size,
+        items: vec![Item::Text {
+            x: 0.0,
+            y: 0.0,
+            size,
+            italic,
+            content: content.to_string(),
+        }],
+    }
+}
+
+fn hbox(boxes: Vec<LayoutBox>) -> LayoutBox {
+    let mut result = LayoutBox::default();
+    for item in boxes {
+        let x = result.width;
+        result.width += item.width;
+        result.append(item, x, 0.0);
+    }
+    result
+}
+
+fn padded(inner: LayoutBox, left: f64, right: f64) -> LayoutBox {
+    let mut result = LayoutBox {
+        width: inner.width + left + right,
+        ..Default::default()
+    };
+    result.append(inner, left, 0.0);
+    result
+}
+
+// 按指定高度绘制可伸缩的定界符，中心与内容中心对齐
+fn delimiter(text: &str, ascent: f64, descent: f64, size: f64) -> LayoutBox {
+    if text.is_empty() {
+        return LayoutBox {
+            width: 0.1 * size,
+            ..Default::default()
+        };
+    }
+    let height = (ascent + descent).max(size);
+    let glyph_size = height / (ASCENT + DESCENT);
+    let center = (descent - ascent) / 2.0;
+    let mut glyph = text_box(text, glyph_size, false);
+    // 字形过宽时压缩间距
+    glyph.width = glyph.width.min(0.6 * size).max(0.35 * size);
+    let mut result = LayoutBox {
+        width: glyph.width,
+        ..Default::default()
+    };
+    result.append(glyph, 0.0, center + (ASCENT - DESCENT) / 2.0 * glyph_size);
+    result
+}
+
+fn script_size(size: f64) -> f64 {
+    (size * 0.7).max(0.5)
+}
+
+pub fn layout(node: &Node, size: f64) -> LayoutBox {
+    match node {
+        Node::Variable(c) => text_box(&c.to_string(), size, true),
+        Node::Text(text) | Node::Symbol(text) => text_box(text, size, false),
+        Node::Operator(text) => padded(text_box(text, size, false), 0.22 * size, 0.22 * size),
+        Node::Space(width) => LayoutBox {
+            width: width * size,
+            ..Default::default()
+        },
+        Node::Group(items) => hbox(items.iter().map(|v| layout(v, size)).collect()),
+        Node::Fraction(num, den) => {
+            let child_size = if size >= 1.0 {
+                size * 0.85
+            } else {
+                script_size(size)
+            };
+            let num = layout(num, child_size);
+            let den = layout(den, child_size);
+            let width = num.width.max(den.width) + 0.2 * size;
+            let gap = 0.12 * size;
+            let axis = -AXIS * size;
+            let mut result = LayoutBox {
+                width,
+                ..Default::default()
+            };
+            let num_y = axis - gap - num.descent;
+            let den_y = axis + gap + den.ascent;
+            let (num_x, den_x) = ((width - num.width) / 2.0, (width - den.width) / 2.0);
+            result.append(num, num_x, num_y);
+            result.append(den, den_x, den_y);
+            result.items.push(Item::Line {
+                x1: 0.05 * size,
+                y1: axis,
+                x2: width - 0.05 * size,
+                y2: axis,
+                thickness: RULE * size,
+            });
+            result
+        }
+        Node::Sqrt(inner, index) => {
+            let inner = layout(inner, size);
+            let gap = 0.1 * size;
+            let top = -(inner.ascent + gap);
+            let bottom = inner.descent;
+            let sign_width = 0.55 * size;
+            let mut result = LayoutBox {
+                width: sign_width + inner.width + 0.1 * size,
+                ascent: -top + RULE * size,
+                descent: bottom,
+                items: vec![Item::Polyline {
+                    points: vec![
+                        (0.0, (top + bottom) * 0.55),
+                        (0.15 * size, (top + bottom) * 0.45),
+                        (0.3 * size, bottom),
+                        (sign_width, top),
+                        (sign_width + inner.width + 0.1 * size, top),
+                    ],
+                    thickness: RULE * size,
+                }],
+            };
+            result.append(inner, sign_width + 0.05 * size, 0.0);
+            if let Some(index) = index {
+                let index = layout(index, script_size(script_size(size)));
+                let shift = (index.width - 0.2 * size).max(0.0);
+                let index_y = (top + bottom) * 0.45 - 0.05 * size - index.descent;
+                let mut shifted = LayoutBox {
+                    width: result.width + shift,
+                    ..Default::default()
+                };
+                shifted.append(index, 0.0, index_y);
+                shifted.append(result, shift, 0.0);
+                result = shifted;
+            }
+            result
+        }
+        Node::BigOperator(text, _) => {
+            if text.chars().all(|c| c.is_ascii_alphabetic()) {
+                // lim 等按普通文字排版
+                return padded(text_box(text, size, false), 0.1 * size, 0.1 * size);
+            }
+            let glyph_size = size * 1.5;
+            let glyph = text_box(text, glyph_size, false);
+            // 使字形中心落在数学轴上
+            let baseline = -AXIS * size + (ASCENT - DESCENT) / 2.0 * glyph_size;
+            let mut result = LayoutBox {
+                width: glyph.width + 0.1 * size,
+                ..Default::default()
+            };
+            result.append(glyph, 0.05 * size, baseline);
+            result
+        }
+        Node::Scripts { base, sup, sub } => {
+            let limits = matches!(base.as_ref(), Node::BigOperator(_, true));
+            let base = layout(base, size);
+            let child_size = script_size(size);
+            let sup = sup.as_ref().map(|v| layout(v, child_size));
+            let sub = sub.as_ref().map(|v| layout(v, child_size));
+            if limits {
+                let width = [
+                    base.width,
+                    sup.as_ref().map(|v| v.width).unwrap_or(0.0),
+                    sub.as_ref().map(|v| v.width).unwrap_or(0.0),
+                ]
+                .iter()
+                .cloned()
+                .fold(0.0, f64::max);
+                let gap = 0.08 * size;
+                let (base_ascent, base_descent) = (base.ascent, base.descent);
+                let mut result = LayoutBox {
+                    width,
+                    ..Default::default()
+                };
+                let base_x = (width - base.width) / 2.0;
+                result.append(base, base_x, 0.0);
+                if let Some(sup) = sup {
+                    let y = -base_ascent - gap - sup.descent;
+                    let x = (width - sup.width) / 2.0;
+                    result.append(sup, x, y);
+                }
+                if let Some(sub) = sub {
+                    let y = base_descent + gap + sub.ascent;
+                    let x = (width - sub.width) / 2.0;
+                    result.append(sub, x, y);
+                }
+                return result;
+            }
+            let mut result = LayoutBox {
+                width: base.width,
+                ..Default::default()
+            };
+            let (base_ascent, base_descent, base_width) = (base.ascent, base.descent, base.width);
+            result.append(base, 0.0, 0.0);
+            let mut script_width: f64 = 0.0;
+            if let Some(sup) = sup {
+                let y = -(base_ascent * 0.55).max(sup.descent + 0.25 * size);
+                script_width = script_width.max(sup.width);
+                result.append(sup, base_width + 0.03 * size, y);
+            }
+            if let Some(sub) = sub {
+                let y = (base_descent + 0.1 * size).max(sub.ascent * 0.6);
+                script_width = script_width.max(sub.width);
+                result.append(sub, base_width + 0.03 * size, y);
+            }
+            result.width += script_width + 0.06 * size;
+            result
+        }
+        Node::Delimited(left, inner, right) => {
+            let inner = layout(inner, size);
+            let left = delimiter(left, inner.ascent, inner.descent, size);
+            let right = delimiter(right, inner.ascent, inner.descent, size);
+            hbox(vec![left, inner, right])
+        }
+        Node::Matrix(rows) => {
+            let cells = rows
+                .iter()
+                .map(|row| row.iter().map(|v| layout(v, size)).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let columns = cells.iter().map(|v| v.len()).max().unwrap_or(0);
+            let column_widths = (0..columns)
+                .map(|i| {
+                    cells
+                        .iter()
+                        .filter_map(|row| row.get(i).map(|v| v.width))
+                        .fold(0.0, f64::max)
+                })
+                .collect::<Vec<_>>();
+            let column_gap = 0.8 * size;
+            let row_gap = 0.3 * size;
+            let mut result = LayoutBox {
+                width: column_widths.iter().sum::<f64>()
+                    + column_gap * (columns.max(1) - 1) as f64
+                    + 0.2 * size,
+                ..Default::default()
+            };
+            let row_heights = cells
+                .iter()
+                .map(|row| {
+                    (
+                        row.iter().map(|v| v.ascent).fold(ASCENT * size, f64::max),
+                        row.iter().map(|v| v.descent).fold(DESCENT * size, f64::max),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let total_height = row_heights.iter().map(|(a, d)| a + d).sum::<f64>()
+                + row_gap * (rows.len().max(1) - 1) as f64;
+            // 整体垂直居中于数学轴
+            let mut y = -AXIS * size - total_height / 2.0;
+            for (row, (ascent, descent)) in cells.into_iter().zip(row_heights) {
+                y += ascent;
+                let mut x = 0.1 * size;
+                for (cell, width) in row.into_iter().zip(column_widths.iter()) {
+                    let cell_x = x + (width - cell.width) / 2.0;
+                    result.append(cell, cell_x, y);
+                    x += width + column_gap;
+                }
+                y += descent + row_gap;
+            }
+            result.ascent = result.ascent.max(AXIS * size + total_height / 2.0);
+            result.descent = result.descent.max(total_height / 2.0 - AXIS * size);
+            result
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn to_svg(layout: &LayoutBox, font_size: f64, font_family: &str) -> String {
+    let margin = 0.3;
+    let width = (layout.width + margin * 2.0) * font_size;
+    let height = (layout.height() + margin * 2.0) * font_size;
+    let (dx, dy) = (margin, margin + layout.ascent);
+    let mut buf = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.2} {h:.2}"><rect width="100%" height="100%" fill="white"/>"#,
+        w = width.ceil(),
+        h = height.ceil()
+    );
+    for item in layout.items.iter().cloned() {
+        match item.translate(dx, dy) {
+            Item::Text {
+                x,
+                y,
+                size,
+                italic,
+                content,
+            } => buf.push_str(&format!(
+                r#"<text x="{:.2}" y="{:.2}" font-size="{:.2}" font-family="{}" font-style="{}" fill="black" xml:space="preserve">{}</text>"#,
+                x * font_size,
+                y * font_size,
+                size * font_size,
+                escape(font_family),
+                if italic { "italic" } else { "normal" },
+                escape(&content)
+            )),
+            Item::Line {
+                x1,
+                y1,
+                x2,
+                y2,
+                thickness,
+            } => buf.push_str(&format!(
+                r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="black" stroke-width="{:.2}"/>"#,
+                x1 * font_size,
+                y1 * font_size,
+                x2 * font_size,
+                y2 * font_size,
+                thickness * font_size
+            )),
+            Item::Polyline { points, thickness } => buf.push_str(&format!(
+                r#"<polyline points="{}" fill="none" stroke="black" stroke-width="{:.2}" stroke-linejoin="round"/>"#,
+                points
+                    .iter()
+                    .map(|(x, y)| format!("{:.2},{:.2}", x * font_size, y * font_size))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                thickness * font_size
+            )),
+        }
+    }
+    buf.push_str("</svg>");
+    buf
+}
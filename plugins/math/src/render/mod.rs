@@ -0,0 +1,19 @@
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{client::ResultType, text_render::svg_to_png};
+
+pub mod layout;
+pub mod parser;
+
+// 不依赖沙箱与TeX发行版的LaTeX渲染，仅支持常用的数学公式子集
+pub fn render_latex_png(formula: &str, font_size: u32, font_family: &str) -> ResultType<Vec<u8>> {
+    let formula = formula.trim().trim_matches('$').trim();
+    if formula.is_empty() {
+        return Err(anyhow!("公式不能为空!").into());
+    }
+    let node = parser::Parser::new(formula)
+        .parse()
+        .map_err(|e| anyhow!("解析公式失败: {}", e))?;
+    let layout_box = layout::layout(&node, 1.0);
+    let svg = layout::to_svg(&layout_box, font_size as f64, font_family);
+    Ok(svg_to_png(&svg, font_family)?)
+}
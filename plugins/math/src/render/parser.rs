@@ -0,0 +1,463 @@
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::client::ResultType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    // 斜体的变量名
+    Variable(char),
+    // 正体文本，如数字、函数名、\text{}
+    Text(String),
+    // 运算符与其他符号，两侧带有间距
+    Operator(String),
+    Symbol(String),
+    Group(Vec<Node>),
+    Fraction(Box<Node>, Box<Node>),
+    Sqrt(Box<Node>, Option<Box<Node>>),
+    Scripts {
+        base: Box<Node>,
+        sup: Option<Box<Node>>,
+        sub: Option<Box<Node>>,
+    },
+    // 求和、积分等大型运算符，limits为true时上下标置于上下方
+    BigOperator(String, bool),
+    Delimited(String, Box<Node>, String),
+    Matrix(Vec<Vec<Node>>),
+    // 以em为单位的空白
+    Space(f64),
+}
+
+fn symbol_of(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" | "vartheta" => "θ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "forall" => "∀",
+        "exists" => "∃",
+        "emptyset" => "∅",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "prime" => "′",
+        "{" | "lbrace" => "{",
+        "}" | "rbrace" => "}",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "|" => "‖",
+        _ => return None,
+    })
+}
+
+fn operator_of(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "times" => "×",
+        "cdot" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "le" | "leq" => "≤",
+        "ge" | "geq" => "≥",
+        "ne" | "neq" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "in" => "∈",
+        "notin" => "∉",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "cup" => "∪",
+        "cap" => "∩",
+        "to" | "rightarrow" => "→",
+        "leftarrow" => "←",
+        "Rightarrow" => "⇒",
+        "Leftrightarrow" | "iff" => "⇔",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        _ => return None,
+    })
+}
+
+fn big_operator_of(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "sum" => ("∑", true),
+        "prod" => ("∏", true),
+        "int" => ("∫", false),
+        "iint" => ("∬", false),
+        "oint" => ("∮", false),
+        "bigcup" => ("⋃", true),
+        "bigcap" => ("⋂", true),
+        _ => return None,
+    })
+}
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "log", "ln", "lg", "exp", "max", "min", "sup", "inf", "det", "gcd", "deg", "dim",
+];
+
+// 最大嵌套层数，避免过深的递归导致栈溢出
+pub const MAX_DEPTH: usize = 64;
+
+pub struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    pub fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            pos: 0,
+            depth: 0,
+        }
+    }
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            self.pos += 1;
+        }
+    }
+    fn read_command(&mut self) -> String {
+        // 调用时已跳过反斜杠
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphabetic() {
+                name.push(c);
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        if name.is_empty() {
+            if let Some(c) = self.peek() {
+                name.push(c);
+                self.pos += 1;
+            }
+        }
+        name
+    }
+    // 判断当前位置是否为指定指令，如 \right 不应匹配 \rightarrow
+    fn at_command(&self, command: &str) -> bool {
+        let expected = command.chars().collect::<Vec<char>>();
+        let end = self.pos + expected.len();
+        if end > self.chars.len() || self.chars[self.pos..end] != expected[..] {
+            return false;
+        }
+        let alphabetic = expected
+            .last()
+            .map(|c| c.is_ascii_alphabetic())
+            .unwrap_or(false);
+        !(alphabetic
+            && self
+                .chars
+                .get(end)
+                .map(|c| c.is_ascii_alphabetic())
+                .unwrap_or(false))
+    }
+    // 读取 {...} 中的原始文本
+    fn read_raw_group(&mut self) -> ResultType<String> {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return Err(anyhow!("此处需要 {{").into());
+        }
+        self.pos += 1;
+        let mut depth = 1;
+        let mut buf = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(buf);
+                    }
+                }
+                _ => {}
+            }
+            buf.push(c);
+        }
+        Err(anyhow!("括号不匹配").into())
+    }
+    fn read_delimiter(&mut self) -> ResultType<String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('\\') => {
+                self.pos += 1;
+                let name = self.read_command();
+                Ok(symbol_of(&name).unwrap_or("").to_string())
+            }
+            Some('.') => {
+                self.pos += 1;
+                Ok(String::new())
+            }
+            Some(c) => {
+                self.pos += 1;
+                Ok(c.to_string())
+            }
+            None => Err(anyhow!("缺少定界符").into()),
+        }
+    }
+    pub fn parse(&mut self) -> ResultType<Node> {
+        let node = self.parse_sequence(&[])?;
+        if self.pos < self.chars.len() {
+            return Err(anyhow!("多余的 {}", self.chars[self.pos]).into());
+        }
+        Ok(node)
+    }
+    // 解析直到遇到结束符(不消耗)或文本结束
+    fn parse_sequence(&mut self, terminators: &[&str]) -> ResultType<Node> {
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            let c = match self.peek() {
+                Some(c) => c,
+                None => break,
+            };
+            if c == '}' || c == '&' {
+                break;
+            }
+            if c == '\\' && terminators.iter().any(|t| self.at_command(t)) {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            items.push(self.parse_scripts(atom)?);
+        }
+        Ok(if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            Node::Group(items)
+        })
+    }
+    fn parse_scripts(&mut self, base: Node) -> ResultType<Node> {
+        let mut sup = None;
+        let mut sub = None;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('^') if sup.is_none() => {
+                    self.pos += 1;
+                    sup = Some(Box::new(self.parse_atom()?));
+                }
+                Some('_') if sub.is_none() => {
+                    self.pos += 1;
+                    sub = Some(Box::new(self.parse_atom()?));
+                }
+                Some('\'') => {
+                    self.pos += 1;
+                    sup = Some(Box::new(Node::Symbol("′".to_string())));
+                }
+                _ => break,
+            }
+        }
+        if sup.is_none() && sub.is_none() {
+            return Ok(base);
+        }
+        Ok(Node::Scripts {
+            base: Box::new(base),
+            sup,
+            sub,
+        })
+    }
+    fn parse_group(&mut self) -> ResultType<Node> {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return self.parse_atom();
+        }
+        self.pos += 1;
+        let node = self.parse_sequence(&[])?;
+        if self.peek() != Some('}') {
+            return Err(anyhow!("括号不匹配").into());
+        }
+        self.pos += 1;
+        Ok(node)
+    }
+    // 所有递归都会经过此处，在这里统计嵌套层数
+    fn parse_atom(&mut self) -> ResultType<Node> {
+        if self.depth >= MAX_DEPTH {
+            return Err(anyhow!("公式嵌套层数过多，最多为 {} 层", MAX_DEPTH).into());
+        }
+        self.depth += 1;
+        let result = self.parse_atom_inner();
+        self.depth -= 1;
+        result
+    }
+    fn parse_atom_inner(&mut self) -> ResultType<Node> {
+        self.skip_whitespace();
+        let c = self.peek().ok_or(anyhow!("公式不完整"))?;
+        self.pos += 1;
+        Ok(match c {
+            '{' => {
+                self.pos -= 1;
+                self.parse_group()?
+            }
+            '\\' => self.parse_command()?,
+            '0'..='9' | '.' => {
+                let mut buf = c.to_string();
+                while let Some(d) = self.peek() {
+                    if d.is_ascii_digit() || d == '.' {
+                        buf.push(d);
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                Node::Text(buf)
+            }
+            '+' | '-' | '=' | '<' | '>' => Node::Operator(if c == '-' {
+                "−".to_string()
+            } else {
+                c.to_string()
+            }),
+            '^' | '_' => return Err(anyhow!("{} 前缺少内容", c).into()),
+            c if c.is_ascii_alphabetic() => Node::Variable(c),
+            c => Node::Symbol(c.to_string()),
+        })
+    }
+    fn parse_command(&mut self) -> ResultType<Node> {
+        let name = self.read_command();
+        Ok(match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let num = self.parse_group()?;
+                let den = self.parse_group()?;
+                Node::Fraction(Box::new(num), Box::new(den))
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = if self.peek() == Some('[') {
+                    self.pos += 1;
+                    let mut buf = String::new();
+                    while let Some(c) = self.peek() {
+                        self.pos += 1;
+                        if c == ']' {
+                            break;
+                        }
+                        buf.push(c);
+                    }
+                    // 子解析器沿用当前深度，否则嵌套的根指数可以绕过深度限制
+                    let mut parser = Parser::new(&buf);
+                    parser.depth = self.depth;
+                    Some(Box::new(parser.parse()?))
+                } else {
+                    None
+                };
+                Node::Sqrt(Box::new(self.parse_group()?), index)
+            }
+            "left" => {
+                let left = self.read_delimiter()?;
+                let inner = self.parse_sequence(&["\\right"])?;
+                self.skip_whitespace();
+                if self.peek() != Some('\\') {
+                    return Err(anyhow!("\\left 缺少对应的 \\right").into());
+                }
+                self.pos += 1;
+                self.read_command();
+                let right = self.read_delimiter()?;
+                Node::Delimited(left, Box::new(inner), right)
+            }
+            "right" => return Err(anyhow!("\\right 缺少对应的 \\left").into()),
+            "text" | "mathrm" | "operatorname" | "textrm" => Node::Text(self.read_raw_group()?),
+            "mathbf" | "mathit" | "mathbb" | "mathcal" | "boldsymbol" => self.parse_group()?,
+            "begin" => {
+                let env = self.read_raw_group()?;
+                self.parse_matrix(&env)?
+            }
+            "," => Node::Space(0.17),
+            ":" | ">" => Node::Space(0.22),
+            ";" => Node::Space(0.28),
+            " " => Node::Space(0.25),
+            "!" => Node::Space(-0.17),
+            "quad" => Node::Space(1.0),
+            "qquad" => Node::Space(2.0),
+            "\\" => Node::Space(0.0),
+            "lim" => Node::BigOperator("lim".to_string(), true),
+            name if FUNCTIONS.contains(&name) => Node::Text(name.to_string()),
+            name => {
+                if let Some(v) = symbol_of(name) {
+                    Node::Symbol(v.to_string())
+                } else if let Some(v) = operator_of(name) {
+                    Node::Operator(v.to_string())
+                } else if let Some((v, limits)) = big_operator_of(name) {
+                    Node::BigOperator(v.to_string(), limits)
+                } else {
+                    // 未知指令原样显示
+                    Node::Text(format!("\\{}", name))
+                }
+            }
+        })
+    }
+    fn parse_matrix(&mut self, env: &str) -> ResultType<Node> {
+        let (left, right) = match env {
+            "matrix" | "array" | "aligned" | "cases" => ("", ""),
+            "pmatrix" => ("(", ")"),
+            "bmatrix" => ("[", "]"),
+            "vmatrix" => ("|", "|"),
+            other => return Err(anyhow!("不支持的环境: {}", other).into()),
+        };
+        if env == "array" {
+            // 忽略列格式
+            self.read_raw_group()?;
+        }
+        let mut rows = vec![];
+        let mut row = vec![];
+        loop {
+            row.push(self.parse_sequence(&["\\\\", "\\end"])?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some('&') => {
+                    self.pos += 1;
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let name = self.read_command();
+                    rows.push(std::mem::take(&mut row));
+                    if name == "end" {
+                        self.read_raw_group()?;
+                        break;
+                    }
+                }
+                _ => return Err(anyhow!("环境 {} 未结束", env).into()),
+            }
+        }
+        let matrix = Node::Matrix(rows);
+        Ok(match env {
+            "cases" => Node::Delimited("{".to_string(), Box::new(matrix), String::new()),
+            _ if left.is_empty() => matrix,
+            _ => Node::Delimited(left.to_string(), Box::new(matrix), right.to_string()),
+        })
+    }
+}
@@ -4,6 +4,8 @@ from numpy import *
 import sys
 import warnings
 warnings.filterwarnings("ignore")
+# 使用内置渲染器时由插件负责渲染LaTeX
+LOCAL_RENDER = {LOCAL_RENDER}
 
 
 def render_latex(formula: str) -> bytes:
@@ -22,7 +24,7 @@ def make_result1(expr):
     return {
         "latex": sympy.latex(expr),
         "python_expr": str(expr),
-        "image": render_latex(f"$${sympy.latex(expr)}$$")
+        "image": b"" if LOCAL_RENDER else render_latex(f"$${sympy.latex(expr)}$$")
     }
 
 
@@ -56,6 +58,54 @@ def series(x0, func):
     return make_result1(sympy.series(func, x0=sympy.simplify(x0), n=10, x=x))
 
 
+def definite_integrate(func, lower, upper):
+    import sympy
+    x = sympy.Symbol("x")
+    return make_result1(sympy.integrate(sympy.sympify(func), (x, sympy.sympify(lower), sympy.sympify(upper))))
+
+
+def limit(x0, func, direction):
+    import sympy
+    x = sympy.Symbol("x")
+    return make_result1(sympy.limit(sympy.sympify(func), x, sympy.sympify(x0), dir=direction))
+
+
+def simplify(func):
+    import sympy
+    return make_result1(sympy.simplify(func))
+
+
+def expand(func):
+    import sympy
+    return make_result1(sympy.expand(func))
+
+
+def matrix(operation, rows):
+    import sympy
+    mat = sympy.Matrix([[sympy.sympify(item) for item in row] for row in rows])
+    if operation == "det":
+        return make_result1(mat.det())
+    elif operation == "inv":
+        return make_result1(mat.inv())
+    else:
+        # 特征值 -> 重数
+        return make_result1(mat.eigenvals())
+
+
+def dsolve(equation):
+    import sympy
+    x = sympy.Symbol("x")
+    f = sympy.Function("f")
+    local_names = {"x": x, "f": f}
+    if "=" in equation:
+        lhs, rhs = equation.split("=")
+        expr = sympy.Eq(sympy.sympify(lhs, locals=local_names),
+                        sympy.sympify(rhs, locals=local_names))
+    else:
+        expr = sympy.sympify(equation, locals=local_names)
+    return make_result1(sympy.dsolve(expr, f(x)))
+
+
 def plot(begin, end, funcs):
     import numpy as np
     MATH_NAMES = {
@@ -147,6 +197,55 @@ def plotpe(begin, end, funcs):
     return {"latex": "", "python_expr": "", "image": buf.getvalue()}
 
 
+def plot3d(x_begin, x_end, y_begin, y_end, funcs):
+    import numpy as np
+    MATH_NAMES = {
+        "sin": np.sin,
+        "cos": np.cos,
+        "tan": np.tan,
+        "exp": np.exp,
+        "floor": np.floor,
+        "around": np.around,
+        "log": np.log,
+        "log10": np.log10,
+        "log2": np.log2,
+        "sinh": np.sinh,
+        "cosh": np.cosh,
+        "tanh": np.tanh,
+        "arcsin": np.arcsin,
+        "arccos": np.arccos,
+        "arctan": np.arctan,
+        "arcsinh": np.arcsinh,
+        "arccosh": np.arccosh,
+        "arctanh": np.arctanh,
+        "abs": np.abs,
+        "sqrt": np.sqrt,
+        "log1p": np.log1p,
+        "sign": np.sign,
+        "ceil": np.ceil,
+        "modf": np.modf,
+        "pi": np.pi,
+        "numpy": np
+    }
+    import io
+    import matplotlib.pyplot as plt
+    xs, ys = np.meshgrid(
+        np.linspace(x_begin, x_end, 100),
+        np.linspace(y_begin, y_end, 100)
+    )
+    buf = io.BytesIO()
+    figure = plt.figure(",".join(funcs))
+    axes = figure.add_subplot(projection="3d")
+    for func in funcs:
+        axes.plot_surface(
+            xs, ys,
+            eval(func, None, {"x": xs, "y": ys, **MATH_NAMES}) + np.zeros_like(xs),
+            cmap="viridis", alpha=0.8
+        )
+    figure.canvas.print_png(buf)
+    return {"latex": "", "python_expr": "", "image": buf.getvalue()}
+
+
 # print("started..", file=sys.stderr,flush=True)
 output = {"latex": "", "python_expr": "", "image": b""}
 {CODE}
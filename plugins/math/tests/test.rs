@@ -0,0 +1,35 @@
+use math::render::parser::{Node, Parser};
+
+#[test]
+fn test_parse_latex() {
+    let node = Parser::new(r"\frac{1}{x^2}").parse().unwrap();
+    assert_eq!(
+        node,
+        Node::Fraction(
+            Box::new(Node::Text("1".to_string())),
+            Box::new(Node::Scripts {
+                base: Box::new(Node::Variable('x')),
+                sup: Some(Box::new(Node::Text("2".to_string()))),
+                sub: None,
+            })
+        )
+    );
+    let node = Parser::new(r"\left( a \rightarrow b \right)")
+        .parse()
+        .unwrap();
+    assert!(matches!(node, Node::Delimited(ref l, _, ref r) if l == "(" && r == ")"));
+    assert!(Parser::new(r"\frac{1}{2").parse().is_err());
+    assert!(Parser::new(r"\begin{pmatrix}1&2\\3&4\end{pmatrix}")
+        .parse()
+        .is_ok());
+}
+
+#[test]
+fn test_parse_depth_limit() {
+    let deep = "{".repeat(10000) + "x" + &"}".repeat(10000);
+    assert!(Parser::new(&deep).parse().is_err());
+    assert!(Parser::new(&r"\frac".repeat(10000)).parse().is_err());
+    assert!(Parser::new(&r"\sqrt[".repeat(10000)).parse().is_err());
+    let shallow = "{".repeat(10) + "x" + &"}".repeat(10);
+    assert!(Parser::new(&shallow).parse().is_ok());
+}
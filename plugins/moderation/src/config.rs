@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModerationAction {
+    // 在群内提醒
+    Warn,
+    // 撤回触发规则的消息
+    Recall,
+    // 禁言，单位为秒
+    Ban { duration: u64 },
+    Kick,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GroupRules {
+    pub keywords: Vec<String>,
+    pub regexes: Vec<String>,
+    // flood_seconds 秒内发送超过 flood_count 条消息视为刷屏，为0时不检测
+    pub flood_count: usize,
+    pub flood_seconds: i64,
+    // repeat_seconds 秒内连续发送 repeat_count 条相同消息视为刷屏，为0时不检测
+    pub repeat_count: usize,
+    pub repeat_seconds: i64,
+}
+
+impl Default for GroupRules {
+    fn default() -> Self {
+        Self {
+            keywords: vec![],
+            regexes: vec![],
+            flood_count: 10,
+            flood_seconds: 10,
+            repeat_count: 5,
+            repeat_seconds: 60,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModerationConfig {
+    // 启用的群，为空时不处理任何群
+    pub enable_groups: Vec<i64>,
+    // 群主与管理员不受限制
+    pub exempt_admins: bool,
+    pub exempt_users: Vec<i64>,
+    pub default_rules: GroupRules,
+    // 指定群使用的规则，覆盖default_rules
+    pub group_rules: HashMap<i64, GroupRules>,
+    // 第n次违规执行escalation[n-1]，超出后重复最后一项
+    pub escalation: Vec<Vec<ModerationAction>>,
+    // 超过此时间(秒)的违规记录不再计入次数
+    pub violation_window: i64,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        use ModerationAction::*;
+        Self {
+            enable_groups: vec![],
+            exempt_admins: true,
+            exempt_users: vec![],
+            default_rules: GroupRules::default(),
+            group_rules: HashMap::new(),
+            escalation: vec![
+                vec![Recall, Warn],
+                vec![Recall, Ban { duration: 10 * 60 }],
+                vec![Recall, Ban { duration: 60 * 60 }],
+                vec![Recall, Kick],
+            ],
+            violation_window: 24 * 60 * 60,
+        }
+    }
+}
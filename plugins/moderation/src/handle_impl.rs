@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use countdown_bot3::countdown_bot::{
+    client::{CountdownBotClient, ResultType},
+    event::message::GroupMessageEvent,
+};
+use log::{error, info};
+use rusqlite::Connection;
+use tokio::sync::Mutex;
+
+use crate::{config::ModerationAction, log_impl::save_log, rules::pick_actions, ModerationPlugin};
+
+// 清理过期记录的最小间隔(秒)
+const PRUNE_INTERVAL: i64 = 60;
+
+pub struct Violation {
+    pub reason: String,
+    pub count: usize,
+    pub actions: Vec<ModerationAction>,
+}
+
+impl ModerationPlugin {
+    pub fn should_check(&self, evt: &GroupMessageEvent) -> bool {
+        let config = self.config.as_ref().unwrap();
+        config.enable_groups.contains(&evt.group_id) && !config.exempt_users.contains(&evt.user_id)
+    }
+    // 只更新内存中的记录，不调用任何API，以便尽快释放插件锁
+    pub fn record_message(&mut self, evt: &GroupMessageEvent, now: i64) -> Option<Violation> {
+        self.prune(now);
+        let config = self.config.as_ref().unwrap();
+        let (group_id, user_id) = (evt.group_id, evt.user_id);
+        let rules = self
+            .group_rules
+            .get(&group_id)
+            .unwrap_or_else(|| self.default_rules.as_ref().unwrap());
+        let history = self.history.entry((group_id, user_id)).or_default();
+        let flood_reason = history.push(&rules.rules, &evt.raw_message, now);
+        let reason = rules.check_content(&evt.raw_message).or(flood_reason)?;
+        history.clear();
+        let violations = self.violations.entry((group_id, user_id)).or_default();
+        violations.retain(|t| now - *t < config.violation_window);
+        violations.push(now);
+        let count = violations.len();
+        Some(Violation {
+            reason,
+            count,
+            actions: pick_actions(&config.escalation, count),
+        })
+    }
+    // 丢弃已超出检测窗口的消息记录与违规记录
+    fn prune(&mut self, now: i64) {
+        if now - self.last_prune < PRUNE_INTERVAL {
+            return;
+        }
+        self.last_prune = now;
+        let (default_rules, group_rules) =
+            (self.default_rules.as_ref().unwrap(), &self.group_rules);
+        self.history.retain(|(group_id, _), history| {
+            let rules = group_rules.get(group_id).unwrap_or(default_rules);
+            !history.is_expired(&rules.rules, now)
+        });
+        let window = self.config.as_ref().unwrap().violation_window;
+        self.violations.retain(|_, violations| {
+            violations.retain(|t| now - *t < window);
+            !violations.is_empty()
+        });
+    }
+}
+
+pub async fn handle_violation(
+    client: &CountdownBotClient,
+    database: &Arc<Mutex<Connection>>,
+    evt: &GroupMessageEvent,
+    violation: Violation,
+) -> ResultType<()> {
+    let Violation {
+        reason,
+        count,
+        actions,
+    } = violation;
+    let (group_id, user_id) = (evt.group_id, evt.user_id);
+    info!(
+        "User {} in group {} violated rules ({}), count = {}, actions = {:?}",
+        user_id, group_id, reason, count, actions
+    );
+    let mut performed = vec![];
+    for action in actions.iter() {
+        let (desc, result) = perform_action(client, evt, action, count).await;
+        if let Err(e) = result {
+            error!("Failed to perform {}:\n{}", desc, e);
+            performed.push(format!("{}(失败)", desc));
+        } else {
+            performed.push(desc);
+        }
+    }
+    save_log(
+        database,
+        group_id,
+        user_id,
+        &format!("第{}次违规: {}", count, reason),
+        &performed.join(","),
+        &evt.raw_message,
+    )
+    .await?;
+    Ok(())
+}
+
+async fn perform_action(
+    client: &CountdownBotClient,
+    evt: &GroupMessageEvent,
+    action: &ModerationAction,
+    count: usize,
+) -> (String, ResultType<()>) {
+    match action {
+        // 具体原因只写入日志，群内的警告不能复述违禁词或暴露过滤规则
+        ModerationAction::Warn => (
+            String::from("警告"),
+            client
+                .send_group_msg(
+                    evt.group_id,
+                    &format!(
+                        "[CQ:at,qq={}] 警告: 你的消息违反了群规 (第{}次违规)",
+                        evt.user_id, count
+                    ),
+                    false,
+                )
+                .await
+                .map(|_| ()),
+        ),
+        ModerationAction::Recall => (
+            String::from("撤回"),
+            client.delete_msg(evt.message_id).await,
+        ),
+        ModerationAction::Ban { duration } => (
+            format!("禁言{}秒", duration),
+            client
+                .set_group_ban(evt.group_id as u64, evt.user_id as u64, *duration)
+                .await,
+        ),
+        ModerationAction::Kick => (
+            String::from("踢出"),
+            client
+                .set_group_kick(evt.group_id as u64, evt.user_id as u64, false)
+                .await,
+        ),
+    }
+}
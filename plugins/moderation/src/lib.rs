@@ -0,0 +1,163 @@
+use std::{any::TypeId, collections::HashMap, sync::Arc};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use config::ModerationConfig;
+use countdown_bot3::{
+    countdown_bot::{
+        bot,
+        client::{CountdownBotClient, ResultType},
+        command::{Command, SenderType},
+        event::{
+            manager::{EventListener, WrappedOOPEventContainer},
+            message::{GroupMessageEvent, GroupSenderRole},
+        },
+        plugin::{BotPlugin, BotPluginWrapped, HookResult, PluginMeta},
+        utils::load_config_or_save_default,
+    },
+    export_static_plugin,
+};
+use log::debug;
+use rules::{CompiledRules, MessageHistory};
+use rusqlite::Connection;
+use tokio::sync::Mutex;
+static PLUGIN_NAME: &str = "moderation";
+
+pub mod config;
+mod handle_impl;
+mod log_impl;
+pub mod rules;
+
+#[derive(Default)]
+struct ModerationPlugin {
+    client: Option<CountdownBotClient>,
+    config: Option<ModerationConfig>,
+    default_rules: Option<CompiledRules>,
+    group_rules: HashMap<i64, CompiledRules>,
+    // (群号, QQ)
+    history: HashMap<(i64, i64), MessageHistory>,
+    violations: HashMap<(i64, i64), Vec<i64>>,
+    last_prune: i64,
+    database: Option<Arc<Mutex<Connection>>>,
+}
+
+#[async_trait]
+impl BotPlugin for ModerationPlugin {
+    fn on_enable(
+        &mut self,
+        bot: &mut bot::CountdownBot,
+        _handle: tokio::runtime::Handle,
+    ) -> HookResult<()> {
+        let config: ModerationConfig =
+            load_config_or_save_default(&bot.ensure_plugin_data_dir(PLUGIN_NAME)?)?;
+        debug!("Config: {:#?}", config);
+        self.default_rules = Some(CompiledRules::compile(&config.default_rules)?);
+        for (group_id, rules) in config.group_rules.iter() {
+            self.group_rules
+                .insert(*group_id, CompiledRules::compile(rules)?);
+        }
+        self.config = Some(config);
+        self.database = Some(Arc::new(Mutex::new(log_impl::open_database(
+            &bot.ensure_plugin_data_dir(PLUGIN_NAME)?
+                .join("moderation.db"),
+        )?)));
+        bot.register_event_handler(TypeId::of::<GroupMessageEvent>(), MyEventHandler {});
+        bot.register_command(
+            Command::new("modlog")
+                .group(true)
+                .console(true)
+                .description("查看群管理处理记录 | modlog [数量]"),
+        )?;
+        Ok(())
+    }
+    fn on_before_start(
+        &mut self,
+        _bot: &mut bot::CountdownBot,
+        client: CountdownBotClient,
+    ) -> HookResult<()> {
+        self.client = Some(client);
+        Ok(())
+    }
+    fn get_meta(&self) -> PluginMeta {
+        PluginMeta {
+            author: String::from("officeyutong"),
+            description: String::from("反刷屏与违禁词处理"),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+    async fn on_command(
+        &mut self,
+        _command: String,
+        args: Vec<String>,
+        sender: &SenderType,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let group_id = match sender {
+            SenderType::Console(_) => None,
+            SenderType::Group(evt) => {
+                if !matches!(
+                    self.client.as_ref().unwrap().get_sender_role(evt).await?,
+                    Some(GroupSenderRole::Owner) | Some(GroupSenderRole::Admin)
+                ) {
+                    return Err(anyhow!("只有群主或管理员可以查看处理记录!").into());
+                }
+                Some(evt.group_id)
+            }
+            _ => return Err(anyhow!("请在群内使用此指令!").into()),
+        };
+        let limit = match args.first().filter(|s| !s.is_empty()) {
+            Some(s) => s
+                .parse::<i64>()
+                .map_err(|_| anyhow!("请输入合法的数量!"))?
+                .clamp(1, 50),
+            None => 10,
+        };
+        self.command_modlog(sender, group_id, limit).await?;
+        Ok(())
+    }
+}
+
+export_static_plugin!(PLUGIN_NAME, ModerationPlugin::default());
+
+struct MyEventHandler;
+#[async_trait]
+impl EventListener for MyEventHandler {
+    async fn on_event(
+        &mut self,
+        event: WrappedOOPEventContainer,
+        plugin: BotPluginWrapped,
+    ) -> ResultType<()> {
+        let event_guard = event.read().await.event.clone();
+        let gevt = event_guard.downcast_ref::<GroupMessageEvent>().unwrap();
+        // 调用API期间不持有插件锁
+        let (client, database, exempt_admins) = {
+            let plugin_guard = plugin.read().await;
+            let casted = plugin_guard.downcast_ref::<ModerationPlugin>().unwrap();
+            if !casted.should_check(gevt) {
+                return Ok(());
+            }
+            (
+                casted.client.clone().unwrap(),
+                casted.database.clone().unwrap(),
+                casted.config.as_ref().unwrap().exempt_admins,
+            )
+        };
+        // 无法获取身份时按普通成员处理
+        if exempt_admins
+            && matches!(
+                client.get_sender_role(gevt).await,
+                Ok(Some(GroupSenderRole::Owner)) | Ok(Some(GroupSenderRole::Admin))
+            )
+        {
+            return Ok(());
+        }
+        let violation = {
+            let mut plugin_guard = plugin.write().await;
+            let casted = plugin_guard.downcast_mut::<ModerationPlugin>().unwrap();
+            casted.record_message(gevt, chrono::Local::now().timestamp())
+        };
+        if let Some(violation) = violation {
+            handle_impl::handle_violation(&client, &database, gevt, violation).await?;
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use countdown_bot3::countdown_bot::{client::ResultType, command::SenderType};
+use rusqlite::{params, Connection};
+use tokio::sync::Mutex;
+
+use crate::ModerationPlugin;
+
+pub fn open_database(path: &Path) -> ResultType<Connection> {
+    let conn = Connection::open(path)?;
+    conn.execute(
+        r#"CREATE TABLE IF NOT EXISTS MODERATION_LOG(
+            TIME        INTEGER NOT NULL,
+            GROUP_ID    INTEGER NOT NULL,
+            USER_ID     INTEGER NOT NULL,
+            REASON      TEXT    NOT NULL,
+            ACTIONS     TEXT    NOT NULL,
+            MESSAGE     TEXT    NOT NULL
+        )"#,
+        params![],
+    )?;
+    Ok(conn)
+}
+
+pub async fn save_log(
+    database: &Mutex<Connection>,
+    group_id: i64,
+    user_id: i64,
+    reason: &str,
+    actions: &str,
+    message: &str,
+) -> ResultType<()> {
+    let db = database.lock().await;
+    db.execute(
+        "INSERT INTO MODERATION_LOG (TIME,GROUP_ID,USER_ID,REASON,ACTIONS,MESSAGE) VALUES (?,?,?,?,?,?)",
+        params![
+            chrono::Local::now().timestamp(),
+            group_id,
+            user_id,
+            reason,
+            actions,
+            message
+        ],
+    )?;
+    Ok(())
+}
+
+impl ModerationPlugin {
+    pub async fn command_modlog(
+        &self,
+        sender: &SenderType,
+        group_id: Option<i64>,
+        limit: i64,
+    ) -> ResultType<()> {
+        let lines = {
+            let db = self.database.as_ref().unwrap().lock().await;
+            let mut stmt = db.prepare(
+                "SELECT TIME,GROUP_ID,USER_ID,REASON,ACTIONS FROM MODERATION_LOG \
+                WHERE (? IS NULL OR GROUP_ID = ?) ORDER BY TIME DESC LIMIT ?",
+            )?;
+            let rows = stmt
+                .query_map(params![group_id, group_id, limit], |r| {
+                    let time: i64 = r.get(0)?;
+                    let group_id: i64 = r.get(1)?;
+                    let user_id: i64 = r.get(2)?;
+                    let reason: String = r.get(3)?;
+                    let actions: String = r.get(4)?;
+                    Ok(format!(
+                        "[{}] 群{} 用户{}: {} | {}",
+                        chrono::NaiveDateTime::from_timestamp(time, 0).format("%Y-%m-%d %H:%M:%S"),
+                        group_id,
+                        user_id,
+                        reason,
+                        actions
+                    ))
+                })?
+                .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+            rows
+        };
+        let text = if lines.is_empty() {
+            String::from("暂无处理记录")
+        } else {
+            lines.join("\n")
+        };
+        self.client
+            .as_ref()
+            .unwrap()
+            .quick_send_by_sender(sender, &text)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::client::ResultType;
+use regex::Regex;
+
+use crate::config::{GroupRules, ModerationAction};
+
+pub struct CompiledRules {
+    pub rules: GroupRules,
+    regexes: Vec<Regex>,
+}
+
+impl CompiledRules {
+    pub fn compile(rules: &GroupRules) -> ResultType<Self> {
+        let mut regexes = vec![];
+        for expr in rules.regexes.iter() {
+            regexes
+                .push(Regex::new(expr).map_err(|e| anyhow!("非法的正则表达式 {}: {}", expr, e))?);
+        }
+        Ok(Self {
+            rules: rules.clone(),
+            regexes,
+        })
+    }
+    // 返回违规原因
+    pub fn check_content(&self, text: &str) -> Option<String> {
+        if let Some(keyword) = self
+            .rules
+            .keywords
+            .iter()
+            .find(|v| !v.is_empty() && text.contains(v.as_str()))
+        {
+            return Some(format!("包含违禁词 {}", keyword));
+        }
+        if let Some(expr) = self.regexes.iter().find(|v| v.is_match(text)) {
+            return Some(format!("匹配违禁规则 {}", expr.as_str()));
+        }
+        return None;
+    }
+}
+
+// 单个用户在某个群内的发言记录
+#[derive(Default)]
+pub struct MessageHistory {
+    times: VecDeque<i64>,
+    last_message: Option<String>,
+    repeat_times: usize,
+    repeat_begin: i64,
+}
+
+impl MessageHistory {
+    // 记录一条消息，触发刷屏规则时返回原因
+    pub fn push(&mut self, rules: &GroupRules, text: &str, now: i64) -> Option<String> {
+        self.times.push_back(now);
+        while let Some(front) = self.times.front() {
+            if now - *front >= rules.flood_seconds {
+                self.times.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self.last_message.as_deref() == Some(text)
+            && now - self.repeat_begin < rules.repeat_seconds
+        {
+            self.repeat_times += 1;
+        } else {
+            self.last_message = Some(text.to_string());
+            self.repeat_times = 1;
+            self.repeat_begin = now;
+        }
+        if rules.flood_count > 0 && self.times.len() > rules.flood_count {
+            return Some(format!(
+                "{}秒内发送了超过{}条消息",
+                rules.flood_seconds, rules.flood_count
+            ));
+        }
+        if rules.repeat_count > 0 && self.repeat_times >= rules.repeat_count {
+            return Some(format!("连续发送了{}条相同的消息", self.repeat_times));
+        }
+        return None;
+    }
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+    // 最后一条消息已超出检测窗口，记录可以丢弃
+    pub fn is_expired(&self, rules: &GroupRules, now: i64) -> bool {
+        match self.times.back() {
+            Some(last) => now - *last >= rules.flood_seconds.max(rules.repeat_seconds),
+            None => true,
+        }
+    }
+}
+
+// 第count次违规时执行的操作
+pub fn pick_actions(escalation: &[Vec<ModerationAction>], count: usize) -> Vec<ModerationAction> {
+    if escalation.is_empty() || count == 0 {
+        return vec![];
+    }
+    escalation[(count - 1).min(escalation.len() - 1)].clone()
+}
@@ -0,0 +1,49 @@
+use moderation::{
+    config::{GroupRules, ModerationAction},
+    rules::{pick_actions, CompiledRules, MessageHistory},
+};
+
+#[test]
+fn test_content_rules() {
+    let rules = CompiledRules::compile(&GroupRules {
+        keywords: vec!["广告".to_string()],
+        regexes: vec![r"加群\d+".to_string()],
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(rules.check_content("这是广告").is_some());
+    assert!(rules.check_content("欢迎加群123456").is_some());
+    assert!(rules.check_content("正常消息").is_none());
+}
+
+#[test]
+fn test_flood_and_escalation() {
+    let rules = GroupRules {
+        flood_count: 3,
+        flood_seconds: 10,
+        repeat_count: 0,
+        ..Default::default()
+    };
+    let mut history = MessageHistory::default();
+    for i in 0..3 {
+        assert!(history.push(&rules, &i.to_string(), i).is_none());
+    }
+    assert!(history.push(&rules, "x", 4).is_some());
+    let escalation = vec![vec![ModerationAction::Warn], vec![ModerationAction::Kick]];
+    assert_eq!(pick_actions(&escalation, 1), vec![ModerationAction::Warn]);
+    assert_eq!(pick_actions(&escalation, 5), vec![ModerationAction::Kick]);
+}
+
+#[test]
+fn test_history_expire() {
+    let rules = GroupRules {
+        flood_seconds: 10,
+        repeat_seconds: 30,
+        ..Default::default()
+    };
+    let mut history = MessageHistory::default();
+    assert!(history.is_expired(&rules, 0));
+    history.push(&rules, "x", 100);
+    assert!(!history.is_expired(&rules, 120));
+    assert!(history.is_expired(&rules, 130));
+}
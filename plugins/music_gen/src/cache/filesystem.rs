@@ -0,0 +1,151 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use countdown_bot3::countdown_bot::client::ResultType;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::{is_valid_name, CacheBackend};
+
+const INDEX_NAME: &str = "index.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CacheEntry {
+    size: u64,
+    // 均为unix时间戳(秒)
+    expire_at: i64,
+    last_access: i64,
+}
+
+/*
+存储在本地目录的缓存，索引保存在index.json中
+过期的文件在访问或写入时删除，超出文件数或总大小时按最近访问时间淘汰
+*/
+pub struct FilesystemCache {
+    dir: PathBuf,
+    max_files: u64,
+    max_size: u64,
+    index: Mutex<HashMap<String, CacheEntry>>,
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|v| v.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+impl FilesystemCache {
+    pub fn open(dir: PathBuf, max_files: u64, max_size: u64) -> ResultType<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let index = match std::fs::read_to_string(dir.join(INDEX_NAME)) {
+            Ok(text) => {
+                serde_json::from_str::<HashMap<String, CacheEntry>>(&text).unwrap_or_else(|e| {
+                    error!("缓存索引损坏，已忽略: {}", e);
+                    HashMap::new()
+                })
+            }
+            Err(_) => HashMap::new(),
+        };
+        // 清理不在索引中的文件
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name != INDEX_NAME && !index.contains_key(&name) {
+                std::fs::remove_file(entry.path()).ok();
+            }
+        }
+        info!("已加载 {} 个缓存文件", index.len());
+        Ok(Self {
+            dir,
+            max_files,
+            max_size,
+            index: Mutex::new(index),
+        })
+    }
+    async fn save_index(&self, index: &HashMap<String, CacheEntry>) -> ResultType<()> {
+        tokio::fs::write(self.dir.join(INDEX_NAME), serde_json::to_string(index)?).await?;
+        Ok(())
+    }
+    async fn remove(&self, index: &mut HashMap<String, CacheEntry>, name: &str) {
+        index.remove(name);
+        tokio::fs::remove_file(self.dir.join(name)).await.ok();
+    }
+    // 删除过期文件，并按LRU淘汰直到满足数量与大小限制
+    async fn evict(&self, index: &mut HashMap<String, CacheEntry>) {
+        let current = now();
+        let expired = index
+            .iter()
+            .filter(|(_, v)| v.expire_at <= current)
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<String>>();
+        for name in expired.iter() {
+            self.remove(index, name).await;
+        }
+        loop {
+            let total_size = index.values().map(|v| v.size).sum::<u64>();
+            if index.len() as u64 <= self.max_files && total_size <= self.max_size {
+                break;
+            }
+            let oldest = match index.iter().min_by_key(|(_, v)| v.last_access) {
+                Some((k, _)) => k.clone(),
+                None => break,
+            };
+            info!("淘汰缓存: {}", oldest);
+            self.remove(index, &oldest).await;
+        }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for FilesystemCache {
+    async fn get(&self, name: &str) -> ResultType<Option<Vec<u8>>> {
+        if !is_valid_name(name) {
+            return Err(anyhow!("非法的缓存名: {}", name).into());
+        }
+        let mut index = self.index.lock().await;
+        let entry = match index.get_mut(name) {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        if entry.expire_at <= now() {
+            self.remove(&mut index, name).await;
+            self.save_index(&index).await?;
+            return Ok(None);
+        }
+        entry.last_access = now();
+        match tokio::fs::read(self.dir.join(name)).await {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => {
+                error!("读取缓存文件 {} 失败: {}", name, e);
+                self.remove(&mut index, name).await;
+                self.save_index(&index).await?;
+                Ok(None)
+            }
+        }
+    }
+    async fn put(&self, name: &str, bytes: &[u8], timeout: u64) -> ResultType<()> {
+        if !is_valid_name(name) {
+            return Err(anyhow!("非法的缓存名: {}", name).into());
+        }
+        if bytes.len() as u64 > self.max_size {
+            return Ok(());
+        }
+        let mut index = self.index.lock().await;
+        tokio::fs::write(self.dir.join(name), bytes).await?;
+        let current = now();
+        index.insert(
+            name.to_string(),
+            CacheEntry {
+                size: bytes.len() as u64,
+                expire_at: current + timeout as i64,
+                last_access: current,
+            },
+        );
+        self.evict(&mut index).await;
+        self.save_index(&index).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use std::{path::Path, sync::Arc};
+
+use async_trait::async_trait;
+use countdown_bot3::countdown_bot::client::ResultType;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::MusicGenConfig, encoder::AudioFormat};
+
+pub mod filesystem;
+pub mod redis;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheBackendType {
+    // 存储在插件数据目录下，无需外部服务
+    Filesystem,
+    Redis,
+}
+
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get(&self, name: &str) -> ResultType<Option<Vec<u8>>>;
+    // timeout以秒为单位
+    async fn put(&self, name: &str, bytes: &[u8], timeout: u64) -> ResultType<()>;
+}
+
+pub type CacheWrapped = Arc<dyn CacheBackend>;
+
+pub fn create_cache(config: &MusicGenConfig, data_dir: &Path) -> ResultType<CacheWrapped> {
+    Ok(match config.cache_backend {
+        CacheBackendType::Filesystem => Arc::new(filesystem::FilesystemCache::open(
+            data_dir.join("cache"),
+            config.max_storing_files,
+            config.max_cache_size,
+        )?),
+        CacheBackendType::Redis => Arc::new(self::redis::RedisCache::open(&config.redis_uri)?),
+    })
+}
+
+// 不同格式的编码结果分别缓存，如 <hash>.mp3
+pub fn cache_name(hash: &str, format: AudioFormat) -> String {
+    format!("{}.{}", hash, format.extension())
+}
+
+// 名称来自下载链接，只允许哈希与扩展名中会出现的字符
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.')
+}
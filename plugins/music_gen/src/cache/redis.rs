@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use countdown_bot3::countdown_bot::client::ResultType;
+use redis::AsyncCommands;
+
+use super::CacheBackend;
+
+pub fn make_key(hash: &str) -> String {
+    format!("countdownbot-music-{}", hash)
+}
+
+pub struct RedisCache {
+    client: redis::Client,
+}
+
+impl RedisCache {
+    pub fn open(uri: &str) -> ResultType<Self> {
+        Ok(Self {
+            client: redis::Client::open(uri)?,
+        })
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisCache {
+    async fn get(&self, name: &str) -> ResultType<Option<Vec<u8>>> {
+        let mut conn = self.client.get_async_connection().await?;
+        let key = make_key(name);
+        if conn.exists(&key).await? {
+            let output: Vec<u8> = conn.get(&key).await?;
+            return Ok(Some(output));
+        } else {
+            return Ok(None);
+        }
+    }
+    async fn put(&self, name: &str, bytes: &[u8], timeout: u64) -> ResultType<()> {
+        let key = make_key(name);
+        let mut conn = self.client.get_async_connection().await?;
+        if conn.exists(&key).await? {
+            return Ok(());
+        }
+        conn.set_ex(&key, bytes, timeout as usize).await?;
+        return Ok(());
+    }
+}
@@ -1,27 +1,32 @@
-use std::{
-    io::{Cursor, Read, Seek, SeekFrom},
-    sync::Arc,
-};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use clap::ArgMatches;
 use countdown_bot3::countdown_bot::{
+    artifact::ArtifactStore,
     client::{CountdownBotClient, ResultType},
     command::SenderType,
-    utils::SubUrlWrapper,
 };
 use log::{debug, info};
 use tokio::sync::Semaphore;
-use wav::{BitDepth, Header};
 
 use crate::{
-    cache::{check_from_cache, store_into_cache},
+    cache::{cache_name, CacheWrapped},
     config::MusicGenConfig,
-    notes::transform_notes,
-    pysynth::pysynth_b::WaveRenderer,
+    encoder::{encode_audio, AudioFormat, PcmAudio},
+    import::fetch_score,
+    notes::{parse_major, transform_notes, transform_single_note},
+    pysynth::{
+        effects::{apply_reverb, pan_gains},
+        voices::{Adsr, Instrument, Synth},
+    },
+    render::render_piano_roll,
+    score::{import_score, midi::export_midi, note_to_pitch, ScoreFormat},
     utils::command_hash,
-    MusicGenPlugin,
+    MusicGenPlugin, PLUGIN_NAME,
 };
 use anyhow::anyhow;
+// 速度变化标记，如 t.90 表示从此处开始以90BPM演奏
+const TEMPO_MARK: &str = "t";
 impl MusicGenPlugin {
     pub async fn generate_music(
         &self,
@@ -33,8 +38,8 @@ impl MusicGenPlugin {
         let client = self.client.clone().unwrap();
         let config = self.config.as_ref().unwrap().clone();
         let sender = sender.clone();
-        let redis_client = self.redis_client.as_ref().unwrap().clone();
-        let url_wrapper = self.url_wrapper.clone().unwrap();
+        let cache = self.cache.as_ref().unwrap().clone();
+        let artifacts = self.artifacts.clone().unwrap();
         tokio::spawn(async move {
             let msg = if let Err(e) = generate_music(
                 semaphore,
@@ -43,8 +48,8 @@ impl MusicGenPlugin {
                 args,
                 &sender,
                 using_pasteboard,
-                redis_client,
-                url_wrapper,
+                cache,
+                artifacts,
             )
             .await
             {
@@ -65,26 +70,49 @@ pub(crate) async fn generate_music(
     args: ArgMatches,
     sender: &SenderType,
     using_pasteboard: bool,
-    redis_client: Arc<redis::Client>,
-    url_wrapper: SubUrlWrapper,
+    cache: CacheWrapped,
+    artifacts: ArtifactStore,
 ) -> ResultType<()> {
     let start_time = std::time::Instant::now();
     let _semaphore_permit = semaphore
         .try_acquire()
         .map_err(|_| anyhow!("当前正在执行的生成音乐任务过多，请等待其他任务执行完成后再调用"))?;
-    let notes = args
-        .values_of("NOTES")
-        .ok_or(anyhow!("请输入音符"))?
-        .collect::<Vec<&str>>();
+    let mut use_number = args.is_present("numbered");
+    let mut using_pasteboard = using_pasteboard;
+    let mut default_bpm = config.default_bpm as u32;
+    let notes_by_track: Vec<Vec<String>> = if let Some(source) = args.value_of("import") {
+        let (name, data) = fetch_score(client, sender, source, config).await?;
+        let format = match args.value_of("import-format") {
+            Some(v) => ScoreFormat::from_name(v)?,
+            None => ScoreFormat::detect(&name, &data)?,
+        };
+        let score = import_score(&data, format, config.max_import_tracks as usize)?;
+        // 导入的乐谱总是使用五线谱，且不受消息长度限制
+        use_number = false;
+        using_pasteboard = true;
+        if let Some(v) = score.bpm {
+            default_bpm = v;
+        }
+        score.tracks
+    } else {
+        let notes = args
+            .values_of("NOTES")
+            .ok_or(anyhow!("请输入音符"))?
+            .collect::<Vec<&str>>();
+        notes
+            .split(|v| *v == "|")
+            .map(|track| track.iter().map(|s| s.to_string()).collect())
+            .collect()
+    };
     // debug!("{:?}", notes);
-    let notes_by_track = notes.split(|v| *v == "|").collect::<Vec<&[&str]>>();
-    let use_number = args.is_present("numbered");
+    let notes = notes_by_track.join(&String::from("|"));
+    let notes = notes.iter().map(|v| v.as_str()).collect::<Vec<&str>>();
     let bpm = args
         .value_of("bpm")
         .map(|v| u32::from_str_radix(v, 10))
         .transpose()
         .map_err(|_| anyhow!("请输入合法的BPM!"))?
-        .unwrap_or(config.default_bpm as u32);
+        .unwrap_or(default_bpm);
     let scale = args
         .value_of("scale")
         .map(|v| v.parse())
@@ -106,6 +134,11 @@ pub(crate) async fn generate_music(
         }
     };
     let will_download = args.is_present("download");
+    let will_upload = args.is_present("upload");
+    let midi_only = args.is_present("midi-only");
+    let export_midi = midi_only || args.is_present("midi");
+    let will_render = args.is_present("render");
+    let check_only = args.is_present("check");
     info!("Will download = {}", will_download);
     let inverse_beats = if args.is_present("inverse") {
         Some(if let Some(v) = args.value_of("beats") {
@@ -121,6 +154,73 @@ pub(crate) async fn generate_music(
             return Err(anyhow!("如果您指定音量分配占比，那么音量数必须与音轨数相同").into());
         }
     }
+    let instruments = match args.value_of("instruments") {
+        Some(v) => {
+            let mut output = Vec::<Instrument>::new();
+            for s in v.split(",") {
+                output.push(Instrument::from_name(s)?);
+            }
+            if output.len() == 1 {
+                vec![output[0]; notes_by_track.len()]
+            } else if output.len() == notes_by_track.len() {
+                output
+            } else {
+                return Err(anyhow!("音色数必须为1或与音轨数相同").into());
+            }
+        }
+        None => vec![Instrument::Piano; notes_by_track.len()],
+    };
+    let pan = match args.value_of("pan") {
+        Some(v) => {
+            let mut output = Vec::<f64>::new();
+            for s in v.split(",") {
+                let val = s
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|v| (-1.0..=1.0).contains(v))
+                    .ok_or(anyhow!("非法声像: {}，取值范围为-1到1", s))?;
+                output.push(val);
+            }
+            if output.len() != notes_by_track.len() {
+                return Err(anyhow!("如果您指定声像，那么声像数必须与音轨数相同").into());
+            }
+            Some(output)
+        }
+        None => None,
+    };
+    let reverb = match args.value_of("reverb") {
+        Some(v) => {
+            let (wet, room_size) = match v.split_once(",") {
+                Some((a, b)) => (a.parse::<f64>().ok(), b.parse::<f64>().ok()),
+                None => (v.parse::<f64>().ok(), Some(config.default_room_size)),
+            };
+            match (wet, room_size) {
+                (Some(a), Some(b)) if (0.0..=1.0).contains(&a) && (0.0..=1.0).contains(&b) => {
+                    Some((a, b))
+                }
+                _ => {
+                    return Err(
+                        anyhow!("混响参数格式为 湿声比例[,房间大小]，取值范围均为0到1").into(),
+                    )
+                }
+            }
+        }
+        None => None,
+    };
+    let adsr = args
+        .value_of("adsr")
+        .map(Adsr::parse)
+        .transpose()?
+        .unwrap_or(config.default_adsr);
+    // 未使用音色与效果时保持原有的缓存键
+    let effects = if ["instruments", "pan", "reverb", "adsr"]
+        .iter()
+        .any(|v| args.is_present(v))
+    {
+        format!("{:?},{:?},{:?},{:?}", instruments, pan, reverb, adsr)
+    } else {
+        String::new()
+    };
     let this_hash = command_hash(
         &notes,
         use_number,
@@ -129,78 +229,101 @@ pub(crate) async fn generate_music(
         major,
         &volume,
         &inverse_beats,
+        &effects,
     );
-    let mut using_cache = false;
-    let mut out_bytes: Vec<u8> = vec![];
+    let note_count: usize = notes_by_track.iter().map(|x| x.len()).sum();
+    if check_only {
+        check_notes(&notes_by_track, use_number, major, &inverse_beats, bpm)?;
+    }
+    {
+        let group_id = match sender {
+            SenderType::Group(v) => Some(v.group_id),
+            _ => None,
+        };
+        let this_group_note_count_limit = group_id
+            .and_then(|v| config.group_limits.get(&v))
+            .cloned()
+            .unwrap_or(config.max_notes as i64);
+        if note_count as i64 > this_group_note_count_limit {
+            return Err(anyhow!(
+                "本群音符数上限为 {} 个! 你的文件一共包含 {} 个音符",
+                this_group_note_count_limit,
+                note_count
+            )
+            .into());
+        }
+    }
+    if !using_pasteboard && note_count > config.max_notes_through_message as usize {
+        return Err(anyhow!("消息过长！请使用剪贴板传递参数").into());
+    }
+    let (processed_tracks, max_len) = prepare_tracks(
+        &notes_by_track,
+        use_number,
+        major,
+        &inverse_beats,
+        bpm,
+        config,
+    )?;
+    if will_render {
+        let tracks = processed_tracks.clone();
+        let image = tokio::task::spawn_blocking(move || render_piano_roll(&tracks))
+            .await?
+            .map_err(|e| anyhow!("绘制乐谱时发生错误: {}", e))?;
+        client
+            .quick_send_by_sender_ex(
+                sender,
+                &format!("[CQ:image,file=base64://{}]", base64::encode(image)),
+                false,
+            )
+            .await?;
+    }
+    if check_only {
+        client
+            .quick_send_by_sender(
+                sender,
+                &format!(
+                    "检查通过: 共计{}个音轨, {}个音符, 最长的音轨长度为{}秒",
+                    processed_tracks.len(),
+                    note_count,
+                    (max_len * 60.0) as i32
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+    if export_midi {
+        send_midi(client, sender, &processed_tracks, bpm, &this_hash).await?;
+        if midi_only {
+            return Ok(());
+        }
+    }
+    // 语音、下载链接与群文件分别使用各自配置的格式
+    let mut formats = vec![config.record_format];
+    if will_download {
+        formats.push(config.download_format);
+    }
+    if will_upload {
+        formats.push(config.upload_format);
+    }
+    let mut encoded = HashMap::<AudioFormat, Vec<u8>>::new();
     if config.use_cache {
-        if let Some(cached) = check_from_cache(redis_client.clone(), &this_hash)
-            .await
-            .map_err(|e| anyhow!("检验缓存时发生错误: {}", e))?
-        {
-            out_bytes = cached;
-            using_cache = true;
+        for format in formats.iter() {
+            if let Some(cached) = cache
+                .get(&cache_name(&this_hash, *format))
+                .await
+                .map_err(|e| anyhow!("检验缓存时发生错误: {}", e))?
+            {
+                encoded.insert(*format, cached);
+            }
         }
     }
+    let using_cache = formats.iter().all(|v| encoded.contains_key(v));
     if using_cache {
         client
             .quick_send_by_sender(sender, "缓存命中，发送中...")
             .await?;
     }
     if !using_cache {
-        let group_id = match sender {
-            SenderType::Group(v) => v.group_id,
-            _ => todo!(),
-        };
-
-        let note_count: usize = notes_by_track.iter().map(|x| x.len()).sum();
-        {
-            let this_group_note_count_limit = *config
-                .group_limits
-                .get(&group_id)
-                .unwrap_or(&(config.max_notes as i64));
-            if note_count as i64 > this_group_note_count_limit {
-                return Err(anyhow!(
-                    "本群音符数上限为 {} 个! 你的文件一共包含 {} 个音符",
-                    this_group_note_count_limit,
-                    note_count
-                )
-                .into());
-            }
-        }
-        if !using_pasteboard && note_count > config.max_notes_through_message as usize {
-            return Err(anyhow!("消息过长！请使用剪贴板传递参数").into());
-        }
-        let transformed_number = if use_number {
-            let mut tracks: Vec<Vec<String>> = vec![];
-            for raw_track in notes_by_track.iter() {
-                tracks.push(transform_notes(raw_track, major)?);
-            }
-            tracks
-        } else {
-            notes_by_track
-                .iter()
-                .map(|f| f.iter().map(|s| s.to_string()).collect::<Vec<String>>())
-                .collect::<Vec<Vec<String>>>()
-        };
-        let mut processed_tracks: Vec<Vec<(String, f64)>> = vec![];
-        let mut max_len: f64 = 0.0;
-        for (index, track) in transformed_number.iter().enumerate() {
-            // proessed_tracks.push();
-            let (data, length) = parse_track(&track[..], &inverse_beats, bpm)?;
-            if length * 60.0 > config.max_length_in_seconds as f64 {
-                return Err(anyhow!(
-                    "音轨 {} 的长度({}s)超出了长度限制 ({}s)",
-                    index + 1,
-                    length * 60.0,
-                    config.max_length_in_seconds
-                )
-                .into());
-            }
-            max_len = max_len.max(length);
-            info!("音轨 {}: {}音符，{}分钟", index, data.len(), length);
-            processed_tracks.push(data);
-        }
-        // info!("{:#?}", processed_tracks);
         client
             .quick_send_by_sender(
                 sender,
@@ -215,77 +338,56 @@ pub(crate) async fn generate_music(
         let mut rendered_data = Vec::<Vec<i16>>::new();
         for (index, track) in processed_tracks.into_iter().enumerate() {
             info!("渲染音轨 {} 中.. 音符数 {}", index + 1, track.len());
+            let synth = Synth {
+                instrument: instruments[index],
+                bpm: bpm as i32,
+                adsr,
+                wavetable: config.wavetable.clone(),
+            };
             rendered_data.push(
                 tokio::task::spawn_blocking(move || {
-                    WaveRenderer::default()
-                        .set_bpm(bpm.clone() as i32)
+                    synth
                         .make_wav(&track[..])
                         .map_err(|e| anyhow!("渲染音轨 {} 时发生错误:\n{}", index.clone() + 1, e))
                 })
                 .await??,
             );
         }
-
-        let mut final_output = Vec::<i16>::new();
-
-        let max_len = rendered_data
-            .iter()
-            .map(|v| v.len())
-            .max()
-            .ok_or(anyhow!("零个音轨，玩你妈呢？"))?;
-
-        final_output.resize(max_len, 0);
-        let volume_sum = volume
-            .as_ref()
-            .map(|v| v.iter().sum())
-            .unwrap_or(rendered_data.len() as u32);
+        if rendered_data.is_empty() {
+            return Err(anyhow!("零个音轨，玩你妈呢？").into());
+        }
         info!("合并中..");
-        let mid_val = tokio::task::spawn_blocking(move || {
-            let mut mid_val = Vec::<i32>::new();
-            mid_val.resize(max_len, 0);
-            for (i, val) in rendered_data.into_iter().enumerate() {
-                let curr_volume = volume.as_ref().map(|v| v[i]).unwrap_or(1);
-                info!("音轨 {} 输出采样点数 {}", i + 1, val.len());
-                for (j, v) in val.iter().enumerate() {
-                    mid_val[j] += curr_volume as i32 * *v as i32;
-                }
-            }
-            mid_val
+        let channel_count: u16 = if pan.is_some() { 2 } else { 1 };
+        let final_output = tokio::task::spawn_blocking(move || {
+            mix_tracks(rendered_data, &volume, &pan, reverb, scale)
         })
         .await?;
-        for (i, v) in mid_val.iter().enumerate() {
-            final_output[i] = (*v / volume_sum as i32) as i16;
-        }
-        if scale != 1.0 {
-            for v in final_output.iter_mut() {
-                let mut s = *v as f64 * scale;
-                if s > i32::MAX as f64 {
-                    s = s * i16::MAX as f64 / i32::MAX as f64;
-                }
-                *v = s as i16;
+        let missing = formats
+            .iter()
+            .filter(|v| !encoded.contains_key(*v))
+            .cloned()
+            .collect::<Vec<AudioFormat>>();
+        let (mp3_bitrate, opus_bitrate) = (config.mp3_bitrate, config.opus_bitrate);
+        info!("编码中.. 格式 {:?}", missing);
+        let encoded_loc = tokio::task::spawn_blocking(move || {
+            let audio = PcmAudio {
+                samples: final_output,
+                channels: channel_count,
+                sample_rate: 44100,
+            };
+            let mut result = vec![];
+            for format in missing.into_iter() {
+                let bitrate = match format {
+                    AudioFormat::Mp3 => mp3_bitrate,
+                    AudioFormat::Opus => opus_bitrate,
+                    AudioFormat::Wav => 0,
+                };
+                result.push((format, encode_audio(&audio, format, bitrate)?));
             }
-        }
-        let out_bytes_loc = {
-            let mut outbuf = Cursor::<Vec<u8>>::new(Vec::new());
-            wav::write(
-                Header {
-                    channel_count: 1,
-                    sampling_rate: 44100,
-                    bytes_per_sample: 2,
-                    bits_per_sample: 16,
-                    audio_format: 1,
-                    bytes_per_second: 88200,
-                },
-                &BitDepth::Sixteen(final_output),
-                &mut outbuf,
-            )
-            .map_err(|e| anyhow!("生成wav时发生错误: {}", e))?;
-            let mut outval = Vec::<u8>::new();
-            outbuf.seek(SeekFrom::Start(0)).unwrap();
-            outbuf.read_to_end(&mut outval).unwrap();
-            // tokio::fs::write("out.wav", outval).await?;
-            outval
-        };
+            Ok::<_, anyhow::Error>(result)
+        })
+        .await?
+        .map_err(|e| anyhow!("编码音频时发生错误: {}", e))?;
         let total_seconds = start_time.elapsed().as_secs();
         client
             .quick_send_by_sender(
@@ -293,34 +395,60 @@ pub(crate) async fn generate_music(
                 &format!("生成共耗时 {} 秒，发送中..", total_seconds),
             )
             .await?;
-        out_bytes = out_bytes_loc;
+        encoded.extend(encoded_loc);
     };
 
     client
         .quick_send_by_sender_ex(
             sender,
-            &format!("[CQ:record,file=base64://{}]", base64::encode(&out_bytes)),
+            &format!(
+                "[CQ:record,file=base64://{}]",
+                base64::encode(&encoded[&config.record_format])
+            ),
             false,
         )
         .await?;
-    if config.use_cache || will_download {
-        store_into_cache(
-            redis_client.clone(),
-            &this_hash,
-            &out_bytes[..],
-            config.cache_timeout as usize,
-        )
-        .await
-        .map_err(|e| anyhow!("存储到Redis时发生错误: {}", e))?;
+    if config.use_cache {
+        for (format, bytes) in encoded.iter() {
+            cache
+                .put(
+                    &cache_name(&this_hash, *format),
+                    &bytes[..],
+                    config.cache_timeout,
+                )
+                .await
+                .map_err(|e| anyhow!("存储到缓存时发生错误: {}", e))?;
+        }
+    }
+    if will_upload {
+        let name = format!(
+            "music_{}.{}",
+            &this_hash[..8],
+            config.upload_format.extension()
+        );
+        upload_to_group(client, sender, &name, &encoded[&config.upload_format]).await?;
+        client
+            .quick_send_by_sender(sender, &format!("音频已上传到群文件: {}", name))
+            .await?;
     }
     if will_download {
+        let name = format!(
+            "music_{}.{}",
+            &this_hash[..8],
+            config.download_format.extension()
+        );
+        let url = artifacts
+            .put(
+                PLUGIN_NAME,
+                &encoded[&config.download_format],
+                config.download_format.mime_type(),
+                Some(&name),
+                Some(Duration::from_secs(config.cache_timeout)),
+            )
+            .await?;
         client
             .quick_send_by_sender(sender, &{
-                let s = format!(
-                    "下载地址 ({} 秒内有效): {}",
-                    config.cache_timeout,
-                    url_wrapper.get_sub_url(&format!("music_gen/download/{}", this_hash)),
-                );
+                let s = format!("下载地址 ({} 秒内有效): {}", config.cache_timeout, url);
                 debug!("Download message: {}", s);
                 s
             })
@@ -329,15 +457,258 @@ pub(crate) async fn generate_music(
     return Ok(());
     // todo!();
 }
+// 混合各音轨并转换为交错的采样，指定声像时输出立体声
+fn mix_tracks(
+    rendered_data: Vec<Vec<i16>>,
+    volume: &Option<Vec<u32>>,
+    pan: &Option<Vec<f64>>,
+    reverb: Option<(f64, f64)>,
+    scale: f64,
+) -> Vec<i16> {
+    let max_len = rendered_data.iter().map(|v| v.len()).max().unwrap_or(0);
+    let volume_sum = volume
+        .as_ref()
+        .map(|v| v.iter().sum())
+        .unwrap_or(rendered_data.len() as u32);
+    let channel_count = if pan.is_some() { 2 } else { 1 };
+    let mut mixed = vec![vec![0.0f64; max_len]; channel_count];
+    for (i, val) in rendered_data.into_iter().enumerate() {
+        let curr_volume = volume.as_ref().map(|v| v[i]).unwrap_or(1) as f64;
+        let gains = match pan {
+            Some(v) => {
+                let (left, right) = pan_gains(v[i]);
+                vec![left, right]
+            }
+            None => vec![1.0],
+        };
+        info!("音轨 {} 输出采样点数 {}", i + 1, val.len());
+        for (channel, gain) in mixed.iter_mut().zip(gains.into_iter()) {
+            for (j, v) in val.iter().enumerate() {
+                channel[j] += curr_volume * gain * *v as f64;
+            }
+        }
+    }
+    for channel in mixed.iter_mut() {
+        for v in channel.iter_mut() {
+            *v /= volume_sum as f64;
+        }
+        if let Some((wet, room_size)) = reverb {
+            apply_reverb(channel, wet, room_size);
+        }
+    }
+    let mut final_output = Vec::<i16>::with_capacity(max_len * channel_count);
+    for j in 0..max_len {
+        for channel in mixed.iter() {
+            let mut v = channel[j] as i16;
+            if scale != 1.0 {
+                let mut s = v as f64 * scale;
+                if s > i32::MAX as f64 {
+                    s = s * i16::MAX as f64 / i32::MAX as f64;
+                }
+                v = s as i16;
+            }
+            final_output.push(v);
+        }
+    }
+    final_output
+}
+fn prepare_tracks(
+    notes_by_track: &[Vec<String>],
+    use_number: bool,
+    major: &str,
+    inverse_beats: &Option<i64>,
+    bpm: u32,
+    config: &MusicGenConfig,
+) -> ResultType<(Vec<Vec<(String, f64)>>, f64)> {
+    let transformed_number = if use_number {
+        let mut tracks: Vec<Vec<String>> = vec![];
+        for raw_track in notes_by_track.iter() {
+            let raw_track = raw_track.iter().map(|v| v.as_str()).collect::<Vec<&str>>();
+            tracks.push(transform_notes(&raw_track, major)?);
+        }
+        tracks
+    } else {
+        notes_by_track.to_vec()
+    };
+    let mut parsed_tracks = vec![];
+    let mut tempo_changes = vec![];
+    for track in transformed_number.iter() {
+        parsed_tracks.push(parse_track(
+            &track[..],
+            inverse_beats,
+            bpm,
+            &mut tempo_changes,
+        )?);
+    }
+    let mut processed_tracks: Vec<Vec<(String, f64)>> = vec![];
+    let mut max_len: f64 = 0.0;
+    for (index, (data, length)) in parsed_tracks.into_iter().enumerate() {
+        // proessed_tracks.push();
+        let (data, length) = if tempo_changes.is_empty() {
+            (data, length)
+        } else {
+            apply_tempo(data, &tempo_changes, bpm)
+        };
+        if length * 60.0 > config.max_length_in_seconds as f64 {
+            return Err(anyhow!(
+                "音轨 {} 的长度({}s)超出了长度限制 ({}s)",
+                index + 1,
+                length * 60.0,
+                config.max_length_in_seconds
+            )
+            .into());
+        }
+        max_len = max_len.max(length);
+        info!("音轨 {}: {}音符，{}分钟", index, data.len(), length);
+        processed_tracks.push(data);
+    }
+    // info!("{:#?}", processed_tracks);
+    return Ok((processed_tracks, max_len));
+}
+async fn upload_to_group(
+    client: &CountdownBotClient,
+    sender: &SenderType,
+    name: &str,
+    data: &[u8],
+) -> ResultType<()> {
+    let group_id = match sender {
+        SenderType::Group(v) => v.group_id,
+        _ => return Err(anyhow!("仅可在群内上传文件!").into()),
+    };
+    let working_dir = tempfile::tempdir()?;
+    let path = working_dir.path().join(name);
+    tokio::fs::write(&path, data).await?;
+    client
+        .upload_group_file(
+            group_id,
+            path.to_str().ok_or(anyhow!("非法的文件路径"))?,
+            name,
+        )
+        .await?;
+    return Ok(());
+}
+// 导出MIDI文件并上传到群文件
+async fn send_midi(
+    client: &CountdownBotClient,
+    sender: &SenderType,
+    tracks: &[Vec<(String, f64)>],
+    bpm: u32,
+    hash: &str,
+) -> ResultType<()> {
+    let data = export_midi(tracks, bpm)?;
+    let name = format!("music_{}.mid", &hash[..8]);
+    upload_to_group(client, sender, &name, &data).await?;
+    client
+        .quick_send_by_sender(sender, &format!("MIDI文件已上传到群文件: {}", name))
+        .await?;
+    return Ok(());
+}
+/*
+逐个检查音符，报告第一个非法音符所在的音轨与位置
+与合成时使用相同的解析流程，并额外检查音符名是否存在
+*/
+fn check_notes(
+    notes_by_track: &[Vec<String>],
+    use_number: bool,
+    major: &str,
+    inverse_beats: &Option<i64>,
+    bpm: u32,
+) -> ResultType<()> {
+    let major_height = if use_number { parse_major(major)? } else { 0 };
+    for (track_index, track) in notes_by_track.iter().enumerate() {
+        for (note_index, note) in track.iter().enumerate() {
+            let check = || -> ResultType<()> {
+                let note = if use_number && !note.contains("r") && !note.starts_with("t.") {
+                    transform_single_note(note.trim(), major_height)?
+                } else {
+                    note.clone()
+                };
+                let (parsed, _) = parse_track(&[note], inverse_beats, bpm, &mut vec![])?;
+                for (name, _) in parsed.iter() {
+                    note_to_pitch(name)?;
+                }
+                Ok(())
+            };
+            if let Err(e) = check() {
+                return Err(anyhow!(
+                    "音轨 {} 的第 {} 个音符 \"{}\" 有误: {}",
+                    track_index + 1,
+                    note_index + 1,
+                    note,
+                    e
+                )
+                .into());
+            }
+        }
+    }
+    return Ok(());
+}
+// 音符周期对应的四分音符数，负数表示附点
+pub(crate) fn beats_of(duration: f64) -> f64 {
+    if duration < 0.0 {
+        4.0 / (-2.0 * duration / 3.0)
+    } else {
+        4.0 / duration
+    }
+}
+/*
+根据所有音轨中的速度变化重新计算音符周期，使渲染时可以使用统一的BPM
+tempo_changes为(四分音符位置, BPM)
+*/
+fn apply_tempo(
+    track: Vec<(String, f64)>,
+    tempo_changes: &[(f64, f64)],
+    bpm: u32,
+) -> (Vec<(String, f64)>, f64) {
+    let mut changes = vec![(0.0, bpm as f64)];
+    changes.extend_from_slice(tempo_changes);
+    changes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    // 从0到指定位置经过的分钟数
+    let minutes_at = |position: f64| {
+        let mut result = 0.0;
+        for (i, (start, tempo)) in changes.iter().enumerate() {
+            if *start >= position {
+                break;
+            }
+            let end = changes
+                .get(i + 1)
+                .map(|v| v.0)
+                .unwrap_or(f64::MAX)
+                .min(position);
+            result += (end - start) / tempo;
+        }
+        result
+    };
+    let mut result = vec![];
+    let mut position = 0.0;
+    for (name, duration) in track.into_iter() {
+        let beats = beats_of(duration);
+        let minutes = minutes_at(position + beats) - minutes_at(position);
+        position += beats;
+        result.push((name, 4.0 / (minutes * bpm as f64)));
+    }
+    (result, minutes_at(position))
+}
 fn parse_track(
     track: &[String],
     beats: &Option<i64>,
     bpm: u32,
+    tempo_changes: &mut Vec<(f64, f64)>,
 ) -> ResultType<(Vec<(String, f64)>, f64)> {
     let mut result: Vec<(String, f64)> = vec![];
     let mut total_minutes = 0.0f64;
+    let mut position = 0.0f64;
     for note in track.iter() {
         let (note_name, duration) = note.split_once(".").ok_or(anyhow!("非法音符: {}", note))?;
+        if note_name == TEMPO_MARK {
+            let tempo = duration
+                .parse::<f64>()
+                .ok()
+                .filter(|v| *v >= 1.0)
+                .ok_or(anyhow!("非法速度: {}", duration))?;
+            tempo_changes.push((position, tempo));
+            continue;
+        }
         let mut parsed_duration: f64 = (if duration.starts_with(".") {
             format!("0{}", duration)
         } else {
@@ -352,6 +723,7 @@ fn parse_track(
             return Err(anyhow!("abs(Duration) >= 0.1").into());
         }
         total_minutes += 4.0 / parsed_duration / (bpm as f64);
+        position += beats_of(parsed_duration);
         result.push((note_name.to_string(), parsed_duration));
     }
     return Ok((result, total_minutes));
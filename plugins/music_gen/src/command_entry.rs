@@ -1,8 +1,10 @@
 use clap::{App, Arg};
-use countdown_bot3::countdown_bot::{client::ResultType, command::SenderType};
+use countdown_bot3::countdown_bot::{
+    client::ResultType, command::SenderType, utils::fetch_luogu_pasteboard,
+};
 use log::error;
 
-use crate::{help::HELP_STR, luogu_fetcher::fetch_luogu_pasteboard, MusicGenPlugin};
+use crate::{help::HELP_STR, MusicGenPlugin};
 use anyhow::anyhow;
 use async_recursion::async_recursion;
 
@@ -57,6 +59,12 @@ impl MusicGenPlugin {
                     .short('d')
                     .help("下载音乐"),
             )
+            .arg(
+                Arg::new("upload")
+                    .long("upload")
+                    .short('u')
+                    .help("上传到群文件"),
+            )
             .arg(
                 Arg::new("inverse")
                     .long("inverse")
@@ -77,6 +85,41 @@ impl MusicGenPlugin {
                     .help("振幅缩放")
                     .takes_value(true),
             )
+            .arg(
+                Arg::new("import")
+                    .long("import")
+                    .help("导入乐谱")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::new("import-format")
+                    .long("import-format")
+                    .help("乐谱格式")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::new("instruments")
+                    .long("instruments")
+                    .help("各音轨的音色")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::new("pan")
+                    .long("pan")
+                    .help("各音轨的声像")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::new("reverb")
+                    .long("reverb")
+                    .help("混响")
+                    .takes_value(true),
+            )
+            .arg(Arg::new("adsr").long("adsr").help("包络").takes_value(true))
+            .arg(Arg::new("midi").long("midi").help("同时导出MIDI"))
+            .arg(Arg::new("midi-only").long("midi-only").help("仅导出MIDI"))
+            .arg(Arg::new("render").long("render").help("绘制乐谱"))
+            .arg(Arg::new("check").long("check").help("仅检查音符"))
             .arg(
                 Arg::new("NOTES")
                     .multiple_values(true)
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+
+use crate::{cache::CacheBackendType, encoder::AudioFormat, pysynth::voices::Adsr};
 // #[derive(Deserialize, Serialize, Clone)]
 // pub struct DownloadInfo {
 
@@ -18,13 +20,34 @@ pub struct MusicGenConfig {
     pub max_notes: u64,
     pub max_notes_through_message: u64,
     pub default_volume: f64,
+    // 缓存后端，使用redis时需要配置redis_uri
+    pub cache_backend: CacheBackendType,
     pub redis_uri: String,
     pub cache_timeout: u64,
+    // 文件系统缓存的最大文件数与总大小(字节)，超出时淘汰最久未使用的文件
     pub max_storing_files: u64,
+    pub max_cache_size: u64,
     pub group_limits: HashMap<i64, i64>,
     pub max_length_in_seconds: u64,
     pub max_execute_sametime: u64,
     pub use_cache: bool,
+    // 导入乐谱时的声部数上限与文件大小上限(字节)
+    pub max_import_tracks: u64,
+    pub import_file_size_limit: i64,
+    // 允许从URL导入乐谱的域名(包括其子域名)，为空时允许除内网地址外的任意地址
+    pub import_allowed_hosts: Vec<String>,
+    // 除钢琴外的音色使用的默认包络
+    pub default_adsr: Adsr,
+    pub default_room_size: f64,
+    // 波表音色的各次谐波振幅
+    pub wavetable: Vec<f64>,
+    // 语音消息、下载链接与群文件分别使用的音频格式
+    pub record_format: AudioFormat,
+    pub download_format: AudioFormat,
+    pub upload_format: AudioFormat,
+    // 码率，以kbps为单位
+    pub mp3_bitrate: u32,
+    pub opus_bitrate: u32,
     // pub download: DownloadInfo,
 }
 impl Default for MusicGenConfig {
@@ -34,13 +57,26 @@ impl Default for MusicGenConfig {
             max_notes: 500,
             max_notes_through_message: 30,
             default_volume: 1.0,
+            cache_backend: CacheBackendType::Filesystem,
             cache_timeout: 3 * 60,
             max_storing_files: 10,
+            max_cache_size: 256 << 20,
             group_limits: Default::default(),
             max_length_in_seconds: 6 * 60,
             redis_uri: String::from("redis://127.0.0.1/0"),
             max_execute_sametime: 2,
             use_cache: false,
+            max_import_tracks: 8,
+            import_file_size_limit: 1 << 20,
+            import_allowed_hosts: vec![],
+            default_adsr: Adsr::default(),
+            default_room_size: 0.5,
+            wavetable: vec![1.0, 0.5, 0.33, 0.25, 0.2, 0.1, 0.05],
+            record_format: AudioFormat::Mp3,
+            download_format: AudioFormat::Mp3,
+            upload_format: AudioFormat::Mp3,
+            mp3_bitrate: 128,
+            opus_bitrate: 64,
             // download: DownloadInfo::default(),
         }
     }
@@ -0,0 +1,226 @@
+use std::io::Cursor;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use wav::{BitDepth, Header};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    Wav,
+    Mp3,
+    // Ogg封装的Opus
+    Opus,
+}
+
+impl AudioFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "ogg",
+        }
+    }
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "audio/wave",
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Opus => "audio/ogg",
+        }
+    }
+}
+
+pub struct PcmAudio {
+    // 多声道时为交错存储的采样
+    pub samples: Vec<i16>,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+// bitrate以kbps为单位，对wav无效
+pub fn encode_audio(
+    audio: &PcmAudio,
+    format: AudioFormat,
+    bitrate: u32,
+) -> anyhow::Result<Vec<u8>> {
+    match format {
+        AudioFormat::Wav => encode_wav(audio),
+        AudioFormat::Mp3 => encode_mp3(audio, bitrate),
+        AudioFormat::Opus => encode_opus(audio, bitrate),
+    }
+}
+
+fn encode_wav(audio: &PcmAudio) -> anyhow::Result<Vec<u8>> {
+    let mut outbuf = Cursor::<Vec<u8>>::new(Vec::new());
+    wav::write(
+        Header {
+            channel_count: audio.channels,
+            sampling_rate: audio.sample_rate,
+            bytes_per_sample: 2 * audio.channels,
+            bits_per_sample: 16,
+            audio_format: 1,
+            bytes_per_second: audio.sample_rate * 2 * audio.channels as u32,
+        },
+        &BitDepth::Sixteen(audio.samples.clone()),
+        &mut outbuf,
+    )
+    .map_err(|e| anyhow!("生成wav时发生错误: {}", e))?;
+    Ok(outbuf.into_inner())
+}
+
+fn encode_mp3(audio: &PcmAudio, bitrate: u32) -> anyhow::Result<Vec<u8>> {
+    use mp3lame_encoder::{Birtate, Builder, FlushNoGap, InterleavedPcm, MonoPcm};
+    // LAME只接受固定的几档码率，取不超过配置值的最大一档
+    let brate = match bitrate {
+        0..=63 => Birtate::Kbps48,
+        64..=95 => Birtate::Kbps64,
+        96..=127 => Birtate::Kbps96,
+        128..=159 => Birtate::Kbps128,
+        160..=191 => Birtate::Kbps160,
+        192..=255 => Birtate::Kbps192,
+        256..=319 => Birtate::Kbps256,
+        _ => Birtate::Kbps320,
+    };
+    let mut builder = Builder::new().ok_or(anyhow!("初始化MP3编码器失败"))?;
+    builder
+        .set_num_channels(audio.channels as u8)
+        .map_err(|e| anyhow!("设置声道数失败: {:?}", e))?;
+    builder
+        .set_sample_rate(audio.sample_rate)
+        .map_err(|e| anyhow!("设置采样率失败: {:?}", e))?;
+    builder
+        .set_brate(brate)
+        .map_err(|e| anyhow!("设置码率失败: {:?}", e))?;
+    let mut encoder = builder
+        .build()
+        .map_err(|e| anyhow!("初始化MP3编码器失败: {:?}", e))?;
+    let frames = audio.samples.len() / audio.channels as usize;
+    let mut output = Vec::<u8>::new();
+    output.reserve(mp3lame_encoder::max_required_buffer_size(frames));
+    let encoded = if audio.channels == 1 {
+        encoder.encode(MonoPcm(&audio.samples), output.spare_capacity_mut())
+    } else {
+        encoder.encode(InterleavedPcm(&audio.samples), output.spare_capacity_mut())
+    }
+    .map_err(|e| anyhow!("MP3编码失败: {:?}", e))?;
+    // 编码器保证写入了encoded个字节
+    unsafe {
+        output.set_len(output.len() + encoded);
+    }
+    output.reserve(7200);
+    let flushed = encoder
+        .flush::<FlushNoGap>(output.spare_capacity_mut())
+        .map_err(|e| anyhow!("MP3编码失败: {:?}", e))?;
+    unsafe {
+        output.set_len(output.len() + flushed);
+    }
+    Ok(output)
+}
+
+const OPUS_SAMPLE_RATE: u32 = 48000;
+// 20ms一帧
+const OPUS_FRAME_SIZE: usize = 960;
+// libopus在48kHz下的默认前导采样数
+const OPUS_PRE_SKIP: u16 = 312;
+
+// Opus不支持44.1kHz，使用线性插值重采样到48kHz
+fn resample(audio: &PcmAudio, target_rate: u32) -> Vec<i16> {
+    let channels = audio.channels as usize;
+    let frames = audio.samples.len() / channels;
+    let target_frames = (frames as u64 * target_rate as u64 / audio.sample_rate as u64) as usize;
+    let ratio = audio.sample_rate as f64 / target_rate as f64;
+    let mut result = Vec::with_capacity(target_frames * channels);
+    for i in 0..target_frames {
+        let pos = i as f64 * ratio;
+        let index = pos as usize;
+        let frac = pos - index as f64;
+        for c in 0..channels {
+            let a = audio.samples[index * channels + c] as f64;
+            let b = *audio
+                .samples
+                .get((index + 1) * channels + c)
+                .unwrap_or(&audio.samples[index * channels + c]) as f64;
+            result.push((a + (b - a) * frac).round() as i16);
+        }
+    }
+    result
+}
+
+fn encode_opus(audio: &PcmAudio, bitrate: u32) -> anyhow::Result<Vec<u8>> {
+    use ogg::{PacketWriteEndInfo, PacketWriter};
+    use opus::{Application, Bitrate, Channels, Encoder};
+    let channels = audio.channels as usize;
+    let mut encoder = Encoder::new(
+        OPUS_SAMPLE_RATE,
+        if channels == 1 {
+            Channels::Mono
+        } else {
+            Channels::Stereo
+        },
+        Application::Audio,
+    )
+    .map_err(|e| anyhow!("初始化Opus编码器失败: {}", e))?;
+    encoder
+        .set_bitrate(Bitrate::Bits(bitrate as i32 * 1000))
+        .map_err(|e| anyhow!("设置码率失败: {}", e))?;
+    let mut samples = resample(audio, OPUS_SAMPLE_RATE);
+    // 补齐最后一帧
+    let frame_len = OPUS_FRAME_SIZE * channels;
+    let padded_len = (samples.len() + frame_len - 1) / frame_len * frame_len;
+    let total_frames = samples.len() / channels;
+    samples.resize(padded_len, 0);
+
+    let serial = 0x6d75_7369;
+    let mut output = Vec::<u8>::new();
+    let mut writer = PacketWriter::new(&mut output);
+    let mut head = b"OpusHead".to_vec();
+    head.push(1);
+    head.push(channels as u8);
+    head.extend_from_slice(&OPUS_PRE_SKIP.to_le_bytes());
+    head.extend_from_slice(&audio.sample_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    writer.write_packet(
+        head.into_boxed_slice(),
+        serial,
+        PacketWriteEndInfo::EndPage,
+        0,
+    )?;
+    let vendor = b"music_gen";
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    writer.write_packet(
+        tags.into_boxed_slice(),
+        serial,
+        PacketWriteEndInfo::EndPage,
+        0,
+    )?;
+    let chunks = samples.chunks(frame_len).collect::<Vec<&[i16]>>();
+    let mut buf = vec![0u8; 4000];
+    for (i, chunk) in chunks.iter().enumerate() {
+        let size = encoder
+            .encode(chunk, &mut buf)
+            .map_err(|e| anyhow!("Opus编码失败: {}", e))?;
+        let last = i + 1 == chunks.len();
+        // 末页的位置为实际采样数，解码器据此裁掉补齐的部分
+        let granule = if last {
+            total_frames as u64 + OPUS_PRE_SKIP as u64
+        } else {
+            ((i + 1) * OPUS_FRAME_SIZE) as u64 + OPUS_PRE_SKIP as u64
+        };
+        writer.write_packet(
+            buf[..size].to_vec().into_boxed_slice(),
+            serial,
+            if last {
+                PacketWriteEndInfo::EndStream
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            },
+            granule,
+        )?;
+    }
+    drop(writer);
+    Ok(output)
+}
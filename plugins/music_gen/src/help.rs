@@ -4,6 +4,7 @@ pub(crate) const HELP_STR: &str = r###"本功能基于PySynth(Rust重制)，通
 musicgen [--参数1] [值1] [--参数2] [值2]... [音轨1:音符1] [音轨1:音符2].... | [音轨2:音符1] [音轨2:音符2...]
 
 其中以|分割不同音轨
+可以在任意音轨中插入 t.<BPM> 以改变此后的速度，例如 t.90，速度变化对所有音轨生效
 
 其中音符的格式如下:
 本插件支持的音符包括普通五线谱和简谱，具体使用何种通过选项指定，详见下文。
@@ -35,12 +36,28 @@ musicgen --bpm 130 c.1 d.1 e.1 f.1 g.1 a.1 b.1
 --major,-m <大调> ———— 可选，用于在使用简谱时指定大调，默认为C
 --volume-mix,-v <音轨1,音轨2..> ———— 控制各音轨的音量占比
 --download,-d ———— 下载生成的音乐
+--upload,-u ———— 将生成的音乐上传到群文件
 --inverse,-i ———— 使用本参数时，节拍x表示的意义将会变成"这个音占y分音符的比例",其中y通过另一个参数beats指定,默认为4
 --beats,-b ———— 上文所述的参数
 --scale,-s ———— 振幅缩放，默认为1.0，数值越大总音量越大。数值太大可能导致整数溢出进而产生奇怪效果。
+--import <来源> ———— 导入MIDI、MusicXML或ABC乐谱，来源可以是URL、洛谷剪贴板或群文件名，此时忽略命令中的音符
+--import-format <格式> ———— 可选，指定导入乐谱的格式(midi/musicxml/abc)，默认根据文件名与内容判断
+--instruments <音色1,音色2..> ———— 各音轨使用的音色，只指定一个时应用于所有音轨，默认为piano
+    可用音色: piano(b), sine(a), bowed(c), woodwind(d), rhodes(e), percussion(p), pluck(s), wavetable(w), drums
+    drums为鼓组，c为底鼓，d为军鼓，f#与g#为闭镲，a#为开镲，b为吊镲，其余为通鼓
+--pan <音轨1,音轨2..> ———— 各音轨的声像，取值-1(左)到1(右)，指定后输出立体声
+--reverb <湿声比例[,房间大小]> ———— 添加混响，取值范围均为0到1
+--adsr <起音,衰减,持续音量,释音> ———— 除piano外音色的包络，时间以秒为单位，持续音量取值0到1
+--midi ———— 同时导出MIDI文件并上传到群文件
+--midi-only ———— 仅导出MIDI文件，不生成音频
+--render ———— 同时发送乐谱的钢琴卷帘图，横轴为时间(竖线为每四拍)，纵轴为音高，不同音轨使用不同颜色，带边框的为重音
+--check ———— 仅检查音符是否合法并报告第一个非法音符的位置，不生成音频
 合法的指令调用举例:
 musicgen --numbered -m bB 5.4 3.4 2.4 1.4 2.8 1.8 2.4 5.-4 r.8 5.4 3.4 2.4 1.4 2.8 1.8 5.4 3.-4 r.8
 
 关于从剪贴板下载：
 由于QQ的限制,单条消息长度不能超过4.5K，故本插件的指令支持从洛谷剪贴板下载数据.
+
+关于导入乐谱：
+导入时和弦会被拆分为多个音轨，未指定BPM时使用乐谱中的速度。通过剪贴板导入MIDI文件时，请将文件以base64编码后粘贴。
 "###;
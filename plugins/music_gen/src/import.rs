@@ -0,0 +1,184 @@
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::{CountdownBotClient, ResultType},
+    command::SenderType,
+    utils::{fetch_luogu_pasteboard, is_luogu_pasteboard_url},
+};
+use log::info;
+use reqwest::{redirect::Policy, Url};
+use std::net::IpAddr;
+
+use crate::config::MusicGenConfig;
+
+const MAX_REDIRECTS: usize = 5;
+
+fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let octets = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                // 100.64.0.0/10
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64))
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                // fc00::/7 与 fe80::/10
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+                && v6
+                    .to_ipv4()
+                    .map(|v| is_public_ip(&IpAddr::V4(v)))
+                    .unwrap_or(true)
+        }
+    }
+}
+
+// 只允许http(s)链接，且主机不能解析到回环或内网地址
+async fn check_url(url: &Url, allowed_hosts: &[String]) -> ResultType<()> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(anyhow!("仅支持http与https链接!").into());
+    }
+    let host = url
+        .host_str()
+        .ok_or(anyhow!("链接中缺少主机名!"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase();
+    if !allowed_hosts.is_empty()
+        && !allowed_hosts
+            .iter()
+            .any(|v| host == *v || host.ends_with(&format!(".{}", v)))
+    {
+        return Err(anyhow!("不允许从 {} 导入乐谱!", host).into());
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| anyhow!("无法解析主机 {}: {}", host, e))?;
+    for addr in addrs {
+        if !is_public_ip(&addr.ip()) {
+            return Err(anyhow!("不允许访问内网地址: {}", host).into());
+        }
+    }
+    Ok(())
+}
+
+// 手动处理重定向，以便检查每次跳转的目标
+// allowed_hosts为空时允许除内网地址外的任意地址
+async fn download(url: &str, size_limit: u64, allowed_hosts: &[String]) -> ResultType<Vec<u8>> {
+    info!("Downloading score: {}", url);
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .map_err(|e| anyhow!("创建HTTP客户端时出错: {}", e))?;
+    let mut url = Url::parse(url).map_err(|e| anyhow!("非法的链接: {}", e))?;
+    let mut redirects = 0;
+    let mut resp = loop {
+        check_url(&url, allowed_hosts).await?;
+        let resp = client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| anyhow!("下载乐谱时出错: {}", e))?;
+        if !resp.status().is_redirection() {
+            break resp
+                .error_for_status()
+                .map_err(|e| anyhow!("下载乐谱时出错: {}", e))?;
+        }
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            return Err(anyhow!("重定向次数过多!").into());
+        }
+        let location = resp
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(anyhow!("重定向缺少目标地址!"))?;
+        url = url
+            .join(location)
+            .map_err(|e| anyhow!("非法的重定向地址: {}", e))?;
+    };
+    if let Some(len) = resp.content_length() {
+        if len > size_limit {
+            return Err(anyhow!("文件过大: {} 字节，上限为 {} 字节", len, size_limit).into());
+        }
+    }
+    let mut buf = vec![];
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| anyhow!("下载乐谱时出错: {}", e))?
+    {
+        if (buf.len() + chunk.len()) as u64 > size_limit {
+            return Err(anyhow!("文件过大: 超过上限 {} 字节", size_limit).into());
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(buf)
+}
+
+/*
+获取要导入的乐谱，返回(文件名, 内容)
+来源可以是洛谷剪贴板、URL或群文件名
+剪贴板中的MIDI文件需要使用base64编码
+*/
+pub(crate) async fn fetch_score(
+    client: &CountdownBotClient,
+    sender: &SenderType,
+    source: &str,
+    config: &MusicGenConfig,
+) -> ResultType<(String, Vec<u8>)> {
+    let size_limit = config.import_file_size_limit.max(0);
+    // 只有主机确为洛谷时才按剪贴板处理，其余链接都需经过check_url
+    if is_luogu_pasteboard_url(source) {
+        let text = fetch_luogu_pasteboard(source).await?;
+        let compact = text.split_whitespace().collect::<String>();
+        if let Ok(bytes) = base64::decode(&compact) {
+            if bytes.starts_with(b"MThd") {
+                return Ok((String::from("paste.mid"), bytes));
+            }
+        }
+        return Ok((String::from("paste"), text.into_bytes()));
+    }
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let name = source
+            .split(|c| c == '?' || c == '#')
+            .next()
+            .and_then(|v| v.rsplit('/').next())
+            .unwrap_or("")
+            .to_string();
+        return Ok((
+            name,
+            download(source, size_limit as u64, &config.import_allowed_hosts).await?,
+        ));
+    }
+    let group_id = match sender {
+        SenderType::Group(evt) => evt.group_id,
+        _ => return Err(anyhow!("仅可在群内导入群文件!").into()),
+    };
+    let file = client.find_group_file(group_id, source).await?;
+    if file.file_size > size_limit {
+        return Err(anyhow!(
+            "文件过大: {} 字节，上限为 {} 字节",
+            file.file_size,
+            size_limit
+        )
+        .into());
+    }
+    let url = client
+        .get_group_file_url(group_id, &file.file_id, file.busid)
+        .await?
+        .url;
+    // 群文件链接由go-cqhttp提供，不受导入域名的限制
+    Ok((
+        file.file_name,
+        download(&url, size_limit as u64, &[]).await?,
+    ))
+}
@@ -2,37 +2,40 @@ use std::sync::Arc;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
+use cache::{create_cache, CacheWrapped};
 use config::MusicGenConfig;
 use countdown_bot3::{
     countdown_bot::{
-        bot::{self, CountdownBot},
-        client::{CountdownBotClient, ResultType},
+        artifact::ArtifactStore,
+        bot,
+        client::CountdownBotClient,
         command::{Command, SenderType},
         plugin::{BotPlugin, HookResult, PluginMeta},
-        utils::{load_config_or_save_default, SubUrlWrapper},
+        utils::load_config_or_save_default,
     },
     export_static_plugin,
 };
-use reqwest::{header::HeaderValue, StatusCode};
-use salvo::{prelude::FlowCtrl, Depot, Handler, Request, Response};
 use tokio::sync::Semaphore;
 static PLUGIN_NAME: &str = "music_gen";
 
-mod cache;
+pub mod cache;
 mod command;
 mod command_entry;
 mod config;
+mod encoder;
 mod help;
-pub mod luogu_fetcher;
+mod import;
 pub mod notes;
 mod pysynth;
+pub mod render;
+pub mod score;
 mod utils;
 struct MusicGenPlugin {
     client: Option<CountdownBotClient>,
     config: Option<MusicGenConfig>,
     semaphore: Option<Arc<tokio::sync::Semaphore>>,
-    redis_client: Option<Arc<redis::Client>>,
-    url_wrapper: Option<SubUrlWrapper>,
+    cache: Option<CacheWrapped>,
+    artifacts: Option<ArtifactStore>,
 }
 impl Default for MusicGenPlugin {
     fn default() -> Self {
@@ -40,8 +43,8 @@ impl Default for MusicGenPlugin {
             client: Default::default(),
             config: Default::default(),
             semaphore: None,
-            redis_client: None,
-            url_wrapper: None,
+            cache: None,
+            artifacts: None,
         }
     }
 }
@@ -52,16 +55,15 @@ impl BotPlugin for MusicGenPlugin {
         bot: &mut bot::CountdownBot,
         _handle: tokio::runtime::Handle,
     ) -> HookResult<()> {
+        let data_dir = bot.ensure_plugin_data_dir(PLUGIN_NAME)?;
         self.config = Some(
-            load_config_or_save_default(&bot.ensure_plugin_data_dir(PLUGIN_NAME)?)
+            load_config_or_save_default(&data_dir)
                 .map_err(|e| anyhow!("加载配置时发生错误: {}\n{}", e, e.backtrace()))?,
         );
         self.semaphore = Some(Arc::new(Semaphore::new(
             self.config.as_ref().unwrap().max_execute_sametime as usize,
         )));
-        self.redis_client = Some(Arc::new(redis::Client::open(
-            self.config.as_ref().unwrap().redis_uri.as_str(),
-        )?));
+        self.cache = Some(create_cache(self.config.as_ref().unwrap(), &data_dir)?);
         bot.register_command(
             Command::new("musicgen")
                 .group(true)
@@ -69,8 +71,7 @@ impl BotPlugin for MusicGenPlugin {
                 .description("生成音乐 | 使用 musicgen --help 查看帮助"),
         )
         .unwrap();
-        self.url_wrapper = Some(bot.create_url_wrapper());
-        setup_salvo(bot, self.redis_client.as_ref().unwrap().clone());
+        self.artifacts = Some(bot.get_artifact_store());
         Ok(())
     }
     fn on_before_start(
@@ -101,56 +102,3 @@ impl BotPlugin for MusicGenPlugin {
 }
 
 export_static_plugin!(PLUGIN_NAME, MusicGenPlugin::default());
-
-fn setup_salvo(bot: &mut CountdownBot, redis_client: Arc<redis::Client>) {
-    use salvo::prelude::*;
-    let router =
-        Router::with_path("/music_gen/download/<hash>").get(SimpleHandler { redis_client });
-    bot.get_salvo_router().routers_mut().push(router);
-}
-
-struct SimpleHandler {
-    redis_client: Arc<redis::Client>,
-}
-impl SimpleHandler {
-    pub(crate) async fn get_data(&self, hash: &str) -> ResultType<Vec<u8>> {
-        let mut conn = self.redis_client.clone().get_async_connection().await?;
-        use redis::AsyncCommands;
-        let key = cache::make_key(hash);
-        if !conn.exists(&key).await? {
-            return Err(anyhow!("Invalid hash!").into());
-        }
-        let resp: Vec<u8> = conn.get(&key).await?;
-        return Ok(resp);
-    }
-}
-#[async_trait::async_trait]
-impl Handler for SimpleHandler {
-    async fn handle(
-        &self,
-        req: &mut Request,
-        _depot: &mut Depot,
-        res: &mut Response,
-        _ctrl: &mut FlowCtrl,
-    ) {
-        if let Some(hash) = req.get_param::<String>("hash") {
-            match self.get_data(&hash).await {
-                Ok(v) => {
-                    res.headers_mut().append(
-                        "Content-Disposition",
-                        HeaderValue::from_str(&format!("attachment; filename={}.wav", hash))
-                            .unwrap(),
-                    );
-                    res.render_binary(HeaderValue::from_static("audio/wave"), &v[..])
-                }
-                Err(e) => {
-                    res.set_status_code(StatusCode::NOT_FOUND);
-                    res.render_plain_text(&format!("Error: {}", e));
-                }
-            };
-        } else {
-            res.set_status_code(StatusCode::BAD_REQUEST);
-            res.render_plain_text("Param required");
-        }
-    }
-}
@@ -95,7 +95,8 @@ pub fn transform_notes(notes: &[&str], major: &str) -> anyhow::Result<Vec<String
     let major_height = parse_major(major)?;
     let mut result: Vec<String> = vec![];
     for note in notes.iter() {
-        let output = if !note.contains("r") {
+        // 速度变化标记无需转换
+        let output = if !note.contains("r") && !note.starts_with("t.") {
             transform_single_note(note.trim(), major_height)?
         } else {
             note.to_string()
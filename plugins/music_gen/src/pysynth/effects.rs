@@ -0,0 +1,80 @@
+// 混音阶段使用的声像与混响
+use std::f64::consts::PI;
+
+// 等功率声像，pan取值-1(左)到1(右)，返回(左,右)增益
+pub fn pan_gains(pan: f64) -> (f64, f64) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * PI / 4.0;
+    (angle.cos(), angle.sin())
+}
+
+struct Comb {
+    buffer: Vec<f64>,
+    index: usize,
+    feedback: f64,
+    damp: f64,
+    last: f64,
+}
+
+impl Comb {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.buffer[self.index];
+        self.last = output * (1.0 - self.damp) + self.last * self.damp;
+        self.buffer[self.index] = input + self.last * self.feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+struct AllPass {
+    buffer: Vec<f64>,
+    index: usize,
+}
+
+impl AllPass {
+    fn process(&mut self, input: f64) -> f64 {
+        let delayed = self.buffer[self.index];
+        let output = delayed - input;
+        self.buffer[self.index] = input + delayed * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+/*
+Schroeder/Freeverb式混响，并联梳状滤波器后串联全通滤波器
+wet为湿声比例，room_size为0到1之间的房间大小
+*/
+pub fn apply_reverb(samples: &mut [f64], wet: f64, room_size: f64) {
+    const COMB_DELAYS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+    const ALLPASS_DELAYS: [usize; 4] = [556, 441, 341, 225];
+    let wet = wet.clamp(0.0, 1.0);
+    if wet <= 0.0 {
+        return;
+    }
+    let feedback = 0.7 + 0.28 * room_size.clamp(0.0, 1.0);
+    let mut combs = COMB_DELAYS
+        .iter()
+        .map(|v| Comb {
+            buffer: vec![0.0; *v],
+            index: 0,
+            feedback,
+            damp: 0.2,
+            last: 0.0,
+        })
+        .collect::<Vec<Comb>>();
+    let mut allpasses = ALLPASS_DELAYS
+        .iter()
+        .map(|v| AllPass {
+            buffer: vec![0.0; *v],
+            index: 0,
+        })
+        .collect::<Vec<AllPass>>();
+    for v in samples.iter_mut() {
+        let input = *v * 0.015;
+        let mut output = combs.iter_mut().map(|c| c.process(input)).sum::<f64>();
+        for allpass in allpasses.iter_mut() {
+            output = allpass.process(output);
+        }
+        *v = *v * (1.0 - wet) + output * wet * 3.0;
+    }
+}
@@ -1,3 +1,5 @@
+pub mod effects;
 pub mod makewav_impl;
 pub mod mkfreq;
 pub mod pysynth_b;
+pub mod voices;
@@ -0,0 +1,318 @@
+// PySynth其他音色(a/c/d/e/p/s)的简化实现，以及波表与鼓组
+use std::f64::consts::PI;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use super::pysynth_b::{WaveRenderer, PITCHHZ};
+
+const SAMPLE_RATE: f64 = 44100.0;
+// 释音的最长秒数
+pub const MAX_RELEASE: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instrument {
+    // PySynth B，原有的钢琴音色
+    Piano,
+    // PySynth A，带泛音的正弦波
+    Sine,
+    // PySynth C，弓弦乐器(锯齿波)
+    Bowed,
+    // PySynth D，木管乐器(方波)
+    Woodwind,
+    // PySynth E，FM合成的电钢琴
+    Rhodes,
+    // PySynth P，有音高的打击乐
+    Percussion,
+    // PySynth S，Karplus-Strong拨弦
+    Pluck,
+    Wavetable,
+    // 按音名映射到底鼓、军鼓、镲等
+    Drums,
+}
+
+impl Instrument {
+    pub fn from_name(name: &str) -> anyhow::Result<Instrument> {
+        Ok(match name.to_lowercase().as_str() {
+            "piano" | "b" => Instrument::Piano,
+            "sine" | "a" => Instrument::Sine,
+            "bowed" | "c" => Instrument::Bowed,
+            "woodwind" | "d" => Instrument::Woodwind,
+            "rhodes" | "e" => Instrument::Rhodes,
+            "percussion" | "p" => Instrument::Percussion,
+            "pluck" | "s" => Instrument::Pluck,
+            "wavetable" | "w" => Instrument::Wavetable,
+            "drums" => Instrument::Drums,
+            _ => return Err(anyhow!("未知的音色: {}", name)),
+        })
+    }
+}
+
+// 以秒为单位的包络参数，sustain为持续阶段的音量比例
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Adsr {
+    pub attack: f64,
+    pub decay: f64,
+    pub sustain: f64,
+    pub release: f64,
+}
+
+impl Default for Adsr {
+    fn default() -> Self {
+        Self {
+            attack: 0.01,
+            decay: 0.15,
+            sustain: 0.6,
+            release: 0.2,
+        }
+    }
+}
+
+impl Adsr {
+    pub fn parse(text: &str) -> anyhow::Result<Adsr> {
+        let values = text
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| anyhow!("非法的ADSR参数: {}", text))?;
+        if values.len() != 4 || values.iter().any(|v| !v.is_finite() || *v < 0.0) || values[2] > 1.0
+        {
+            return Err(anyhow!("ADSR参数格式为 起音,衰减,持续音量(0~1),释音"));
+        }
+        if values[3] > MAX_RELEASE {
+            return Err(anyhow!("释音时间不能超过 {} 秒", MAX_RELEASE));
+        }
+        Ok(Adsr {
+            attack: values[0],
+            decay: values[1],
+            sustain: values[2],
+            release: values[3],
+        })
+    }
+    // t为从按下开始经过的秒数，hold为按住的秒数
+    fn level(&self, t: f64, hold: f64) -> f64 {
+        let held = |t: f64| {
+            if t < self.attack {
+                t / self.attack
+            } else if t < self.attack + self.decay {
+                1.0 - (1.0 - self.sustain) * (t - self.attack) / self.decay
+            } else {
+                self.sustain
+            }
+        };
+        if t < hold {
+            held(t)
+        } else if self.release > 0.0 && t < hold + self.release {
+            held(hold) * (1.0 - (t - hold) / self.release)
+        } else {
+            0.0
+        }
+    }
+}
+
+// 固定种子的噪声，保证同样的输入生成同样的音频
+struct Noise(u64);
+
+impl Noise {
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+    }
+}
+
+// 叠加不超过奈奎斯特频率的谐波
+fn harmonics(freq: f64, phase: f64, amplitude: impl Fn(usize) -> f64) -> f64 {
+    let mut result = 0.0;
+    let mut n = 1;
+    while n <= 16 && freq * (n as f64) < SAMPLE_RATE / 2.0 {
+        result += amplitude(n) * (phase * n as f64).sin();
+        n += 1;
+    }
+    result
+}
+
+#[derive(Clone, Copy)]
+enum DrumKind {
+    Kick,
+    Snare,
+    Tom,
+    ClosedHat,
+    OpenHat,
+    Crash,
+}
+
+// 参照General MIDI鼓组的排布
+fn drum_of(key: usize) -> DrumKind {
+    // key为钢琴键序号，a0为0
+    match (key + 9) % 12 {
+        0 | 1 => DrumKind::Kick,
+        2 | 3 => DrumKind::Snare,
+        6 | 8 => DrumKind::ClosedHat,
+        10 => DrumKind::OpenHat,
+        11 => DrumKind::Crash,
+        _ => DrumKind::Tom,
+    }
+}
+
+pub struct Synth {
+    pub instrument: Instrument,
+    pub bpm: i32,
+    pub adsr: Adsr,
+    // 波表音色的各次谐波振幅
+    pub wavetable: Vec<f64>,
+}
+
+impl Synth {
+    fn render_note(&self, freq: f64, key: usize, hold: f64, vol: f64, out: &mut [f64]) {
+        let mut noise = Noise(0x9E3779B97F4A7C15 ^ key as u64);
+        match self.instrument {
+            Instrument::Pluck => {
+                // Karplus-Strong: 用噪声填充延迟线并不断取平均
+                let period = (SAMPLE_RATE / freq).round().max(2.0) as usize;
+                let mut buffer = (0..period).map(|_| noise.next()).collect::<Vec<f64>>();
+                for (i, v) in out.iter_mut().enumerate() {
+                    let t = i as f64 / SAMPLE_RATE;
+                    let index = i % period;
+                    let sample = buffer[index];
+                    buffer[index] = 0.996 * 0.5 * (sample + buffer[(index + 1) % period]);
+                    let release = if t < hold {
+                        1.0
+                    } else {
+                        (-(t - hold) / self.adsr.release.max(0.01)).exp()
+                    };
+                    *v += vol * sample * release;
+                }
+            }
+            Instrument::Drums => {
+                let kind = drum_of(key);
+                let mut last = 0.0;
+                for (i, v) in out.iter_mut().enumerate() {
+                    let t = i as f64 / SAMPLE_RATE;
+                    let white = noise.next();
+                    // 一阶差分作为简单的高通滤波
+                    let bright = white - last;
+                    last = white;
+                    let sample = match kind {
+                        DrumKind::Kick => {
+                            let f = 50.0 + 100.0 * (-t * 30.0).exp();
+                            (2.0 * PI * f * t).sin() * (-t * 8.0).exp()
+                        }
+                        DrumKind::Snare => {
+                            (0.6 * white + 0.4 * (2.0 * PI * 180.0 * t).sin()) * (-t * 20.0).exp()
+                        }
+                        DrumKind::Tom => {
+                            let f = freq.min(400.0) * (1.0 + 0.5 * (-t * 20.0).exp());
+                            (2.0 * PI * f * t).sin() * (-t * 10.0).exp()
+                        }
+                        DrumKind::ClosedHat => 0.5 * bright * (-t * 60.0).exp(),
+                        DrumKind::OpenHat => 0.5 * bright * (-t * 8.0).exp(),
+                        DrumKind::Crash => 0.5 * bright * (-t * 3.0).exp(),
+                    };
+                    *v += vol * sample;
+                }
+            }
+            _ => {
+                let mut low = 0.0;
+                for (i, v) in out.iter_mut().enumerate() {
+                    let t = i as f64 / SAMPLE_RATE;
+                    let env = self.adsr.level(t, hold);
+                    if env <= 0.0 && t >= hold {
+                        break;
+                    }
+                    let phase = 2.0 * PI * freq * t;
+                    let sample = match self.instrument {
+                        Instrument::Sine => harmonics(freq, phase, |n| match n {
+                            1 => 1.0,
+                            2 => 0.3 * (-t * 3.0).exp(),
+                            3 => 0.15 * (-t * 5.0).exp(),
+                            _ => 0.0,
+                        }),
+                        Instrument::Bowed => {
+                            let vibrato = 1.0 + 0.004 * (2.0 * PI * 5.5 * t).sin();
+                            harmonics(freq, phase * vibrato, |n| 0.6 / n as f64)
+                        }
+                        Instrument::Woodwind => harmonics(freq, phase, |n| {
+                            if n % 2 == 1 {
+                                0.7 / n as f64
+                            } else {
+                                0.05 / n as f64
+                            }
+                        }),
+                        Instrument::Rhodes => {
+                            let index = 1.5 * (-t * 4.0).exp();
+                            (phase + index * phase.sin()).sin()
+                        }
+                        Instrument::Percussion => {
+                            // 按音高调整截止频率的低通噪声
+                            let alpha = (freq / SAMPLE_RATE * 2.0 * PI).min(1.0);
+                            low += alpha * (noise.next() - low);
+                            (low * 4.0 + 0.3 * phase.sin()) * (-t * 12.0).exp()
+                        }
+                        Instrument::Wavetable => {
+                            harmonics(freq, phase, |n| *self.wavetable.get(n - 1).unwrap_or(&0.0))
+                        }
+                        Instrument::Piano | Instrument::Pluck | Instrument::Drums => 0.0,
+                    };
+                    *v += vol * env * sample;
+                }
+            }
+        }
+    }
+    pub fn make_wav(&self, song: &[(String, f64)]) -> anyhow::Result<Vec<i16>> {
+        if self.instrument == Instrument::Piano {
+            return WaveRenderer::default().set_bpm(self.bpm).make_wav(song);
+        }
+        let bpmfac = 120.0 / self.bpm as f64;
+        let length = |v: f64| {
+            if v < 0.0 {
+                88200.0 / (-2.0 * v / 3.0) * bpmfac
+            } else {
+                88200.0 / v * bpmfac
+            }
+        };
+        let total = song.iter().map(|(_, v)| length(*v)).sum::<f64>();
+        // 与钢琴音色一致，末尾留出2秒
+        let mut data = vec![0.0f64; (total + 2.0 * SAMPLE_RATE).ceil() as usize + 1];
+        let mut pos = 0.0;
+        for (name, duration) in song.iter() {
+            let samples = length(*duration);
+            if name.is_empty() {
+                return Err(anyhow!("Empty note encoutered."));
+            }
+            if name != "r" {
+                let (vol, mut note) = match name.strip_suffix('*') {
+                    Some(v) => (1.1, v.to_string()),
+                    None => (1.0, name.clone()),
+                };
+                if !note.chars().last().unwrap().is_ascii_digit() {
+                    note.push('4');
+                }
+                let freq = *PITCHHZ.get(&note).ok_or(anyhow!("非法音符: {}", note))?;
+                let key = ((freq / 27.5).log2() * 12.0).round() as usize;
+                // 持续音符的时值，与PySynth一致留出一点断奏
+                let hold = samples * 0.9 / SAMPLE_RATE;
+                let tail = match self.instrument {
+                    Instrument::Drums => 2.0,
+                    Instrument::Pluck => hold + 5.0 * self.adsr.release.max(0.01),
+                    _ => hold + self.adsr.release,
+                };
+                let begin = pos as usize;
+                let end = begin
+                    .saturating_add((tail * SAMPLE_RATE) as usize)
+                    .min(data.len());
+                self.render_note(freq, key, hold, vol, &mut data[begin..end]);
+            }
+            pos += samples;
+        }
+        data.truncate((pos + 2.0 * SAMPLE_RATE) as usize);
+        let maxval = data.iter().fold(0.0f64, |a, b| a.max(b.abs()));
+        if maxval > 0.0 {
+            for v in data.iter_mut() {
+                *v /= maxval * 2.0;
+            }
+        }
+        Ok(data.iter().map(|v| (v * 32000.0).round() as i16).collect())
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt::Write;
+
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::text_render::svg_to_png;
+
+use crate::{command::beats_of, score::note_to_pitch};
+
+// 每个四分音符与每个半音所占的像素
+const BEAT_WIDTH: f64 = 24.0;
+const ROW_HEIGHT: f64 = 8.0;
+// 乐曲过长时压缩横向比例，使图像宽度不超过此值
+const MAX_WIDTH: f64 = 4000.0;
+const LABEL_WIDTH: f64 = 36.0;
+const PADDING: f64 = 4.0;
+const COLORS: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+struct RollNote {
+    start: f64,
+    length: f64,
+    pitch: i32,
+    accent: bool,
+    track: usize,
+}
+
+/*
+将处理后的音轨绘制为钢琴卷帘图，返回PNG
+横轴为以四分音符为单位的时间，纵轴为音高，不同音轨使用不同颜色
+*/
+pub fn render_piano_roll(tracks: &[Vec<(String, f64)>]) -> anyhow::Result<Vec<u8>> {
+    let mut notes = vec![];
+    let mut total_beats: f64 = 0.0;
+    for (index, track) in tracks.iter().enumerate() {
+        let mut position = 0.0;
+        for (name, duration) in track.iter() {
+            let beats = beats_of(*duration);
+            if let Some(pitch) = note_to_pitch(name).map_err(|e| anyhow!("{}", e))? {
+                notes.push(RollNote {
+                    start: position,
+                    length: beats,
+                    pitch,
+                    accent: name.ends_with('*'),
+                    track: index,
+                });
+            }
+            position += beats;
+        }
+        total_beats = total_beats.max(position);
+    }
+    if notes.is_empty() {
+        return Err(anyhow!("没有可以绘制的音符"));
+    }
+    let mut low = notes.iter().map(|v| v.pitch).min().unwrap() - 1;
+    let mut high = notes.iter().map(|v| v.pitch).max().unwrap() + 1;
+    // 至少显示一个八度
+    if high - low < 12 {
+        let extra = 12 - (high - low);
+        low -= extra / 2;
+        high += extra - extra / 2;
+    }
+    let beat_width = BEAT_WIDTH.min(MAX_WIDTH / total_beats);
+    let width = LABEL_WIDTH + total_beats * beat_width + PADDING * 2.0;
+    let height = (high - low + 1) as f64 * ROW_HEIGHT + PADDING * 2.0;
+    let row_top = |pitch: i32| PADDING + (high - pitch) as f64 * ROW_HEIGHT;
+    let x_of = |beats: f64| LABEL_WIDTH + PADDING + beats * beat_width;
+
+    let mut svg = String::new();
+    write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" font-family="sans-serif">"#,
+        width.ceil(),
+        height.ceil()
+    )?;
+    write!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+    for pitch in low..=high {
+        let y = row_top(pitch);
+        // 黑键所在的行使用灰色背景
+        if [1, 3, 6, 8, 10].contains(&pitch.rem_euclid(12)) {
+            write!(
+                svg,
+                r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="#f0f0f0"/>"##,
+                x_of(0.0),
+                y,
+                total_beats * beat_width,
+                ROW_HEIGHT
+            )?;
+        }
+        if pitch.rem_euclid(12) == 0 {
+            write!(
+                svg,
+                r##"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#c0c0c0" stroke-width="1"/>"##,
+                x_of(0.0),
+                y + ROW_HEIGHT,
+                x_of(total_beats),
+                y + ROW_HEIGHT
+            )?;
+            write!(
+                svg,
+                r##"<text x="{:.1}" y="{:.1}" font-size="{:.0}" fill="#404040">C{}</text>"##,
+                PADDING,
+                y + ROW_HEIGHT,
+                ROW_HEIGHT * 1.5,
+                pitch.div_euclid(12) - 1
+            )?;
+        }
+    }
+    // 每四拍一条小节线，过密时省略
+    if beat_width * 4.0 >= 4.0 {
+        let mut bar = 0.0;
+        while bar <= total_beats {
+            write!(
+                svg,
+                r##"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#d8d8d8" stroke-width="1"/>"##,
+                x_of(bar),
+                PADDING,
+                x_of(bar),
+                height - PADDING
+            )?;
+            bar += 4.0;
+        }
+    }
+    for note in notes.iter() {
+        write!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="1" fill="{}" fill-opacity="0.85"{}/>"#,
+            x_of(note.start),
+            row_top(note.pitch) + 0.5,
+            (note.length * beat_width - 0.5).max(1.0),
+            ROW_HEIGHT - 1.0,
+            COLORS[note.track % COLORS.len()],
+            if note.accent {
+                r#" stroke="black" stroke-width="1""#
+            } else {
+                ""
+            }
+        )?;
+    }
+    svg.push_str("</svg>");
+
+    svg_to_png(&svg, "")
+}
@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::client::ResultType;
+
+use super::TimedNote;
+
+const LETTERS: &str = "CDEFGAB";
+const BASE: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+// 升号与降号依次出现的音名
+const SHARP_ORDER: &str = "FCGDAEB";
+const FLAT_ORDER: &str = "BEADGCF";
+
+// 只接受有限的正数，如 1/8
+fn parse_fraction(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let value = match text.split_once('/') {
+        Some((a, b)) => a.trim().parse::<f64>().ok()? / b.trim().parse::<f64>().ok()?,
+        None => text.parse::<f64>().ok()?,
+    };
+    Some(value).filter(|v| v.is_finite() && *v > 0.0)
+}
+
+// 根据调号计算每个音名的升降
+fn key_signature(key: &str) -> ResultType<[i32; 7]> {
+    let key = key.split_whitespace().next().unwrap_or("C");
+    let mut result = [0; 7];
+    if key.is_empty() || key.eq_ignore_ascii_case("none") {
+        return Ok(result);
+    }
+    let mut chars = key.chars();
+    let tonic = chars.next().unwrap().to_ascii_uppercase();
+    let mut rest = chars.as_str();
+    let mut pitch = BASE[LETTERS.find(tonic).ok_or(anyhow!("非法调号: {}", key))?];
+    if let Some(v) = rest.strip_prefix('#') {
+        pitch += 1;
+        rest = v;
+    } else if let Some(v) = rest.strip_prefix('b') {
+        pitch -= 1;
+        rest = v;
+    }
+    let mode = rest.to_lowercase();
+    if mode.starts_with('m') && !mode.starts_with("maj") && !mode.starts_with("mix") {
+        // 小调转换为关系大调
+        pitch += 3;
+    }
+    // 大调主音对应的升号数，负数表示降号
+    let count = match pitch.rem_euclid(12) {
+        0 => 0,
+        7 => 1,
+        2 => 2,
+        9 => 3,
+        4 => 4,
+        11 => 5,
+        6 => {
+            if key.contains('b') {
+                -6
+            } else {
+                6
+            }
+        }
+        5 => -1,
+        10 => -2,
+        3 => -3,
+        8 => -4,
+        1 => -5,
+        _ => unreachable!(),
+    };
+    let (order, delta) = if count >= 0 {
+        (SHARP_ORDER, 1)
+    } else {
+        (FLAT_ORDER, -1)
+    };
+    for c in order.chars().take(count.unsigned_abs() as usize) {
+        result[LETTERS.find(c).unwrap()] = delta;
+    }
+    Ok(result)
+}
+
+struct Voice {
+    notes: Vec<TimedNote>,
+    time: f64,
+}
+
+struct AbcParser {
+    // 单位时值，以四分音符为单位
+    unit: f64,
+    key: [i32; 7],
+    // 小节内的临时升降记号
+    bar_accidentals: HashMap<(usize, i32), i32>,
+    voices: Vec<Voice>,
+    voice_ids: HashMap<String, usize>,
+    current: usize,
+    bpm: Option<u32>,
+    // 连音: (剩余音符数, 时值系数)
+    tuplet: Option<(u32, f64)>,
+    // 附点节奏对下一个音符的系数
+    broken: Option<f64>,
+    tie: bool,
+}
+
+impl AbcParser {
+    fn new() -> Self {
+        Self {
+            unit: 0.5,
+            key: [0; 7],
+            bar_accidentals: HashMap::new(),
+            voices: vec![Voice {
+                notes: vec![],
+                time: 0.0,
+            }],
+            voice_ids: HashMap::new(),
+            current: 0,
+            bpm: None,
+            tuplet: None,
+            broken: None,
+            tie: false,
+        }
+    }
+    fn handle_field(&mut self, name: char, value: &str) -> ResultType<()> {
+        let value = value.trim();
+        match name {
+            'L' => {
+                self.unit =
+                    parse_fraction(value).ok_or(anyhow!("非法的单位时值: {}", value))? * 4.0;
+            }
+            'Q' => {
+                let bpm = match value.split_once('=') {
+                    Some((beat, bpm)) => {
+                        let beat = beat
+                            .split_whitespace()
+                            .filter_map(parse_fraction)
+                            .sum::<f64>();
+                        bpm.trim().parse::<f64>().ok().map(|v| v * beat * 4.0)
+                    }
+                    None => value.parse::<f64>().ok(),
+                }
+                .filter(|v| v.is_finite());
+                if let Some(v) = bpm {
+                    self.bpm = Some(v.round().max(1.0) as u32);
+                }
+            }
+            'K' => {
+                self.key = key_signature(value)?;
+            }
+            'V' => {
+                let id = value.split_whitespace().next().unwrap_or("").to_string();
+                let next = self.voices.len();
+                // 第一次声明声部时沿用默认声部
+                let index = if self.voice_ids.is_empty() && self.voices[0].notes.is_empty() {
+                    *self.voice_ids.entry(id).or_insert(0)
+                } else {
+                    *self.voice_ids.entry(id).or_insert(next)
+                };
+                if index == self.voices.len() {
+                    self.voices.push(Voice {
+                        notes: vec![],
+                        time: 0.0,
+                    });
+                }
+                self.current = index;
+                self.bar_accidentals.clear();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    fn read_length(chars: &[char], pos: &mut usize) -> f64 {
+        let read_number = |pos: &mut usize| {
+            let begin = *pos;
+            while *pos < chars.len() && chars[*pos].is_ascii_digit() {
+                *pos += 1;
+            }
+            chars[begin..*pos]
+                .iter()
+                .collect::<String>()
+                .parse::<f64>()
+                .ok()
+        };
+        let mut result = read_number(pos).unwrap_or(1.0);
+        while *pos < chars.len() && chars[*pos] == '/' {
+            *pos += 1;
+            result /= read_number(pos).unwrap_or(2.0);
+        }
+        result
+    }
+    // 读取一个音符或休止符，返回(音高, 时值倍数)
+    fn read_note(&mut self, chars: &[char], pos: &mut usize) -> ResultType<(Option<i32>, f64)> {
+        let mut accidental = None;
+        while *pos < chars.len() && "^_=".contains(chars[*pos]) {
+            let delta = match chars[*pos] {
+                '^' => 1,
+                '_' => -1,
+                _ => 0,
+            };
+            accidental = Some(accidental.unwrap_or(0) + delta);
+            *pos += 1;
+        }
+        let c = *chars.get(*pos).ok_or(anyhow!("乐谱不完整"))?;
+        *pos += 1;
+        if c == 'z' || c == 'x' {
+            return Ok((None, Self::read_length(chars, pos)));
+        }
+        let letter = LETTERS
+            .find(c.to_ascii_uppercase())
+            .ok_or(anyhow!("非法音符: {}", c))?;
+        let mut octave = if c.is_ascii_lowercase() { 5 } else { 4 };
+        while *pos < chars.len() && (chars[*pos] == ',' || chars[*pos] == '\'') {
+            octave += if chars[*pos] == ',' { -1 } else { 1 };
+            *pos += 1;
+        }
+        let alter = match accidental {
+            Some(v) => {
+                self.bar_accidentals.insert((letter, octave), v);
+                v
+            }
+            None => *self
+                .bar_accidentals
+                .get(&(letter, octave))
+                .unwrap_or(&self.key[letter]),
+        };
+        let pitch = (octave + 1) * 12 + BASE[letter] + alter;
+        Ok((Some(pitch), Self::read_length(chars, pos)))
+    }
+    fn push(&mut self, pitches: &[i32], multiplier: f64) {
+        let mut length = multiplier * self.unit;
+        if let Some(v) = self.broken.take() {
+            length *= v;
+        }
+        if let Some((remaining, factor)) = self.tuplet {
+            length *= factor;
+            self.tuplet = if remaining > 1 {
+                Some((remaining - 1, factor))
+            } else {
+                None
+            };
+        }
+        let tie = std::mem::replace(&mut self.tie, false);
+        let voice = &mut self.voices[self.current];
+        let start = voice.time;
+        for pitch in pitches.iter() {
+            if tie {
+                if let Some(prev) = voice
+                    .notes
+                    .iter_mut()
+                    .rev()
+                    .find(|v| v.pitch == *pitch && (v.start + v.length - start).abs() < 1e-6)
+                {
+                    prev.length += length;
+                    continue;
+                }
+            }
+            voice.notes.push(TimedNote {
+                start,
+                length,
+                pitch: *pitch,
+            });
+        }
+        voice.time += length;
+    }
+    // 根据附点节奏调整上一个音符并记录下一个音符的系数
+    fn apply_broken(&mut self, count: usize, longer_first: bool) {
+        let short = 0.5f64.powi(count as i32);
+        let (prev_factor, next_factor) = if longer_first {
+            (2.0 - short, short)
+        } else {
+            (short, 2.0 - short)
+        };
+        let voice = &mut self.voices[self.current];
+        let last_start = match voice.notes.last() {
+            Some(v) => v.start,
+            None => return,
+        };
+        let mut delta = 0.0;
+        for note in voice.notes.iter_mut().filter(|v| v.start == last_start) {
+            delta = note.length * (prev_factor - 1.0);
+            note.length *= prev_factor;
+        }
+        voice.time += delta;
+        self.broken = Some(next_factor);
+    }
+    fn parse_body(&mut self, line: &str) -> ResultType<()> {
+        let chars = line.chars().collect::<Vec<char>>();
+        let mut pos = 0;
+        while pos < chars.len() {
+            let c = chars[pos];
+            match c {
+                '%' => break,
+                '|' | ':' => {
+                    pos += 1;
+                    self.bar_accidentals.clear();
+                    // 反复记号后的跳跃记号
+                    while pos < chars.len() && chars[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+                '"' | '!' | '+' | '{' => {
+                    let end = match c {
+                        '{' => '}',
+                        v => v,
+                    };
+                    pos += 1;
+                    while pos < chars.len() && chars[pos] != end {
+                        pos += 1;
+                    }
+                    pos += 1;
+                }
+                '[' if pos + 2 < chars.len()
+                    && chars[pos + 1].is_ascii_alphabetic()
+                    && chars[pos + 2] == ':' =>
+                {
+                    let end = chars[pos..]
+                        .iter()
+                        .position(|v| *v == ']')
+                        .map(|v| v + pos)
+                        .unwrap_or(chars.len());
+                    let value = chars[pos + 3..end].iter().collect::<String>();
+                    self.handle_field(chars[pos + 1], &value)?;
+                    pos = end + 1;
+                }
+                '[' if pos + 1 < chars.len() && chars[pos + 1].is_ascii_digit() => {
+                    pos += 2;
+                }
+                '[' => {
+                    pos += 1;
+                    let mut pitches = vec![];
+                    let mut multiplier = None;
+                    while pos < chars.len() && chars[pos] != ']' {
+                        if chars[pos].is_whitespace() || chars[pos] == '-' {
+                            pos += 1;
+                            continue;
+                        }
+                        let (pitch, length) = self.read_note(&chars, &mut pos)?;
+                        multiplier.get_or_insert(length);
+                        pitches.extend(pitch);
+                    }
+                    pos += 1;
+                    let outer = Self::read_length(&chars, &mut pos);
+                    self.push(&pitches, multiplier.unwrap_or(1.0) * outer);
+                }
+                '(' => {
+                    pos += 1;
+                    if pos < chars.len() && chars[pos].is_ascii_digit() {
+                        let n = chars[pos].to_digit(10).unwrap();
+                        pos += 1;
+                        // (0 与 (1 不构成连音
+                        if n > 1 {
+                            let q = match n {
+                                2 | 4 | 8 => 3,
+                                3 | 6 => 2,
+                                _ => n - 1,
+                            };
+                            self.tuplet = Some((n, q as f64 / n as f64));
+                        }
+                    }
+                }
+                '>' | '<' => {
+                    let mut count = 0;
+                    while pos < chars.len() && chars[pos] == c {
+                        count += 1;
+                        pos += 1;
+                    }
+                    self.apply_broken(count, c == '>');
+                }
+                '-' => {
+                    self.tie = true;
+                    pos += 1;
+                }
+                'A'..='G' | 'a'..='g' | 'z' | 'x' | '^' | '_' | '=' => {
+                    let (pitch, length) = self.read_note(&chars, &mut pos)?;
+                    match pitch {
+                        Some(v) => self.push(&[v], length),
+                        None => self.push(&[], length),
+                    }
+                }
+                _ => {
+                    // 装饰音、连线等其他记号均忽略
+                    pos += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_abc(text: &str) -> ResultType<(Vec<Vec<TimedNote>>, Option<u32>)> {
+    let mut parser = AbcParser::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+        let chars = line.chars().collect::<Vec<char>>();
+        if chars.len() >= 2 && chars[0].is_ascii_alphabetic() && chars[1] == ':' {
+            // 歌词等字段不含音符
+            parser.handle_field(chars[0], &line[2..])?;
+            continue;
+        }
+        parser.parse_body(line.trim_end_matches('\\'))?;
+    }
+    Ok((
+        parser
+            .voices
+            .into_iter()
+            .map(|v| v.notes)
+            .filter(|v| !v.is_empty())
+            .collect(),
+        parser.bpm,
+    ))
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::client::ResultType;
+use midly::{
+    num::{u15, u24, u28, u4, u7},
+    Format, Header, MetaMessage, MidiMessage, Smf, Timing, TrackEvent, TrackEventKind,
+};
+
+use super::{note_to_pitch, TimedNote};
+
+const TICKS_PER_BEAT: u16 = 480;
+// 第10通道为打击乐，无法用音高表示
+const DRUM_CHANNEL: u8 = 9;
+// 速度以每拍微秒数保存为24位整数，BPM小于4时会溢出
+const MIN_BPM: u32 = 4;
+const MAX_BPM: u32 = 1000;
+
+pub fn parse_midi(data: &[u8]) -> ResultType<(Vec<Vec<TimedNote>>, Option<u32>)> {
+    let smf = Smf::parse(data).map_err(|e| anyhow!("解析MIDI文件失败: {}", e))?;
+    let ticks_per_beat = match smf.header.timing {
+        Timing::Metrical(v) => v.as_int() as f64,
+        Timing::Timecode(_, _) => return Err(anyhow!("不支持使用SMPTE时间码的MIDI文件").into()),
+    };
+    let mut bpm = None;
+    let mut voices = vec![];
+    for track in smf.tracks.iter() {
+        let mut tick: u64 = 0;
+        // (通道, 音高) -> 起始tick
+        let mut active = HashMap::<(u8, u8), u64>::new();
+        let mut notes = vec![];
+        for event in track.iter() {
+            tick += event.delta.as_int() as u64;
+            match event.kind {
+                TrackEventKind::Midi { channel, message } => {
+                    let channel = channel.as_int();
+                    if channel == DRUM_CHANNEL {
+                        continue;
+                    }
+                    let (key, pressed) = match message {
+                        MidiMessage::NoteOn { key, vel } => (key.as_int(), vel.as_int() > 0),
+                        MidiMessage::NoteOff { key, .. } => (key.as_int(), false),
+                        _ => continue,
+                    };
+                    if pressed {
+                        active.entry((channel, key)).or_insert(tick);
+                    } else if let Some(start) = active.remove(&(channel, key)) {
+                        notes.push(TimedNote {
+                            start: start as f64 / ticks_per_beat,
+                            length: (tick - start) as f64 / ticks_per_beat,
+                            pitch: key as i32,
+                        });
+                    }
+                }
+                TrackEventKind::Meta(MetaMessage::Tempo(v)) if bpm.is_none() => {
+                    bpm = Some((60_000_000 / v.as_int().max(1)).max(1));
+                }
+                _ => {}
+            }
+        }
+        if !notes.is_empty() {
+            voices.push(notes);
+        }
+    }
+    Ok((voices, bpm))
+}
+
+// 将解析后的音轨导出为MIDI文件，每个音轨使用单独的通道
+pub fn export_midi(tracks: &[Vec<(String, f64)>], bpm: u32) -> ResultType<Vec<u8>> {
+    let mut smf = Smf::new(Header::new(
+        Format::Parallel,
+        Timing::Metrical(u15::new(TICKS_PER_BEAT)),
+    ));
+    smf.tracks.push(vec![
+        TrackEvent {
+            delta: u28::new(0),
+            kind: TrackEventKind::Meta(MetaMessage::Tempo(u24::new(
+                60_000_000 / bpm.clamp(MIN_BPM, MAX_BPM),
+            ))),
+        },
+        TrackEvent {
+            delta: u28::new(0),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        },
+    ]);
+    for (index, track) in tracks.iter().enumerate() {
+        let channel = {
+            let v = (index % 15) as u8;
+            u4::new(if v >= DRUM_CHANNEL { v + 1 } else { v })
+        };
+        let mut events = vec![];
+        let mut pending: u32 = 0;
+        for (name, duration) in track.iter() {
+            // 与PySynth一致，负数表示附点
+            let duration = if *duration < 0.0 {
+                -2.0 * duration / 3.0
+            } else {
+                *duration
+            };
+            let ticks = (4.0 / duration * TICKS_PER_BEAT as f64).round() as u32;
+            match note_to_pitch(name)? {
+                Some(pitch) => {
+                    let key = u7::new(pitch as u8);
+                    let vel = u7::new(if name.ends_with('*') { 110 } else { 80 });
+                    events.push(TrackEvent {
+                        delta: u28::new(pending),
+                        kind: TrackEventKind::Midi {
+                            channel,
+                            message: MidiMessage::NoteOn { key, vel },
+                        },
+                    });
+                    events.push(TrackEvent {
+                        delta: u28::new(ticks),
+                        kind: TrackEventKind::Midi {
+                            channel,
+                            message: MidiMessage::NoteOff { key, vel },
+                        },
+                    });
+                    pending = 0;
+                }
+                None => pending += ticks,
+            }
+        }
+        events.push(TrackEvent {
+            delta: u28::new(pending),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        });
+        smf.tracks.push(events);
+    }
+    let mut buf = vec![];
+    smf.write_std(&mut buf)
+        .map_err(|e| anyhow!("生成MIDI文件失败: {}", e))?;
+    Ok(buf)
+}
@@ -0,0 +1,193 @@
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::client::ResultType;
+
+use crate::pysynth::pysynth_b::KEYNUM;
+
+pub mod abc;
+pub mod midi;
+pub mod musicxml;
+
+const KEYS: [&str; 12] = [
+    "c", "c#", "d", "d#", "e", "f", "f#", "g", "g#", "a", "a#", "b",
+];
+// 以四分音符为单位的最小时值，导入时按此量化
+const QUANTUM: f64 = 1.0 / 8.0;
+// 单个音符的最长时值，超出的休止符会被拆分
+const MAX_BEATS: f64 = 16.0;
+// 导入的乐谱总长度(以四分音符为单位)与转换后的音符总数上限
+const MAX_TOTAL_BEATS: f64 = 4096.0;
+const MAX_TOKENS: usize = 20000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreFormat {
+    Midi,
+    MusicXml,
+    Abc,
+}
+
+impl ScoreFormat {
+    pub fn from_name(name: &str) -> ResultType<ScoreFormat> {
+        match name.to_lowercase().as_str() {
+            "midi" | "mid" => Ok(ScoreFormat::Midi),
+            "musicxml" | "xml" => Ok(ScoreFormat::MusicXml),
+            "abc" => Ok(ScoreFormat::Abc),
+            _ => Err(anyhow!("不支持的格式: {}，可用格式: midi, musicxml, abc", name).into()),
+        }
+    }
+    // 根据文件名后缀或文件内容猜测格式
+    pub fn detect(name: &str, data: &[u8]) -> ResultType<ScoreFormat> {
+        if data.starts_with(b"MThd") {
+            return Ok(ScoreFormat::Midi);
+        }
+        if let Some((_, ext)) = name.rsplit_once('.') {
+            if let Ok(v) = ScoreFormat::from_name(ext) {
+                return Ok(v);
+            }
+        }
+        let text = String::from_utf8_lossy(data);
+        if text.contains("<score-partwise") {
+            return Ok(ScoreFormat::MusicXml);
+        }
+        if text.lines().any(|line| line.trim_start().starts_with("K:")) {
+            return Ok(ScoreFormat::Abc);
+        }
+        Err(anyhow!("无法识别乐谱格式，请使用 --import-format 指定").into())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportedScore {
+    // 每个音轨为一组五线谱格式的音符，如 c#5.4
+    pub tracks: Vec<Vec<String>>,
+    pub bpm: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TimedNote {
+    // 以四分音符为单位
+    pub start: f64,
+    pub length: f64,
+    pub pitch: i32,
+}
+
+pub fn import_score(
+    data: &[u8],
+    format: ScoreFormat,
+    max_tracks: usize,
+) -> ResultType<ImportedScore> {
+    let (voices, bpm) = match format {
+        ScoreFormat::Midi => midi::parse_midi(data)?,
+        ScoreFormat::MusicXml => musicxml::parse_musicxml(
+            std::str::from_utf8(data).map_err(|_| anyhow!("MusicXML文件不是合法的UTF-8文本"))?,
+        )?,
+        ScoreFormat::Abc => abc::parse_abc(&String::from_utf8_lossy(data))?,
+    };
+    // 拆分声部前检查时间，避免构造的乐谱展开出大量休止符
+    for note in voices.iter().flatten() {
+        if !note.start.is_finite() || !note.length.is_finite() || note.start < 0.0 {
+            return Err(anyhow!("乐谱中存在非法的时值").into());
+        }
+        if note.start + note.length > MAX_TOTAL_BEATS {
+            return Err(anyhow!("乐谱过长，最多支持 {} 拍", MAX_TOTAL_BEATS).into());
+        }
+    }
+    let mut tracks = vec![];
+    for notes in voices.into_iter() {
+        tracks.extend(split_voices(notes)?);
+    }
+    let token_count = tracks.iter().map(|v| v.len()).sum::<usize>();
+    if token_count > MAX_TOKENS {
+        return Err(anyhow!("乐谱包含 {} 个音符，超出了上限 {}", token_count, MAX_TOKENS).into());
+    }
+    if tracks.is_empty() {
+        return Err(anyhow!("乐谱中没有任何音符").into());
+    }
+    if tracks.len() > max_tracks {
+        return Err(anyhow!(
+            "乐谱包含 {} 个声部，超出了上限 {}",
+            tracks.len(),
+            max_tracks
+        )
+        .into());
+    }
+    Ok(ImportedScore { tracks, bpm })
+}
+
+// MIDI音高转换为PySynth音符名，60为中央C(c4)
+pub fn pitch_to_note(pitch: i32) -> ResultType<String> {
+    if !(21..21 + 88).contains(&pitch) {
+        return Err(anyhow!("音高超出钢琴音域: {}", pitch).into());
+    }
+    Ok(format!("{}{}", KEYS[(pitch % 12) as usize], pitch / 12 - 1))
+}
+
+// PySynth音符名转换为MIDI音高，休止符返回None
+pub fn note_to_pitch(note: &str) -> ResultType<Option<i32>> {
+    let mut note = note.trim_end_matches('*').to_string();
+    if note == "r" {
+        return Ok(None);
+    }
+    if !note
+        .chars()
+        .last()
+        .map(|c| c.is_ascii_digit())
+        .unwrap_or(false)
+    {
+        note.push('4');
+    }
+    let key = KEYNUM.get(&note).ok_or(anyhow!("非法音符: {}", note))?;
+    Ok(Some(*key as i32 + 21))
+}
+
+fn quantize(beats: f64) -> f64 {
+    (beats / QUANTUM).round() * QUANTUM
+}
+
+// 以四分音符为单位的时值转换为音符的周期部分，如1拍为4，过长的时值会被拆分
+fn push_note(track: &mut Vec<String>, name: &str, beats: f64) {
+    let mut rest = beats;
+    while rest > 1e-6 {
+        let current = rest.min(MAX_BEATS);
+        let text = format!("{:.3}", 4.0 / current);
+        track.push(format!(
+            "{}.{}",
+            name,
+            text.trim_end_matches('0').trim_end_matches('.')
+        ));
+        rest -= current;
+    }
+}
+
+// 将可能重叠的音符分配到多个单声部音轨中
+pub fn split_voices(mut notes: Vec<TimedNote>) -> ResultType<Vec<Vec<String>>> {
+    for note in notes.iter_mut() {
+        note.start = quantize(note.start);
+        note.length = quantize(note.length);
+    }
+    notes.retain(|v| v.length > 0.0);
+    notes.sort_by(|a, b| {
+        a.start
+            .partial_cmp(&b.start)
+            .unwrap()
+            .then(b.pitch.cmp(&a.pitch))
+    });
+    // 每个声部的当前结束时间与音符
+    let mut voices: Vec<(f64, Vec<String>)> = vec![];
+    for note in notes.into_iter() {
+        let name = pitch_to_note(note.pitch)?;
+        let slot = voices.iter_mut().find(|(end, _)| *end <= note.start + 1e-6);
+        let (end, track) = match slot {
+            Some(v) => v,
+            None => {
+                voices.push((0.0, vec![]));
+                voices.last_mut().unwrap()
+            }
+        };
+        if note.start - *end > 1e-6 {
+            push_note(track, "r", note.start - *end);
+        }
+        push_note(track, &name, note.length);
+        *end = note.start + note.length;
+    }
+    Ok(voices.into_iter().map(|(_, v)| v).collect())
+}
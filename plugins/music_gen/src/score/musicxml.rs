@@ -0,0 +1,123 @@
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::client::ResultType;
+use roxmltree::{Document, Node};
+
+use super::TimedNote;
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|v| v.has_tag_name(name))
+}
+
+// 不接受inf与NaN
+fn child_number(node: Node, name: &str) -> Option<f64> {
+    child(node, name)
+        .and_then(|v| v.text())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| v.is_finite())
+}
+
+// 时值必须为正数
+fn child_duration(node: Node) -> Option<f64> {
+    child_number(node, "duration").filter(|v| *v > 0.0)
+}
+
+fn parse_pitch(pitch: Node) -> ResultType<i32> {
+    let step = child(pitch, "step")
+        .and_then(|v| v.text())
+        .ok_or(anyhow!("音符缺少音名"))?
+        .trim();
+    let base = match step {
+        "C" => 0,
+        "D" => 2,
+        "E" => 4,
+        "F" => 5,
+        "G" => 7,
+        "A" => 9,
+        "B" => 11,
+        _ => return Err(anyhow!("非法音名: {}", step).into()),
+    };
+    let alter = child_number(pitch, "alter").unwrap_or(0.0).round() as i32;
+    let octave = child_number(pitch, "octave").ok_or(anyhow!("音符缺少八度"))? as i32;
+    Ok((octave + 1) * 12 + base + alter)
+}
+
+// 仅支持score-partwise格式，每个part作为一组声部
+pub fn parse_musicxml(text: &str) -> ResultType<(Vec<Vec<TimedNote>>, Option<u32>)> {
+    let doc = Document::parse(text).map_err(|e| anyhow!("解析MusicXML失败: {}", e))?;
+    let root = doc.root_element();
+    if !root.has_tag_name("score-partwise") {
+        return Err(anyhow!("仅支持score-partwise格式的MusicXML").into());
+    }
+    let bpm = doc
+        .descendants()
+        .filter(|v| v.has_tag_name("sound"))
+        .find_map(|v| v.attribute("tempo"))
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|v| v.is_finite())
+        .map(|v| v.round().max(1.0) as u32);
+    let mut voices = vec![];
+    for part in root.children().filter(|v| v.has_tag_name("part")) {
+        let mut divisions = 1.0;
+        // 当前位置与上一个音符的起始位置，以四分音符为单位
+        let mut time = 0.0;
+        let mut last_start = 0.0;
+        let mut notes: Vec<TimedNote> = vec![];
+        for measure in part.children().filter(|v| v.has_tag_name("measure")) {
+            for elem in measure.children().filter(|v| v.is_element()) {
+                match elem.tag_name().name() {
+                    "attributes" => {
+                        if let Some(v) = child_number(elem, "divisions").filter(|v| *v > 0.0) {
+                            divisions = v.max(1.0);
+                        }
+                    }
+                    "backup" => {
+                        time = (time - child_duration(elem).unwrap_or(0.0) / divisions).max(0.0);
+                    }
+                    "forward" => {
+                        time += child_duration(elem).unwrap_or(0.0) / divisions;
+                    }
+                    "note" => {
+                        // 倚音没有时值，直接忽略
+                        let duration = match child_duration(elem) {
+                            Some(v) => v / divisions,
+                            None => continue,
+                        };
+                        let start = if child(elem, "chord").is_some() {
+                            last_start
+                        } else {
+                            last_start = time;
+                            time += duration;
+                            last_start
+                        };
+                        let pitch = match child(elem, "pitch") {
+                            Some(v) => parse_pitch(v)?,
+                            None => continue,
+                        };
+                        let tie_stop = elem
+                            .children()
+                            .any(|v| v.has_tag_name("tie") && v.attribute("type") == Some("stop"));
+                        // 连音线连接的音符合并为一个
+                        if tie_stop {
+                            if let Some(prev) = notes.iter_mut().rev().find(|v| {
+                                v.pitch == pitch && (v.start + v.length - start).abs() < 1e-6
+                            }) {
+                                prev.length += duration;
+                                continue;
+                            }
+                        }
+                        notes.push(TimedNote {
+                            start,
+                            length: duration,
+                            pitch,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if !notes.is_empty() {
+            voices.push(notes);
+        }
+    }
+    Ok((voices, bpm))
+}
@@ -6,6 +6,7 @@ pub fn command_hash(
     major: &str,
     volume: &Option<Vec<u32>>,
     inverse_beats: &Option<i64>,
+    effects: &str,
 ) -> String {
     use sha2::Digest;
     let mut inst = sha2::Sha256::new();
@@ -31,5 +32,8 @@ pub fn command_hash(
     for s in notes.iter() {
         inst.update(s.as_bytes());
     }
+    if !effects.is_empty() {
+        inst.update(effects.as_bytes());
+    }
     return hex::encode(inst.finalize());
 }
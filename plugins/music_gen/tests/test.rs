@@ -1,4 +1,7 @@
+use music_gen::cache::{filesystem::FilesystemCache, is_valid_name, CacheBackend};
 use music_gen::notes::{parse_major, parse_note, transform_single_note};
+use music_gen::render::render_piano_roll;
+use music_gen::score::{import_score, midi::export_midi, ScoreFormat};
 
 #[test]
 fn normal_things() {
@@ -44,3 +47,134 @@ fn transform_single_note_normal() {
     assert_eq!(transform_single_note("#7*.5", 3).unwrap(), "d#5*.5");
     assert_eq!(transform_single_note("b1*.5", 8).unwrap(), "g4*.5");
 }
+
+#[test]
+fn import_abc() {
+    let text = "X:1\nL:1/4\nQ:1/4=100\nK:G\nG A B c | [CE]2 z f |";
+    let score = import_score(text.as_bytes(), ScoreFormat::Abc, 8).unwrap();
+    assert_eq!(score.bpm, Some(100));
+    assert_eq!(
+        score.tracks[0],
+        vec!["g4.4", "a4.4", "b4.4", "c5.4", "e4.2", "r.4", "f#5.4"]
+    );
+    assert_eq!(score.tracks[1], vec!["r.1", "c4.2"]);
+}
+
+#[test]
+fn import_malformed_scores() {
+    // 非法或过长的时值应当报错，而不是卡住或崩溃
+    assert!(import_score(b"L:1/0\nK:C\nC D", ScoreFormat::Abc, 8).is_err());
+    assert!(import_score(b"L:1/4\nK:C\nC z8192 D", ScoreFormat::Abc, 8).is_err());
+    assert!(import_score(b"L:1/4\nK:C\n(0C D", ScoreFormat::Abc, 8).is_ok());
+    let xml = "<score-partwise><part id=\"P1\"><measure><note><pitch><step>C</step>\
+        <octave>4</octave></pitch><duration>inf</duration></note></measure></part>\
+        </score-partwise>";
+    assert!(import_score(xml.as_bytes(), ScoreFormat::MusicXml, 8).is_err());
+}
+
+#[test]
+fn midi_round_trip() {
+    let tracks = vec![vec![
+        ("c4".to_string(), 4.0),
+        ("r".to_string(), 4.0),
+        ("g#5*".to_string(), -4.0),
+    ]];
+    let data = export_midi(&tracks, 90).unwrap();
+    assert_eq!(ScoreFormat::detect("", &data).unwrap(), ScoreFormat::Midi);
+    let score = import_score(&data, ScoreFormat::Midi, 8).unwrap();
+    assert_eq!(score.bpm, Some(90));
+    assert_eq!(score.tracks, vec![vec!["c4.4", "r.4", "g#5.2.667"]]);
+    // 超出范围的BPM在导出时被限制
+    let data = export_midi(&tracks, 1).unwrap();
+    assert_eq!(
+        import_score(&data, ScoreFormat::Midi, 8).unwrap().bpm,
+        Some(4)
+    );
+}
+
+#[test]
+fn render_piano_roll_png() {
+    let tracks = vec![
+        vec![("c4".to_string(), 4.0), ("e4*".to_string(), -4.0)],
+        vec![("r".to_string(), 2.0), ("g3".to_string(), 2.0)],
+    ];
+    let data = render_piano_roll(&tracks).unwrap();
+    assert!(data.starts_with(b"\x89PNG"));
+    assert!(render_piano_roll(&[vec![("r".to_string(), 4.0)]]).is_err());
+}
+
+// 写入带有指定访问时间的缓存文件与索引，之后通过open重新加载
+fn prepare_cache_dir(entries: &[(&str, &[u8], i64)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let mut index = serde_json::Map::new();
+    for (name, data, last_access) in entries.iter() {
+        std::fs::write(dir.path().join(name), data).unwrap();
+        index.insert(
+            name.to_string(),
+            serde_json::json!({"size": data.len(), "expire_at": i64::MAX, "last_access": last_access}),
+        );
+    }
+    std::fs::write(
+        dir.path().join("index.json"),
+        serde_json::Value::Object(index).to_string(),
+    )
+    .unwrap();
+    // 不在索引中的文件应当在打开时被删除
+    std::fs::write(dir.path().join("orphan"), b"x").unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn filesystem_cache_reload_and_lru() {
+    let dir = prepare_cache_dir(&[("a.mp3", b"aaaa", 100), ("b.mp3", b"bbbb", 200)]);
+    let cache = FilesystemCache::open(dir.path().to_path_buf(), 2, 1 << 20).unwrap();
+    assert!(!dir.path().join("orphan").exists());
+    assert_eq!(cache.get("b.mp3").await.unwrap(), Some(b"bbbb".to_vec()));
+    // 超出文件数时淘汰最久未访问的a.mp3
+    cache.put("c.mp3", b"cccc", 60).await.unwrap();
+    assert_eq!(cache.get("a.mp3").await.unwrap(), None);
+    assert!(!dir.path().join("a.mp3").exists());
+    assert!(cache.get("b.mp3").await.unwrap().is_some());
+    // 重新打开后索引保持一致
+    drop(cache);
+    let cache = FilesystemCache::open(dir.path().to_path_buf(), 2, 1 << 20).unwrap();
+    assert_eq!(cache.get("c.mp3").await.unwrap(), Some(b"cccc".to_vec()));
+    assert_eq!(cache.get("a.mp3").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn filesystem_cache_size_limit() {
+    let dir = prepare_cache_dir(&[("a.mp3", b"aaaa", 100), ("b.mp3", b"bbbb", 200)]);
+    let cache = FilesystemCache::open(dir.path().to_path_buf(), 10, 10).unwrap();
+    // 总大小12字节超出上限，淘汰a.mp3
+    cache.put("c.mp3", b"cccc", 60).await.unwrap();
+    assert_eq!(cache.get("a.mp3").await.unwrap(), None);
+    assert!(cache.get("b.mp3").await.unwrap().is_some());
+    assert!(cache.get("c.mp3").await.unwrap().is_some());
+    // 单个文件超出上限时不缓存
+    cache.put("d.mp3", &[0; 11], 60).await.unwrap();
+    assert_eq!(cache.get("d.mp3").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn filesystem_cache_expire() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = FilesystemCache::open(dir.path().to_path_buf(), 10, 1 << 20).unwrap();
+    cache.put("a.mp3", b"aaaa", 0).await.unwrap();
+    assert_eq!(cache.get("a.mp3").await.unwrap(), None);
+    assert!(!dir.path().join("a.mp3").exists());
+    cache.put("b.mp3", b"bbbb", 60).await.unwrap();
+    assert!(cache.get("b.mp3").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn filesystem_cache_invalid_name() {
+    for name in ["", ".mp3", "../a.mp3", "a/b.mp3", "a\\b", "index json"] {
+        assert!(!is_valid_name(name), "{}", name);
+    }
+    assert!(is_valid_name("0123abcdef.mp3"));
+    let dir = tempfile::tempdir().unwrap();
+    let cache = FilesystemCache::open(dir.path().to_path_buf(), 10, 1 << 20).unwrap();
+    assert!(cache.get("../a.mp3").await.is_err());
+    assert!(cache.put("../a.mp3", b"a", 60).await.is_err());
+}
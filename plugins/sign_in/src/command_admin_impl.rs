@@ -0,0 +1,134 @@
+use crate::SignInPlugin;
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::ResultType, command::SenderType, event::message::GroupSenderRole,
+    message::cq_code::escape,
+};
+
+// 解析 QQ号 或 [CQ:at,qq=xxx]
+fn parse_target(arg: &str) -> Option<i64> {
+    if let Some(rest) = arg.strip_prefix("[CQ:at,qq=") {
+        return rest
+            .split(|c| c == ',' || c == ']')
+            .next()?
+            .parse::<i64>()
+            .ok();
+    }
+    arg.parse::<i64>().ok().filter(|v| *v > 10000)
+}
+
+impl SignInPlugin {
+    async fn require_admin(&self, sender: &SenderType) -> ResultType<()> {
+        let evt = match sender {
+            SenderType::Group(e) => e,
+            _ => return Err(anyhow!("请在群内使用此指令!").into()),
+        };
+        let is_admin = matches!(
+            self.client.as_ref().unwrap().get_sender_role(evt).await?,
+            Some(GroupSenderRole::Owner) | Some(GroupSenderRole::Admin)
+        );
+        if !is_admin
+            && !self
+                .config
+                .as_ref()
+                .unwrap()
+                .super_users
+                .contains(&evt.user_id)
+        {
+            return Err(anyhow!("只有群主或管理员可以使用此指令!").into());
+        }
+        Ok(())
+    }
+    pub async fn command_adjust_score(
+        &self,
+        args: &Vec<String>,
+        sender: &SenderType,
+    ) -> ResultType<()> {
+        self.require_admin(sender).await?;
+        let (group_id, user_id) = self.score_group_sender(sender)?;
+        let target = args
+            .first()
+            .and_then(|v| parse_target(v))
+            .ok_or(anyhow!("请指定目标用户(QQ号或@)!"))?;
+        let score_changes = args
+            .get(1)
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v != 0)
+            .ok_or(anyhow!("请输入合法的积分变化量!"))?;
+        let reason = args[2..].join(" ");
+        let reason = if reason.trim().is_empty() {
+            String::from("管理员调整")
+        } else {
+            format!("管理员调整: {}", reason.trim())
+        };
+        let score = self
+            .change_score(group_id, target, user_id, score_changes, &reason, false)
+            .await?;
+        self.client
+            .as_ref()
+            .unwrap()
+            .quick_send_by_sender_ex(
+                sender,
+                &format!(
+                    "已将 {} 的积分调整 {:+}，当前积分：{}\n操作者：{}",
+                    target, score_changes, score, user_id
+                ),
+                false,
+            )
+            .await?;
+        return Ok(());
+    }
+    pub async fn command_score_logs(
+        &self,
+        args: &Vec<String>,
+        sender: &SenderType,
+    ) -> ResultType<()> {
+        use chrono::prelude::*;
+        self.require_admin(sender).await?;
+        let (group_id, _) = self.score_group_sender(sender)?;
+        let page = match args.first().filter(|v| !v.is_empty()) {
+            Some(v) => v
+                .parse::<i64>()
+                .ok()
+                .filter(|v| *v >= 1)
+                .ok_or(anyhow!("请输入合法的页码!"))?,
+            None => 1,
+        };
+        let page_size = self.config.as_ref().unwrap().ranklist_page_size.max(1);
+        let (total, logs) = self
+            .get_score_logs(group_id, (page - 1) * page_size, page_size)
+            .await?;
+        if total == 0 {
+            return Err(anyhow!("本群暂无积分变动记录!").into());
+        }
+        let page_count = (total + page_size - 1) / page_size;
+        if page > page_count {
+            return Err(anyhow!("页码超出范围，共{}页!", page_count).into());
+        }
+        let names = self.get_member_names(group_id).await;
+        let name_of = |id: i64| names.get(&id).cloned().unwrap_or(id.to_string());
+        let mut buf = format!("积分变动记录 (第{}/{}页)\n", page, page_count);
+        for log in logs.iter() {
+            let time_str = Local
+                .timestamp_opt(log.time, 0)
+                .single()
+                .map(|v| v.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or("时间格式错误".to_string());
+            buf.push_str(&format!(
+                "{} {} {:+} -> {} ({}，操作者: {})\n",
+                time_str,
+                name_of(log.user_id),
+                log.score_changes,
+                log.score,
+                escape(&log.reason, false),
+                name_of(log.operator_id)
+            ));
+        }
+        self.client
+            .as_ref()
+            .unwrap()
+            .quick_send_by_sender_ex(sender, buf.trim_end(), false)
+            .await?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use crate::{models::RanklistKind, SignInPlugin};
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::ResultType, command::SenderType, message::cq_code::escape,
+};
+use log::error;
+
+impl SignInPlugin {
+    //返回群成员的群名片(为空时为昵称)，获取失败时返回空表
+    //名字已转义，可直接以不转义的方式发送
+    pub async fn get_member_names(&self, group_id: i64) -> HashMap<i64, String> {
+        match self
+            .client
+            .as_ref()
+            .unwrap()
+            .get_group_member_list(group_id)
+            .await
+        {
+            Ok(v) => v
+                .into_iter()
+                .map(|m| {
+                    let name = if m.card.is_empty() {
+                        m.nickname
+                    } else {
+                        m.card
+                    };
+                    (m.user_id, escape(&name, false))
+                })
+                .collect(),
+            Err(e) => {
+                error!("Failed to fetch member list of {}: {}", group_id, e);
+                HashMap::new()
+            }
+        }
+    }
+    pub async fn command_ranklist(
+        &self,
+        args: &Vec<String>,
+        sender: &SenderType,
+    ) -> ResultType<()> {
+        use chrono::prelude::*;
+        let group_id = match sender {
+            SenderType::Group(e) => e.group_id,
+            _ => todo!(),
+        };
+        let config = self.config.as_ref().unwrap();
+        let mut kind = RanklistKind::Today;
+        let mut page: i64 = 1;
+        for arg in args.iter().filter(|v| !v.is_empty()) {
+            if let Some(v) = RanklistKind::from_name(arg) {
+                kind = v;
+            } else {
+                page = arg.parse::<i64>().ok().filter(|v| *v >= 1).ok_or(anyhow!(
+                    "请输入合法的排行榜类型(今日、本月、积分、连续)或页码!"
+                ))?;
+            }
+        }
+        if kind == RanklistKind::Score && config.hide_score_groups.contains(&group_id) {
+            return Err(anyhow!("本群隐藏了积分!").into());
+        }
+        let page_size = config.ranklist_page_size.max(1);
+        let (total, items) = self
+            .get_ranklist(group_id, kind, (page - 1) * page_size, page_size)
+            .await?;
+        if total == 0 {
+            return Err(anyhow!("暂无排行数据!").into());
+        }
+        let page_count = (total + page_size - 1) / page_size;
+        if page > page_count {
+            return Err(anyhow!("页码超出范围，共{}页!", page_count).into());
+        }
+        let names = self.get_member_names(group_id).await;
+        let mut buf = format!("{} (第{}/{}页)\n", kind.title(), page, page_count);
+        for (index, item) in items.iter().enumerate() {
+            let value = match kind {
+                RanklistKind::Today => NaiveDateTime::from_timestamp_opt(item.value, 0)
+                    .map(|v| v.format("%H:%M:%S").to_string())
+                    .unwrap_or("时间格式错误".to_string()),
+                RanklistKind::Month => format!("{}次", item.value),
+                RanklistKind::Score => format!("{}分", item.value),
+                RanklistKind::Streak => format!("{}天", item.value),
+            };
+            buf.push_str(&format!(
+                "{}. {} {}\n",
+                (page - 1) * page_size + index as i64 + 1,
+                names
+                    .get(&item.user_id)
+                    .cloned()
+                    .unwrap_or(item.user_id.to_string()),
+                value
+            ));
+        }
+        self.client
+            .as_ref()
+            .unwrap()
+            .quick_send_by_sender_ex(sender, buf.trim_end(), false)
+            .await?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    database::{self, REPAIR_CARD},
+    SignInPlugin,
+};
+use anyhow::anyhow;
+use countdown_bot3::countdown_bot::{
+    client::ResultType,
+    command::SenderType,
+    message::cq_code::{escape, unescape},
+};
+use log::error;
+
+impl SignInPlugin {
+    // 返回群号与QQ号，签到停用或隐藏积分的群不能使用积分相关功能
+    pub fn score_group_sender(&self, sender: &SenderType) -> ResultType<(i64, i64)> {
+        let (group_id, user_id) = match sender {
+            SenderType::Group(e) => (e.group_id, e.user_id),
+            _ => todo!(),
+        };
+        let config = self.config.as_ref().unwrap();
+        if config.black_list_groups.contains(&group_id) {
+            return Err(anyhow!("签到功能在本群停用!").into());
+        }
+        if config.hide_score_groups.contains(&group_id) {
+            return Err(anyhow!("本群隐藏了积分，无法使用此功能!").into());
+        }
+        Ok((group_id, user_id))
+    }
+    pub async fn command_shop(&self, sender: &SenderType) -> ResultType<()> {
+        let (group_id, user_id) = self.score_group_sender(sender)?;
+        let config = self.config.as_ref().unwrap();
+        let buf = format!(
+            "[CQ:at,qq={}]\n当前积分：{}\n持有补签卡：{}张\n\n补签卡 - {}积分\n断签不超过{}天时，签到会自动使用补签卡保持连续签到(每天一张)\n\n头衔 - {}积分\n设置本群的专属头衔，最多{}个字\n\n使用 签到购买 补签卡 [数量] 或 签到购买 头衔 <头衔> 购买",
+            user_id,
+            self.get_score(group_id, user_id).await?,
+            self.get_item_count(group_id, user_id, REPAIR_CARD).await?,
+            config.repair_card_price,
+            config.max_repair_days,
+            config.title_price,
+            config.max_title_length
+        );
+        self.client
+            .as_ref()
+            .unwrap()
+            .quick_send_by_sender_ex(sender, &buf, false)
+            .await?;
+        return Ok(());
+    }
+    pub async fn command_buy(&self, args: &Vec<String>, sender: &SenderType) -> ResultType<()> {
+        let (group_id, user_id) = self.score_group_sender(sender)?;
+        let config = self.config.as_ref().unwrap();
+        let client = self.client.as_ref().unwrap();
+        let item = args.first().map(|v| v.as_str()).unwrap_or("");
+        let buf = match item {
+            "补签卡" => {
+                let count = match args.get(1) {
+                    Some(v) => v
+                        .parse::<i64>()
+                        .ok()
+                        .filter(|v| *v >= 1 && *v <= 100)
+                        .ok_or(anyhow!("请输入合法的数量(1~100)!"))?,
+                    None => 1,
+                };
+                let (score, owned) = {
+                    let mut db = self.database.as_ref().unwrap().lock().await;
+                    database::buy_item(
+                        &mut db,
+                        group_id,
+                        user_id,
+                        REPAIR_CARD,
+                        count,
+                        config.repair_card_price * count,
+                        &format!("购买补签卡x{}", count),
+                    )?
+                };
+                format!(
+                    "[CQ:at,qq={}]购买成功！\n持有补签卡：{}张\n剩余积分：{}",
+                    user_id, owned, score
+                )
+            }
+            "头衔" => {
+                // 参数来自原始消息，其中的CQ码(图片、表情等)不能作为头衔，转义的文本需要还原
+                let title = args[1..].join(" ");
+                if title.contains("[CQ:") {
+                    return Err(anyhow!("头衔只能包含文字!").into());
+                }
+                let title = unescape(&title);
+                let title = title.trim();
+                if title.is_empty() {
+                    return Err(anyhow!("请输入头衔!").into());
+                }
+                if title.chars().count() > config.max_title_length {
+                    return Err(anyhow!("头衔最多{}个字!", config.max_title_length).into());
+                }
+                let score = self
+                    .change_score(
+                        group_id,
+                        user_id,
+                        user_id,
+                        -config.title_price,
+                        &format!("购买头衔: {}", title),
+                        true,
+                    )
+                    .await?;
+                // 设置失败时退还积分
+                if let Err(e) = client
+                    .set_group_special_title(group_id as u64, user_id as u64, title, -1)
+                    .await
+                {
+                    error!("Failed to set special title: {}", e);
+                    self.change_score(
+                        group_id,
+                        user_id,
+                        user_id,
+                        config.title_price,
+                        "设置头衔失败，退还积分",
+                        false,
+                    )
+                    .await?;
+                    return Err(
+                        anyhow!("设置头衔失败(机器人可能不是群主)，积分已退还: {}", e).into(),
+                    );
+                }
+                format!(
+                    "[CQ:at,qq={}]头衔已设置为：{}\n剩余积分：{}",
+                    user_id,
+                    escape(title, false),
+                    score
+                )
+            }
+            _ => {
+                return Err(anyhow!(
+                    "请输入要购买的物品: 签到购买 补签卡 [数量] 或 签到购买 头衔 <头衔>"
+                )
+                .into())
+            }
+        };
+        client.quick_send_by_sender_ex(sender, &buf, false).await?;
+        return Ok(());
+    }
+}
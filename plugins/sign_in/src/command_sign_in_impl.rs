@@ -1,7 +1,7 @@
 use countdown_bot3::countdown_bot::{client::ResultType, command::SenderType};
 use log::debug;
 
-use crate::{models::SignInData, SignInPlugin};
+use crate::{database, SignInPlugin};
 use anyhow::anyhow;
 impl SignInPlugin {
     pub async fn command_signin(&self, sender: &SenderType) -> ResultType<()> {
@@ -38,7 +38,10 @@ impl SignInPlugin {
                 user_id, last_sign_in_data.duration
             ));
             if !config.hide_score_groups.contains(&group_id) {
-                buf.push_str(&format!("当前积分: {}\n", last_sign_in_data.score));
+                buf.push_str(&format!(
+                    "当前积分: {}\n",
+                    self.get_score(group_id, user_id).await?
+                ));
             }
             buf.push_str(&format!(
                 "本月签到次数: {}\n累计群签到次数: {}",
@@ -47,31 +50,22 @@ impl SignInPlugin {
             client.quick_send_by_sender_ex(sender, &buf, false).await?;
             return Ok(());
         }
-        let mut sign_in_data = SignInData::new(group_id, user_id);
-        sign_in_data.time = current_time.timestamp();
-        if last_time.num_days_from_ce() + 1 == current_time.num_days_from_ce() {
-            sign_in_data.duration = last_sign_in_data.duration + 1;
-        } else {
-            sign_in_data.duration = 1;
-        }
-        let mut duration_add = sign_in_data.duration - 1;
-        if duration_add > 10 {
-            duration_add = 10;
-        }
-        if sign_in_data.duration > 30 {
-            sign_in_data.duration = 15;
-        }
-        /*
-        # 连续签到加成计算：
-        # 2-10天：天数-1
-        # 10-30天：10
-        # 大于30天：15
-        */
-        sign_in_data.score_changes = 10 + duration_add;
-        sign_in_data.score = last_sign_in_data.score + sign_in_data.score_changes;
-        self.save_data(&sign_in_data).await?;
+        let result = {
+            let mut db = self.database.as_ref().unwrap().lock().await;
+            database::sign_in(
+                &mut db,
+                &last_sign_in_data,
+                current_time,
+                config.max_repair_days,
+            )?
+        };
+        let (sign_in_data, duration_add, used_cards) =
+            (result.data, result.duration_add, result.used_cards);
         let mut buf = String::new();
         buf.push_str(&format!("给[CQ:at,qq={}]签到成功了！\n", user_id));
+        if used_cards > 0 {
+            buf.push_str(&format!("使用了{}张补签卡，连续签到未中断\n", used_cards));
+        }
         buf.push_str(&format!("连续签到：{}天\n", sign_in_data.duration));
         if !config.hide_score_groups.contains(&group_id) {
             buf.push_str(&format!(
@@ -0,0 +1,359 @@
+use crate::models::{RanklistItem, RanklistKind, SignInData, SignInResult};
+use anyhow::anyhow;
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use countdown_bot3::countdown_bot::client::ResultType;
+use fallible_iterator::FallibleIterator;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+
+pub const REPAIR_CARD: &str = "repair_card";
+
+pub fn create_tables(db: &Connection) -> ResultType<()> {
+    db.execute(
+        r#"CREATE TABLE IF NOT EXISTS SIGNINS(
+            GROUP_ID      INTEGER NOT NULL,
+            USER_ID       INTEGER NOT NULL,
+            TIME          INTEGER NOT NULL,
+            DURATION      INTEGER NOT NULL,
+            SCORE         INTEGER NOT NULL,
+            SCORE_CHANGES INTEGER NOT NULL
+        )"#,
+        params![],
+    )
+    .map_err(|e| anyhow!("创建表 SIGNINS 时发生错误: {}", e))?;
+    db.execute(
+        r#"CREATE TABLE IF NOT EXISTS USERS(
+            GROUP_ID INTEGER NOT NULL,
+            USER_ID  INTEGER NOT NULL,
+            SCORE    INTEGER NOT NULL
+        )"#,
+        params![],
+    )
+    .map_err(|e| anyhow!("创建表 USERS 时发生错误: {}", e))?;
+    db.execute(
+        r#"CREATE TABLE IF NOT EXISTS ITEMS(
+            GROUP_ID INTEGER NOT NULL,
+            USER_ID  INTEGER NOT NULL,
+            ITEM     TEXT    NOT NULL,
+            COUNT    INTEGER NOT NULL
+        )"#,
+        params![],
+    )
+    .map_err(|e| anyhow!("创建表 ITEMS 时发生错误: {}", e))?;
+    db.execute(
+        r#"CREATE TABLE IF NOT EXISTS SCORE_LOGS(
+            GROUP_ID      INTEGER NOT NULL,
+            USER_ID       INTEGER NOT NULL,
+            OPERATOR_ID   INTEGER NOT NULL,
+            TIME          INTEGER NOT NULL,
+            SCORE_CHANGES INTEGER NOT NULL,
+            SCORE         INTEGER NOT NULL,
+            REASON        TEXT    NOT NULL
+        )"#,
+        params![],
+    )
+    .map_err(|e| anyhow!("创建表 SCORE_LOGS 时发生错误: {}", e))?;
+    db.execute(
+        "CREATE INDEX SIGNIN_GROUP_ID_INDEX ON SIGNINS(GROUP_ID)",
+        params![],
+    )
+    .ok();
+    db.execute(
+        "CREATE INDEX SIGNIN_USER_ID_INDEX  ON SIGNINS(USER_ID)",
+        params![],
+    )
+    .ok();
+    db.execute(
+        "CREATE INDEX SIGNIN_TIME_INDEX     ON SIGNINS(TIME)",
+        params![],
+    )
+    .ok();
+    db.execute(
+        "CREATE INDEX USERS_GROUP_ID_INDEX  ON USERS(GROUP_ID)",
+        params![],
+    )
+    .ok();
+    db.execute(
+        "CREATE INDEX USERS_USER_ID_INDEX   ON USERS(USER_ID)",
+        params![],
+    )
+    .ok();
+    db.execute(
+        "CREATE INDEX ITEMS_GROUP_USER_INDEX ON ITEMS(GROUP_ID, USER_ID)",
+        params![],
+    )
+    .ok();
+    db.execute(
+        "CREATE INDEX SCORE_LOGS_GROUP_ID_INDEX ON SCORE_LOGS(GROUP_ID)",
+        params![],
+    )
+    .ok();
+
+    Ok(())
+}
+pub fn save_data(db: &Connection, sign_in_data: &SignInData) -> ResultType<()> {
+    db.execute("INSERT INTO SIGNINS (GROUP_ID,USER_ID,TIME,DURATION,SCORE,SCORE_CHANGES) VALUES (?,?,?,?,?,?)", params![
+        sign_in_data.group_id,
+        sign_in_data.user_id,
+        sign_in_data.time,
+        sign_in_data.duration,
+        sign_in_data.score,
+        sign_in_data.score_changes
+    ])?;
+    let exists = {
+        let mut stmt = db.prepare("SELECT * FROM USERS WHERE GROUP_ID = ? AND USER_ID = ?")?;
+        stmt.exists(params![sign_in_data.group_id, sign_in_data.user_id])?
+    };
+    if exists {
+        db.execute(
+            "UPDATE USERS SET SCORE = ? WHERE GROUP_ID = ? AND USER_ID = ?",
+            params![
+                sign_in_data.score,
+                sign_in_data.group_id,
+                sign_in_data.user_id
+            ],
+        )?;
+    } else {
+        db.execute(
+            "INSERT INTO USERS (GROUP_ID,USER_ID,SCORE) VALUES (?,?,?)",
+            params![
+                sign_in_data.group_id,
+                sign_in_data.user_id,
+                sign_in_data.score
+            ],
+        )?;
+    }
+    return Ok(());
+}
+//返回某群某人的当前积分
+pub fn get_score(db: &Connection, group_id: i64, user_id: i64) -> ResultType<i64> {
+    let score = db
+        .query_row(
+            "SELECT SCORE FROM USERS WHERE GROUP_ID = ? AND USER_ID = ?",
+            params![group_id, user_id],
+            |r| r.get(0),
+        )
+        .optional()?;
+    return Ok(score.unwrap_or(0));
+}
+/*
+修改某群某人的积分，并记录到 SCORE_LOGS
+    check_balance 为 true 时，积分不足则失败
+    返回修改后的积分
+*/
+pub fn change_score(
+    db: &Connection,
+    group_id: i64,
+    user_id: i64,
+    operator_id: i64,
+    score_changes: i64,
+    reason: &str,
+    check_balance: bool,
+) -> ResultType<i64> {
+    let current: Option<i64> = db
+        .query_row(
+            "SELECT SCORE FROM USERS WHERE GROUP_ID = ? AND USER_ID = ?",
+            params![group_id, user_id],
+            |r| r.get(0),
+        )
+        .optional()?;
+    let score = current.unwrap_or(0) + score_changes;
+    if check_balance && score < 0 {
+        return Err(anyhow!(
+            "积分不足! 当前积分: {}，需要: {}",
+            current.unwrap_or(0),
+            -score_changes
+        )
+        .into());
+    }
+    if current.is_some() {
+        db.execute(
+            "UPDATE USERS SET SCORE = ? WHERE GROUP_ID = ? AND USER_ID = ?",
+            params![score, group_id, user_id],
+        )?;
+    } else {
+        db.execute(
+            "INSERT INTO USERS (GROUP_ID,USER_ID,SCORE) VALUES (?,?,?)",
+            params![group_id, user_id, score],
+        )?;
+    }
+    db.execute(
+        "INSERT INTO SCORE_LOGS (GROUP_ID,USER_ID,OPERATOR_ID,TIME,SCORE_CHANGES,SCORE,REASON) VALUES (?,?,?,?,?,?,?)",
+        params![
+            group_id,
+            user_id,
+            operator_id,
+            chrono::Local::now().timestamp(),
+            score_changes,
+            score,
+            reason
+        ],
+    )?;
+    return Ok(score);
+}
+pub fn get_item_count(db: &Connection, group_id: i64, user_id: i64, item: &str) -> ResultType<i64> {
+    let count = db
+        .query_row(
+            "SELECT COUNT FROM ITEMS WHERE GROUP_ID = ? AND USER_ID = ? AND ITEM = ?",
+            params![group_id, user_id, item],
+            |r| r.get(0),
+        )
+        .optional()?;
+    return Ok(count.unwrap_or(0));
+}
+/*
+修改某群某人持有的物品数量
+    数量不足时失败，返回修改后的数量
+*/
+pub fn change_item_count(
+    db: &Connection,
+    group_id: i64,
+    user_id: i64,
+    item: &str,
+    changes: i64,
+) -> ResultType<i64> {
+    let current: Option<i64> = db
+        .query_row(
+            "SELECT COUNT FROM ITEMS WHERE GROUP_ID = ? AND USER_ID = ? AND ITEM = ?",
+            params![group_id, user_id, item],
+            |r| r.get(0),
+        )
+        .optional()?;
+    let count = current.unwrap_or(0) + changes;
+    if count < 0 {
+        return Err(anyhow!("物品数量不足!").into());
+    }
+    if current.is_some() {
+        db.execute(
+            "UPDATE ITEMS SET COUNT = ? WHERE GROUP_ID = ? AND USER_ID = ? AND ITEM = ?",
+            params![count, group_id, user_id, item],
+        )?;
+    } else {
+        db.execute(
+            "INSERT INTO ITEMS (GROUP_ID,USER_ID,ITEM,COUNT) VALUES (?,?,?,?)",
+            params![group_id, user_id, item, count],
+        )?;
+    }
+    return Ok(count);
+}
+/*
+花费积分购买物品，扣除积分与增加物品在同一事务中完成
+    返回剩余积分与持有的物品数量
+*/
+pub fn buy_item(
+    db: &mut Connection,
+    group_id: i64,
+    user_id: i64,
+    item: &str,
+    count: i64,
+    price: i64,
+    reason: &str,
+) -> ResultType<(i64, i64)> {
+    let tx = db.transaction()?;
+    let score = change_score(&tx, group_id, user_id, user_id, -price, reason, true)?;
+    let owned = change_item_count(&tx, group_id, user_id, item, count)?;
+    tx.commit()?;
+    return Ok((score, owned));
+}
+/*
+在 now 时为上一次签到记录为 last 的用户签到
+    断签的天数不超过 max_repair_days 且补签卡足够时，自动使用补签卡保持连续签到
+    使用补签卡与保存签到记录在同一事务中完成
+*/
+pub fn sign_in(
+    db: &mut Connection,
+    last: &SignInData,
+    now: NaiveDateTime,
+    max_repair_days: i64,
+) -> ResultType<SignInResult> {
+    let tx = db.transaction()?;
+    let (group_id, user_id) = (last.group_id, last.user_id);
+    let last_time = NaiveDateTime::from_timestamp(last.time, 0);
+    let mut sign_in_data = SignInData::new(group_id, user_id);
+    sign_in_data.time = now.timestamp();
+    let missed_days = (now.num_days_from_ce() - last_time.num_days_from_ce() - 1) as i64;
+    let mut used_cards = 0;
+    if missed_days > 0
+        && last.time != 0
+        && missed_days <= max_repair_days
+        && get_item_count(&tx, group_id, user_id, REPAIR_CARD)? >= missed_days
+    {
+        change_item_count(&tx, group_id, user_id, REPAIR_CARD, -missed_days)?;
+        used_cards = missed_days;
+    }
+    if missed_days == 0 || used_cards > 0 {
+        sign_in_data.duration = last.duration + 1;
+    } else {
+        sign_in_data.duration = 1;
+    }
+    let mut duration_add = sign_in_data.duration - 1;
+    if duration_add > 10 {
+        duration_add = 10;
+    }
+    /*
+    # 连续签到加成计算：
+    # 2-10天：天数-1
+    # 大于10天：10
+    # DURATION记录实际的连续天数，用于连续签到排行
+    */
+    sign_in_data.score_changes = 10 + duration_add;
+    sign_in_data.score = get_score(&tx, group_id, user_id)? + sign_in_data.score_changes;
+    save_data(&tx, &sign_in_data)?;
+    tx.commit()?;
+    return Ok(SignInResult {
+        data: sign_in_data,
+        duration_add,
+        used_cards,
+    });
+}
+//返回某群排行榜的总人数，以及从 offset 开始的 limit 项，today 为当前的本地日期
+pub fn get_ranklist(
+    db: &Connection,
+    group_id: i64,
+    kind: RanklistKind,
+    today: NaiveDate,
+    offset: i64,
+    limit: i64,
+) -> ResultType<(i64, Vec<RanklistItem>)> {
+    // 签到时间按本地时间存储
+    let (query, time_begin) = match kind {
+        RanklistKind::Today => (
+            "SELECT USER_ID, MIN(TIME) AS VALUE FROM SIGNINS WHERE GROUP_ID = ? AND TIME >= ? \
+             GROUP BY USER_ID ORDER BY VALUE ASC",
+            Some(today.and_hms(0, 0, 0).timestamp()),
+        ),
+        RanklistKind::Month => (
+            "SELECT USER_ID, COUNT(*) AS VALUE FROM SIGNINS WHERE GROUP_ID = ? AND TIME >= ? \
+             GROUP BY USER_ID ORDER BY VALUE DESC, MIN(TIME) ASC",
+            Some(today.with_day(1).unwrap().and_hms(0, 0, 0).timestamp()),
+        ),
+        RanklistKind::Score => (
+            "SELECT USER_ID, SCORE AS VALUE FROM USERS WHERE GROUP_ID = ? ORDER BY VALUE DESC",
+            None,
+        ),
+        // 旧版本在连续超过30天时将DURATION重置为15，此前的记录可能偏小
+        RanklistKind::Streak => (
+            "SELECT USER_ID, MAX(DURATION) AS VALUE FROM SIGNINS WHERE GROUP_ID = ? \
+             GROUP BY USER_ID ORDER BY VALUE DESC",
+            None,
+        ),
+    };
+    let mut values = vec![group_id];
+    values.extend(time_begin);
+    let total = db.query_row(
+        &format!("SELECT COUNT(*) FROM ({})", query),
+        params_from_iter(values.iter()),
+        |r| r.get(0),
+    )?;
+    values.extend([limit, offset]);
+    let mut stmt = db.prepare(&format!("{} LIMIT ? OFFSET ?", query))?;
+    let items = stmt
+        .query(params_from_iter(values.iter()))?
+        .map(|r| {
+            Ok(RanklistItem {
+                user_id: r.get(0)?,
+                value: r.get(1)?,
+            })
+        })
+        .collect::<Vec<RanklistItem>>()?;
+    return Ok((total, items));
+}
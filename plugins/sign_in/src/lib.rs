@@ -10,22 +10,34 @@ use countdown_bot3::{
     },
     export_static_plugin,
 };
-use rusqlite::{params, Connection};
+use rusqlite::Connection;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 static PLUGIN_NAME: &str = "sign_in";
 use serde::{Deserialize, Serialize};
 
+mod command_admin_impl;
 mod command_group_query_impl;
+mod command_ranklist_impl;
+mod command_shop_impl;
 mod command_sign_in_impl;
 mod command_user_query_impl;
+pub mod database;
 mod misc_impl;
-mod models;
+pub mod models;
 
 #[derive(Deserialize, Serialize)]
 pub struct SignInConfig {
     pub black_list_groups: Vec<i64>,
     pub hide_score_groups: Vec<i64>,
+    // 除群主与管理员外，可以调整积分的用户
+    pub super_users: Vec<i64>,
+    pub ranklist_page_size: i64,
+    pub repair_card_price: i64,
+    // 断签超过此天数时不能使用补签卡
+    pub max_repair_days: i64,
+    pub title_price: i64,
+    pub max_title_length: usize,
 }
 
 impl Default for SignInConfig {
@@ -33,6 +45,12 @@ impl Default for SignInConfig {
         Self {
             black_list_groups: vec![888888888],
             hide_score_groups: vec![888888888],
+            super_users: vec![],
+            ranklist_page_size: 10,
+            repair_card_price: 100,
+            max_repair_days: 3,
+            title_price: 500,
+            max_title_length: 6,
         }
     }
 }
@@ -85,11 +103,39 @@ impl BotPlugin for SignInPlugin {
                 .description("签到记录查询 | 签到记录 [月份(可选)] [年份(可选)]"),
         )
         .unwrap();
+        bot.register_command(
+            Command::new("签到排行")
+                .group(true)
+                .description("签到排行榜 | 签到排行 [今日|本月|积分|连续(可选)] [页码(可选)]"),
+        )
+        .unwrap();
+        bot.register_command(
+            Command::new("签到商店")
+                .group(true)
+                .description("查看可以用积分购买的物品"),
+        )
+        .unwrap();
+        bot.register_command(
+            Command::new("签到购买").group(true).description(
+                "使用积分购买物品 | 签到购买 补签卡 [数量(可选)] | 签到购买 头衔 <头衔>",
+            ),
+        )
+        .unwrap();
+        bot.register_command(
+            Command::new("积分调整")
+                .group(true)
+                .description("调整群成员的积分(管理员) | 积分调整 <QQ号或@> <变化量> [原因(可选)]"),
+        )
+        .unwrap();
+        bot.register_command(
+            Command::new("积分日志")
+                .group(true)
+                .description("查看本群的积分变动记录(管理员) | 积分日志 [页码(可选)]"),
+        )
+        .unwrap();
         let cloned = self.database.as_ref().unwrap().clone();
         tokio::spawn(async move {
-            init_database(cloned)
-                .await
-                .expect("初始化数据库时发生错误!");
+            database::create_tables(&*cloned.lock().await).expect("初始化数据库时发生错误!");
         });
         Ok(())
     }
@@ -124,6 +170,21 @@ impl BotPlugin for SignInPlugin {
             "签到记录" => {
                 self.command_group_query(&args, sender).await?;
             }
+            "签到排行" => {
+                self.command_ranklist(&args, sender).await?;
+            }
+            "签到商店" => {
+                self.command_shop(sender).await?;
+            }
+            "签到购买" => {
+                self.command_buy(&args, sender).await?;
+            }
+            "积分调整" => {
+                self.command_adjust_score(&args, sender).await?;
+            }
+            "积分日志" => {
+                self.command_score_logs(&args, sender).await?;
+            }
             _ => todo!(),
         };
         return Ok(());
@@ -131,54 +192,3 @@ impl BotPlugin for SignInPlugin {
 }
 
 export_static_plugin!(PLUGIN_NAME, SignInPlugin::default());
-async fn init_database(conn: Arc<Mutex<Connection>>) -> ResultType<()> {
-    let db = conn.lock().await;
-    db.execute(
-        r#"CREATE TABLE IF NOT EXISTS SIGNINS(
-            GROUP_ID      INTEGER NOT NULL,
-            USER_ID       INTEGER NOT NULL,
-            TIME          INTEGER NOT NULL,
-            DURATION      INTEGER NOT NULL,
-            SCORE         INTEGER NOT NULL,
-            SCORE_CHANGES INTEGER NOT NULL
-        )"#,
-        params![],
-    )
-    .map_err(|e| anyhow!("创建表 SIGNINS 时发生错误: {}", e))?;
-    db.execute(
-        r#"CREATE TABLE IF NOT EXISTS USERS(
-            GROUP_ID INTEGER NOT NULL,
-            USER_ID  INTEGER NOT NULL,
-            SCORE    INTEGER NOT NULL
-        )"#,
-        params![],
-    )
-    .map_err(|e| anyhow!("创建表 USERS 时发生错误: {}", e))?;
-    db.execute(
-        "CREATE INDEX SIGNIN_GROUP_ID_INDEX ON SIGNINS(GROUP_ID)",
-        params![],
-    )
-    .ok();
-    db.execute(
-        "CREATE INDEX SIGNIN_USER_ID_INDEX  ON SIGNINS(USER_ID)",
-        params![],
-    )
-    .ok();
-    db.execute(
-        "CREATE INDEX SIGNIN_TIME_INDEX     ON SIGNINS(TIME)",
-        params![],
-    )
-    .ok();
-    db.execute(
-        "CREATE INDEX USERS_GROUP_ID_INDEX  ON USERS(GROUP_ID)",
-        params![],
-    )
-    .ok();
-    db.execute(
-        "CREATE INDEX USERS_USER_ID_INDEX   ON USERS(USER_ID)",
-        params![],
-    )
-    .ok();
-
-    Ok(())
-}
@@ -1,5 +1,6 @@
 use crate::{
-    models::{SignInData, UserData},
+    database,
+    models::{RanklistItem, RanklistKind, ScoreLog, SignInData, UserData},
     SignInPlugin,
 };
 use chrono::{Datelike, TimeZone};
@@ -176,39 +177,78 @@ impl SignInPlugin {
 
         return Ok(vec);
     }
-    pub async fn save_data(&self, sign_in_data: &SignInData) -> ResultType<()> {
+    //返回某群某人的当前积分
+    pub async fn get_score(&self, group_id: i64, user_id: i64) -> ResultType<i64> {
         let db = self.database.as_ref().unwrap().lock().await;
-        db.execute("INSERT INTO SIGNINS (GROUP_ID,USER_ID,TIME,DURATION,SCORE,SCORE_CHANGES) VALUES (?,?,?,?,?,?)", params![
-            sign_in_data.group_id,
-            sign_in_data.user_id,
-            sign_in_data.time,
-            sign_in_data.duration,
-            sign_in_data.score,
-            sign_in_data.score_changes
-        ])?;
-        let exists = {
-            let mut stmt = db.prepare("SELECT * FROM USERS WHERE GROUP_ID = ? AND USER_ID = ?")?;
-            stmt.exists(params![sign_in_data.group_id, sign_in_data.user_id])?
-        };
-        if exists {
-            db.execute(
-                "UPDATE USERS SET SCORE = ? WHERE GROUP_ID = ? AND USER_ID = ?",
-                params![
-                    sign_in_data.score,
-                    sign_in_data.group_id,
-                    sign_in_data.user_id
-                ],
-            )?;
-        } else {
-            db.execute(
-                "INSERT INTO USERS (GROUP_ID,USER_ID,SCORE) VALUES (?,?,?)",
-                params![
-                    sign_in_data.group_id,
-                    sign_in_data.user_id,
-                    sign_in_data.score
-                ],
-            )?;
-        }
-        return Ok(());
+        database::get_score(&db, group_id, user_id)
+    }
+    //修改某群某人的积分并记录，见 database::change_score
+    pub async fn change_score(
+        &self,
+        group_id: i64,
+        user_id: i64,
+        operator_id: i64,
+        score_changes: i64,
+        reason: &str,
+        check_balance: bool,
+    ) -> ResultType<i64> {
+        let db = self.database.as_ref().unwrap().lock().await;
+        database::change_score(
+            &db,
+            group_id,
+            user_id,
+            operator_id,
+            score_changes,
+            reason,
+            check_balance,
+        )
+    }
+    //返回某群积分变动记录的总数，以及按时间倒序的一页记录
+    pub async fn get_score_logs(
+        &self,
+        group_id: i64,
+        offset: i64,
+        limit: i64,
+    ) -> ResultType<(i64, Vec<ScoreLog>)> {
+        let db = self.database.as_ref().unwrap().lock().await;
+        let total = db.query_row(
+            "SELECT COUNT(*) FROM SCORE_LOGS WHERE GROUP_ID = ?",
+            params![group_id],
+            |r| r.get(0),
+        )?;
+        let mut stmt = db.prepare(
+            "SELECT USER_ID, OPERATOR_ID, TIME, SCORE_CHANGES, SCORE, REASON FROM SCORE_LOGS \
+             WHERE GROUP_ID = ? ORDER BY TIME DESC, ROWID DESC LIMIT ? OFFSET ?",
+        )?;
+        let logs = stmt
+            .query(params![group_id, limit, offset])?
+            .map(|r| {
+                Ok(ScoreLog {
+                    user_id: r.get(0)?,
+                    operator_id: r.get(1)?,
+                    time: r.get(2)?,
+                    score_changes: r.get(3)?,
+                    score: r.get(4)?,
+                    reason: r.get(5)?,
+                })
+            })
+            .collect::<Vec<ScoreLog>>()?;
+        return Ok((total, logs));
+    }
+    pub async fn get_item_count(&self, group_id: i64, user_id: i64, item: &str) -> ResultType<i64> {
+        let db = self.database.as_ref().unwrap().lock().await;
+        database::get_item_count(&db, group_id, user_id, item)
+    }
+    //返回某群排行榜的总人数，以及从 offset 开始的 limit 项
+    pub async fn get_ranklist(
+        &self,
+        group_id: i64,
+        kind: RanklistKind,
+        offset: i64,
+        limit: i64,
+    ) -> ResultType<(i64, Vec<RanklistItem>)> {
+        let today = chrono::Local::now().naive_local().date();
+        let db = self.database.as_ref().unwrap().lock().await;
+        database::get_ranklist(&db, group_id, kind, today, offset, limit)
     }
 }
@@ -20,6 +20,13 @@ impl SignInData {
     }
 }
 #[derive(Debug)]
+pub struct SignInResult {
+    pub data: SignInData,
+    // 连续签到加成
+    pub duration_add: i64,
+    pub used_cards: i64,
+}
+#[derive(Debug)]
 pub struct UserData {
     pub group_id: i64,
     pub user_id: i64,
@@ -43,3 +50,50 @@ pub struct UserData {
 //     pub month_times: i64,
 //     pub total_times: i64,
 // }
+
+#[derive(Debug)]
+pub struct RanklistItem {
+    pub user_id: i64,
+    // 今日排行为签到时间，其余为次数、积分或天数
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RanklistKind {
+    // 今日签到先后
+    Today,
+    // 本月签到次数
+    Month,
+    Score,
+    // 最长连续签到天数
+    Streak,
+}
+impl RanklistKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "今日" | "今天" | "today" => Some(Self::Today),
+            "本月" | "month" => Some(Self::Month),
+            "积分" | "score" => Some(Self::Score),
+            "连续" | "streak" => Some(Self::Streak),
+            _ => None,
+        }
+    }
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Today => "今日签到排行",
+            Self::Month => "本月签到次数排行",
+            Self::Score => "积分排行",
+            Self::Streak => "最长连续签到排行",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScoreLog {
+    pub user_id: i64,
+    pub operator_id: i64,
+    pub time: i64,
+    pub score_changes: i64,
+    pub score: i64,
+    pub reason: String,
+}
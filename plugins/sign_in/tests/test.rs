@@ -0,0 +1,112 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::Connection;
+use sign_in::{
+    database::{self, REPAIR_CARD},
+    models::{RanklistItem, RanklistKind, SignInData},
+};
+
+fn open_database() -> Connection {
+    let db = Connection::open_in_memory().unwrap();
+    database::create_tables(&db).unwrap();
+    db
+}
+
+fn sign_in_data(user_id: i64, time: NaiveDateTime, duration: i64) -> SignInData {
+    let mut data = SignInData::new(1, user_id);
+    data.time = time.timestamp();
+    data.duration = duration;
+    data
+}
+
+#[test]
+fn test_repair_card_streak() {
+    let mut db = open_database();
+    let day = |d| NaiveDate::from_ymd(2022, 1, d).and_hms(12, 0, 0);
+    database::change_item_count(&db, 1, 2, REPAIR_CARD, 3).unwrap();
+    // 断签两天，使用两张补签卡
+    let result = database::sign_in(&mut db, &sign_in_data(2, day(10), 5), day(13), 3).unwrap();
+    assert_eq!((result.data.duration, result.used_cards), (6, 2));
+    assert_eq!(database::get_item_count(&db, 1, 2, REPAIR_CARD).unwrap(), 1);
+    // 补签卡不足时连续签到中断，不消耗补签卡
+    let result = database::sign_in(&mut db, &sign_in_data(2, day(13), 6), day(16), 3).unwrap();
+    assert_eq!((result.data.duration, result.used_cards), (1, 0));
+    assert_eq!(database::get_item_count(&db, 1, 2, REPAIR_CARD).unwrap(), 1);
+    // 断签天数超过上限时不使用补签卡
+    database::change_item_count(&db, 1, 2, REPAIR_CARD, 10).unwrap();
+    let result = database::sign_in(&mut db, &sign_in_data(2, day(16), 1), day(21), 3).unwrap();
+    assert_eq!((result.data.duration, result.used_cards), (1, 0));
+    assert_eq!(
+        database::get_item_count(&db, 1, 2, REPAIR_CARD).unwrap(),
+        11
+    );
+    let result = database::sign_in(&mut db, &sign_in_data(2, day(21), 1), day(22), 3).unwrap();
+    assert_eq!((result.data.duration, result.used_cards), (2, 0));
+    assert_eq!(database::get_score(&db, 1, 2).unwrap(), 15 + 10 + 10 + 11);
+}
+
+#[test]
+fn test_buy_item_rollback() {
+    let mut db = open_database();
+    database::change_score(&db, 1, 2, 2, 150, "测试", false).unwrap();
+    assert_eq!(
+        database::buy_item(&mut db, 1, 2, REPAIR_CARD, 1, 100, "购买").unwrap(),
+        (50, 1)
+    );
+    // 积分不足时不应获得物品
+    assert!(database::buy_item(&mut db, 1, 2, REPAIR_CARD, 1, 100, "购买").is_err());
+    assert_eq!(database::get_score(&db, 1, 2).unwrap(), 50);
+    assert_eq!(database::get_item_count(&db, 1, 2, REPAIR_CARD).unwrap(), 1);
+}
+
+#[test]
+fn test_ranklist_pagination() {
+    let db = open_database();
+    for user_id in 1..=5 {
+        database::change_score(&db, 1, user_id, user_id, user_id * 10, "测试", false).unwrap();
+    }
+    // 其他群的数据不计入
+    database::change_score(&db, 2, 6, 6, 100, "测试", false).unwrap();
+    let today = NaiveDate::from_ymd(2022, 1, 10);
+    let values = |items: &[RanklistItem]| {
+        items
+            .iter()
+            .map(|v| (v.user_id, v.value))
+            .collect::<Vec<_>>()
+    };
+    let (total, items) = database::get_ranklist(&db, 1, RanklistKind::Score, today, 2, 2).unwrap();
+    assert_eq!(total, 5);
+    assert_eq!(values(&items), vec![(3, 30), (2, 20)]);
+    let (_, items) = database::get_ranklist(&db, 1, RanklistKind::Score, today, 4, 2).unwrap();
+    assert_eq!(values(&items), vec![(1, 10)]);
+    // 今日排行只统计当天的签到，按签到时间排序
+    for (user_id, time) in [
+        (1, today.and_hms(8, 0, 0)),
+        (2, today.pred().and_hms(7, 0, 0)),
+        (3, today.and_hms(7, 0, 0)),
+    ] {
+        database::save_data(&db, &sign_in_data(user_id, time, 1)).unwrap();
+    }
+    let (total, items) = database::get_ranklist(&db, 1, RanklistKind::Today, today, 0, 10).unwrap();
+    assert_eq!(total, 2);
+    assert_eq!(
+        items.iter().map(|v| v.user_id).collect::<Vec<_>>(),
+        vec![3, 1]
+    );
+}
+
+#[test]
+fn test_streak_not_capped() {
+    let mut db = open_database();
+    let start = NaiveDate::from_ymd(2022, 1, 1);
+    let mut last = sign_in_data(1, start.and_hms(12, 0, 0), 1);
+    database::save_data(&db, &last).unwrap();
+    for day in 1..40 {
+        let now = (start + chrono::Duration::days(day)).and_hms(12, 0, 0);
+        let result = database::sign_in(&mut db, &last, now, 3).unwrap();
+        assert_eq!(result.duration_add, day.min(10));
+        last = result.data;
+    }
+    assert_eq!(last.duration, 40);
+    let (_, items) = database::get_ranklist(&db, 1, RanklistKind::Streak, start, 0, 10).unwrap();
+    assert_eq!(items[0].value, 40);
+}